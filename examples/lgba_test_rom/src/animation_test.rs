@@ -0,0 +1,63 @@
+use crate::game_data_test::RomData;
+use lgba::{
+    display::{
+        animation::{Animation, AnimationPlayer, AnimationStorage},
+        objects::ObjectTable,
+        palette,
+        palette::PaletteKind,
+        Color, Mode0,
+    },
+    input::InputState,
+    sys::Button,
+};
+use lgba_data::StrHash;
+
+pub fn run() -> ! {
+    let mut mode = Mode0::new();
+    mode.set_objects_enabled(true);
+    let _mode = mode.activate();
+
+    let mut bank = palette::alloc_bank(PaletteKind::Object);
+    bank.set_colors(1, &[Color::YELLOW, Color::from_rgb24(80, 120, 255)]);
+    palette::commit();
+
+    let orbit = RomData.anims(StrHash!("orbit"));
+    let orbit = Animation::from_bytes(orbit.frames().as_slice(), orbit.tiles().as_slice());
+    let pulse = RomData.anims(StrHash!("pulse"));
+    let pulse = Animation::from_bytes(pulse.frames().as_slice(), pulse.tiles().as_slice());
+
+    let table = ObjectTable::new();
+    let chars = table.char_access();
+
+    // one copy of each animation streams its frames, and the other keeps them all in VRAM
+    let mut players = [
+        AnimationPlayer::new(orbit, chars, 0, AnimationStorage::Streamed),
+        AnimationPlayer::new(orbit, chars, 4, AnimationStorage::Resident),
+        AnimationPlayer::new(pulse, chars, 36, AnimationStorage::Streamed),
+        AnimationPlayer::new(pulse, chars, 37, AnimationStorage::Resident),
+    ];
+    let mut objects = [table.alloc(), table.alloc(), table.alloc(), table.alloc()];
+    for (i, object) in objects.iter_mut().enumerate() {
+        object
+            .set_position(40 + (i as i16 % 2) * 40, 40 + (i as i16 / 2) * 40)
+            .set_palette(bank.id() as u8)
+            .set_visible(true);
+    }
+
+    let mut input = InputState::new();
+    loop {
+        lgba::sys::wait_for_vblank();
+
+        // A restarts the animations
+        input.update();
+        let restart = input.is_just_pressed(Button::A);
+
+        for (player, object) in players.iter_mut().zip(&mut objects) {
+            if restart {
+                player.restart();
+            }
+            player.update(object);
+        }
+        table.commit();
+    }
+}
@@ -0,0 +1,75 @@
+use lgba::{
+    display::{
+        double_buffer::DoubleBufferedMap, palette, palette::PaletteKind, vram_alloc, Color,
+        MapAccess, Mode0, VramTile,
+    },
+    dma::DmaChannelId,
+    input::InputState,
+    sys::Button,
+};
+
+/// A solid character.
+static SOLID_CHAR: [u32; 8] = [0x11111111; 8];
+
+/// A diagonal stripe character.
+static STRIPE_CHAR: [u32; 8] = [
+    0x22000000, 0x02200000, 0x00220000, 0x00022000, 0x00002200, 0x00000220, 0x00000022, 0x20000002,
+];
+
+/// Slowly draws a checkerboard pattern, so partly drawn maps would be visible.
+fn draw_room(map: MapAccess, room: usize, solid: VramTile, stripe: VramTile) {
+    let cell = 1 + room % 4;
+    for y in 0..32 {
+        let mut row = [solid; 32];
+        for (x, tile) in row.iter_mut().enumerate() {
+            if (x / cell + y / cell + room).is_multiple_of(2) {
+                *tile = stripe;
+            }
+        }
+        map.set_tiles(0, y, &row);
+        if y % 4 == 3 {
+            lgba::sys::wait_for_vblank();
+        }
+    }
+}
+
+pub fn run() -> ! {
+    let mut mode = Mode0::new();
+
+    let mut bank = palette::alloc_bank(PaletteKind::Background);
+    bank.set_colors(1, &[Color::from_rgb24(40, 60, 140), Color::from_rgb24(240, 200, 80)]);
+    palette::commit();
+
+    let chars = vram_alloc::alloc_chars(2);
+    let char_access = chars.char_access();
+    char_access.write_char_4bpp(chars.first_char(), &SOLID_CHAR[..]);
+    char_access.write_char_4bpp(chars.first_char() + 1, &STRIPE_CHAR[..]);
+    let solid = VramTile::default()
+        .with_char(chars.first_char() as u16)
+        .with_palette(bank.id() as u8);
+    let stripe = solid.with_char(chars.first_char() as u16 + 1);
+
+    mode.layers[0].set_enabled(true).set_char_range(chars);
+    let mut mode = mode.activate();
+    let mut map = DoubleBufferedMap::new(&mut mode.layers[0], DmaChannelId::Dma3.create());
+
+    // press A to change rooms, and hold B to draw directly into the displayed map instead
+    let mut room = 0;
+    draw_room(map.map_access(0), room, solid, stripe);
+    map.present();
+
+    let mut input = InputState::new();
+    loop {
+        lgba::sys::wait_for_vblank();
+        input.update();
+        if input.is_just_pressed(Button::A) {
+            room += 1;
+            if input.is_held(Button::B) {
+                draw_room(map.front_map_access(0), room, solid, stripe);
+            } else {
+                draw_room(map.map_access(0), room, solid, stripe);
+                map.present();
+            }
+        }
+    }
+}
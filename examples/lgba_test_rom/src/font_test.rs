@@ -0,0 +1,46 @@
+use lgba::{
+    display::{Color, Terminal},
+    dma::DmaChannelId,
+};
+
+lgba::display::terminal_font! {
+    /// A font for testing custom glyphs alongside the built-in ones.
+    TestFont {
+        sources: ["fonts/test_glyphs.hex"],
+        blocks: ["Basic Latin", "Arrows", "Geometric Shapes"],
+        chars: "♥☺",
+    }
+}
+
+pub fn run() -> ! {
+    let mut terminal = Terminal::new();
+    terminal.use_dma_channel(DmaChannelId::Dma3);
+    terminal.set_force_blank(true);
+    let terminal = terminal.activate::<TestFont>();
+    let mut terminal = terminal.lock();
+
+    terminal.set_color(0, Color::from_rgb24(54, 131, 255), Color::WHITE);
+    terminal.set_color(1, Color::BLACK, Color::from_rgb24(255, 194, 211));
+
+    terminal.write_str("Custom font test");
+    terminal.new_line();
+    terminal.new_line();
+
+    terminal.write_str("Custom glyphs: ♥ ☺");
+    terminal.new_line();
+
+    terminal.set_half_width(true);
+    terminal.write_str("Half-width custom glyphs: ♥ ☺");
+    terminal.new_line();
+
+    terminal.set_active_color(1);
+    terminal.write_str("Arrows and shapes from the built-in blocks: ← ↑ → ↓ ○ ●");
+    terminal.new_line();
+
+    lgba::sys::wait_for_vblank();
+    terminal.set_force_blank(false);
+
+    loop {
+        lgba::sys::wait_for_vblank();
+    }
+}
@@ -4,7 +4,7 @@ use lgba::{
 };
 use lgba_data::StrHash;
 
-lgba_data::load_data!(RomData, "RomData.toml");
+lgba_data::load_data!(pub RomData, "RomData.toml");
 
 pub fn run() -> ! {
     let mut terminal = Terminal::new();
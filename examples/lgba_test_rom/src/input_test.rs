@@ -0,0 +1,101 @@
+use alloc::vec;
+use lgba::{
+    display::{widgets::ConfirmDialog, ActiveTerminalAccess, Terminal, TerminalFontBasic},
+    dma::DmaChannelId,
+    input,
+    input::{InputRecording, InputState},
+    save::SaveAccess,
+    sys::Button,
+};
+
+const RECORDING_SIZE: usize = 4096;
+
+/// Shows the buttons held, and how long the longest held button has been held for.
+fn draw_input(
+    terminal: &mut ActiveTerminalAccess<TerminalFontBasic>,
+    y: usize,
+    state: &InputState,
+) {
+    terminal.clear_line(y);
+    terminal.set_cursor(0, y);
+    let longest = state
+        .keys()
+        .iter()
+        .map(|button| state.held_frames(button))
+        .max()
+        .unwrap_or(0);
+    write!(terminal.write(), "Held {longest:4} frames:");
+    for button in state.keys() {
+        write!(terminal.write(), " {button:?}");
+    }
+}
+
+/// Plays a recording back, showing the input the test sees.
+fn replay(terminal: &mut ActiveTerminalAccess<TerminalFontBasic>, recording: InputRecording) {
+    terminal.clear_line(6);
+    terminal.set_cursor(0, 6);
+    write!(terminal.write(), "Replaying {} frames...", recording.frame_count());
+
+    input::start_replay(recording);
+    let mut state = InputState::new();
+    while input::is_replaying() {
+        lgba::sys::wait_for_vblank();
+        state.update();
+        draw_input(terminal, 8, &state);
+    }
+    terminal.clear_line(6);
+    terminal.set_cursor(0, 6);
+    terminal.write_str("Replay finished.");
+}
+
+pub fn run() -> ! {
+    let mut terminal = Terminal::new();
+    terminal.use_dma_channel(DmaChannelId::Dma3);
+    let active_terminal = terminal.activate::<TerminalFontBasic>();
+    let mut terminal = active_terminal.lock();
+    terminal.set_half_width(true);
+
+    terminal.write_str("Input recording test\n\n");
+    terminal.write_str("Press buttons to record them, and press Start to stop.\n");
+
+    // record until start is pressed
+    let buffer = vec![0; RECORDING_SIZE].leak();
+    input::start_recording(buffer, 1234);
+    let mut state = InputState::new();
+    while !state.is_just_pressed(Button::Start) && !input::is_recording_full() {
+        lgba::sys::wait_for_vblank();
+        state.update();
+        draw_input(&mut terminal, 4, &state);
+    }
+    let recording = input::stop_recording().unwrap();
+    replay(&mut terminal, recording);
+
+    // store the recording in save media, and replay the copy read back from it
+    let save = ConfirmDialog::new("Write the recording to save media?")
+        .set_position(0, 10)
+        .set_width(54)
+        .run(&mut terminal);
+    terminal.clear_line(12);
+    terminal.set_cursor(0, 12);
+    if save {
+        lgba::save::init_flash_128k();
+        let mut access = SaveAccess::open().unwrap();
+        recording.write_to_save(&mut access, 0).unwrap();
+
+        let buffer = vec![0; RECORDING_SIZE].leak();
+        let loaded = InputRecording::read_from_save(&mut access, 0, buffer).unwrap();
+        match loaded {
+            Some(loaded) if loaded.as_bytes() == recording.as_bytes() => {
+                terminal.write_str("Recording saved at offset 0.");
+                replay(&mut terminal, loaded);
+            }
+            _ => terminal.write_str("Recording could not be read back!"),
+        }
+    } else {
+        terminal.write_str("Recording discarded.");
+    }
+
+    loop {
+        lgba::sys::wait_for_vblank();
+    }
+}
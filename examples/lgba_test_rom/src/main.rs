@@ -5,23 +5,40 @@
 extern crate lgba;
 extern crate alloc;
 
+mod animation_test;
+mod double_buffer_test;
+mod font_test;
 mod game_data_test;
+mod input_test;
 mod interrupt_test;
+mod raster_test;
 mod savegame_test;
 mod terminal_test;
+mod vwf_test;
+mod widget_test;
+mod window_test;
 
+use alloc::vec::Vec;
 use core::pin::pin;
 use lgba::{
-    display::{Terminal, TerminalFontBasic},
+    display::{widgets::Menu, Terminal, TerminalFontBasic},
     dma::DmaChannelId,
     irq::{Interrupt, InterruptHandler},
     sys::Button,
 };
 
-static OPTIONS: &[(&'static str, fn() -> !)] = &[
+static OPTIONS: &[(&str, fn() -> !)] = &[
     ("Test terminal function", || terminal_test::run()),
+    ("Test custom terminal fonts", || font_test::run()),
+    ("Test terminal windows", || window_test::run()),
+    ("Test variable-width text", || vwf_test::run()),
+    ("Test terminal widgets", || widget_test::run()),
+    ("Test sprite animations", || animation_test::run()),
+    ("Test input recording", || input_test::run()),
     ("Test savegame function", || savegame_test::run()),
     ("Test interrupt handlers", || interrupt_test::run()),
+    ("Test raster scheduler", || raster_test::run()),
+    ("Test double-buffered maps", || double_buffer_test::run()),
     ("Test game data", || game_data_test::run()),
     ("Test panic handler", || {
         panic!("oh no something really bad happened!!! help!!!")
@@ -53,11 +70,6 @@ fn rom_entry() -> ! {
     terminal.set_cursor(0, 18);
     terminal.write_str("Press ↓○A to reset any test");
 
-    for (i, (name, _)) in OPTIONS.iter().enumerate() {
-        terminal.set_cursor(3, i + 3);
-        terminal.write_str(name);
-    }
-
     lgba::sys::wait_for_vblank();
     terminal.set_force_blank(false);
 
@@ -66,34 +78,15 @@ fn rom_entry() -> ! {
     handler.register(Interrupt::Keypad);
     lgba::irq::enable(Interrupt::Keypad);
 
-    let mut cursor_pos = 0;
-    let mut last_held = lgba::sys::pressed_keys();
-    loop {
-        lgba::sys::wait_for_vblank();
-
-        let held = lgba::sys::pressed_keys();
-        let pressed = held - last_held;
-        last_held = held;
-
-        terminal.set_char_full(0, cursor_pos + 3, ' ', 0);
-        if pressed.contains(Button::Up) {
-            if cursor_pos == 0 {
-                cursor_pos = OPTIONS.len() - 1;
-            } else {
-                cursor_pos -= 1;
-            }
-        } else if pressed.contains(Button::Down) {
-            cursor_pos += 1;
-            if cursor_pos == OPTIONS.len() {
-                cursor_pos = 0;
-            }
-        }
-        terminal.set_char_full(0, cursor_pos + 3, '>', 0);
-
-        if pressed.contains(Button::A) {
-            drop(terminal);
-            drop(active_terminal);
-            OPTIONS[cursor_pos].1();
-        }
-    }
+    let names: Vec<&str> = OPTIONS.iter().map(|(name, _)| *name).collect();
+    let choice = Menu::new(&names)
+        .set_position(1, 3)
+        .set_size(54, 14)
+        .set_cancellable(false)
+        .run(&mut terminal)
+        .unwrap();
+
+    drop(terminal);
+    drop(active_terminal);
+    OPTIONS[choice].1()
 }
@@ -0,0 +1,53 @@
+use core::pin::pin;
+use lgba::display::{
+    palette,
+    palette::PaletteKind,
+    raster::{RasterAction, RasterScheduler},
+    vram_alloc, Color, Mode0, VramTile,
+};
+
+/// A diagonal stripe character for the playfield.
+static STRIPE_CHAR: [u32; 8] = [
+    0x11000000, 0x01100000, 0x00110000, 0x00011000, 0x00001100, 0x00000110, 0x00000011, 0x10000001,
+];
+
+pub fn run() -> ! {
+    let mut mode = Mode0::new();
+
+    let mut bank = palette::alloc_bank(PaletteKind::Background);
+    bank.set_colors(1, &[Color::from_rgb24(240, 200, 80)]);
+    palette::commit();
+
+    let chars = vram_alloc::alloc_chars(1);
+    chars
+        .char_access()
+        .write_char_4bpp(chars.first_char(), &STRIPE_CHAR[..]);
+    let blocks = vram_alloc::alloc_screen_blocks(1);
+    let tile = VramTile::default()
+        .with_char(chars.first_char() as u16)
+        .with_palette(bank.id() as u8);
+    for y in 0..32 {
+        blocks.map_access(0).set_tiles(0, y, &[tile; 32]);
+    }
+    mode.layers[0]
+        .set_enabled(true)
+        .set_char_range(chars)
+        .set_screen_blocks(blocks);
+    let _mode = mode.activate();
+
+    // the playfield scrolls, while the status bar at the bottom stays still
+    let mut raster = pin!(RasterScheduler::new());
+    raster.as_mut().start();
+
+    let mut scroll = 0i16;
+    loop {
+        raster.set_schedule(&[
+            (0, RasterAction::SetLayerOffset { layer: 0, x: scroll, y: scroll / 2 }),
+            (0, RasterAction::SetBackdrop(Color::from_rgb24(20, 40, 100))),
+            (128, RasterAction::SetLayerOffset { layer: 0, x: 0, y: 0 }),
+            (128, RasterAction::SetBackdrop(Color::from_rgb24(60, 20, 60))),
+        ]);
+        scroll = scroll.wrapping_add(1);
+        lgba::sys::wait_for_vblank();
+    }
+}
@@ -1,20 +1,24 @@
 use lgba::{
-    display::{Terminal, TerminalFontBasic},
+    display::{Color, Terminal, TerminalFontBasic, TerminalScrollback},
     dma::DmaChannelId,
     sys::Button,
 };
 
+#[lgba::ewram]
+static SCROLLBACK: TerminalScrollback<64> = TerminalScrollback::new();
+
 pub fn run() -> ! {
     let mut terminal = Terminal::new();
     terminal.use_dma_channel(DmaChannelId::Dma3);
+    terminal.set_scrollback(&SCROLLBACK);
     terminal.set_force_blank(true);
     let terminal = terminal.activate::<TerminalFontBasic>();
     let mut terminal = terminal.lock();
 
     let cycles = lgba::timer::time_cycles(|| {
-        terminal.set_color(0, lgba::display::rgb_24bpp(54, 131, 255), !0);
-        terminal.set_color(1, 0, lgba::display::rgb_24bpp(255, 194, 211));
-        terminal.set_color(2, !0, 0);
+        terminal.set_color(0, Color::from_rgb24(54, 131, 255), Color::WHITE);
+        terminal.set_color(1, Color::BLACK, Color::from_rgb24(255, 194, 211));
+        terminal.set_color(2, Color::WHITE, Color::BLACK);
 
         terminal.write_str("Hello, world!");
         terminal.new_line();
@@ -35,6 +39,8 @@ pub fn run() -> ! {
         terminal.set_half_width(true);
         terminal.write_str("Half-width reverse text! Half-width reverse text!");
         terminal.new_line();
+        terminal.write_str("Escape codes: \x1b[31mpink\x1b[0m, \x1b[32mreverse\x1b[0m, normal");
+        terminal.new_line();
 
         terminal.new_line();
         terminal.set_active_color(0);
@@ -75,6 +81,9 @@ pub fn run() -> ! {
         terminal.set_half_width(false);
 
         let keys = lgba::sys::pressed_keys();
+        if keys.contains(Button::Select) && keys.contains(Button::Start) {
+            terminal.view_scrollback();
+        }
         write!(terminal.write(), "#{frame:03} / ");
         if keys.is_empty() {
             terminal.set_half_width(true);
@@ -0,0 +1,92 @@
+use lgba::{
+    display::{
+        palette,
+        palette::PaletteKind,
+        vram_alloc,
+        vwf::{Typewriter, VwfRenderer},
+        Color, Mode0, TerminalFontBasic, VramTile,
+    },
+    input::InputState,
+    sys::Button,
+};
+
+static KERNING: &[(char, char, i8)] = &[('A', 'V', -1), ('V', 'A', -1), ('T', 'o', -1)];
+
+static TEXT: &str = "Variable-width text is drawn into a strip of characters using the actual \
+    width of each glyph. Words are wrapped onto the next line when they do not fit, and the text \
+    is revealed one character at a time.\n\nPress A to show the rest of a page, or to continue \
+    once it is full, or B to show text split into pages. AVAVA To";
+
+static PAGED_TEXT: &str = "Each page is filled at once, and the next page continues from the \
+    first character that did not fit, so no letters are lost at a page break: Alpha Bravo \
+    Charlie Delta Echo Foxtrot Golf Hotel India Juliett Kilo Lima Mike November Oscar Papa \
+    Quebec Romeo Sierra Tango Uniform Victor Whiskey Xray Yankee Zulu.";
+
+pub fn run() -> ! {
+    let mut mode = Mode0::new();
+
+    let mut bank = palette::alloc_bank(PaletteKind::Background);
+    bank.set_colors(0, &[
+        Color::from_rgb24(20, 20, 60),
+        Color::WHITE,
+        Color::from_rgb24(40, 40, 120),
+    ]);
+    palette::commit();
+
+    let mut text = VwfRenderer::<TerminalFontBasic, 28>::new(4);
+    text.set_colors(1, 2).set_kerning(KERNING);
+    text.clear();
+
+    // fill the rest of the layer with a blank character
+    let blank_char = vram_alloc::alloc_chars_at_base(text.chars().char_base(), 1);
+    blank_char
+        .char_access()
+        .write_char_4bpp(blank_char.first_char(), &[0u32; 8][..]);
+    let blocks = vram_alloc::alloc_screen_blocks(1);
+    let blank = VramTile::default().with_char(blank_char.first_char() as u16);
+    for y in 0..32 {
+        blocks.map_access(0).set_tiles(0, y, &[blank; 32]);
+    }
+    text.map_tiles(blocks.map_access(0), 1, 1, bank.id() as u8);
+    mode.layers[0]
+        .set_enabled(true)
+        .set_char_base(text.chars().char_base())
+        .set_screen_blocks(blocks);
+    let _mode = mode.activate();
+
+    let mut typewriter = Typewriter::new(TEXT, 2);
+    // the position of the next page of the paged text, if it is shown
+    let mut paged = None;
+    let mut input = InputState::new();
+    loop {
+        lgba::sys::wait_for_vblank();
+
+        input.update();
+        if input.is_just_pressed(Button::B) {
+            text.clear();
+            paged = Some(text.write_str(PAGED_TEXT));
+        } else if let Some(pos) = paged {
+            if input.is_just_pressed(Button::A) {
+                text.clear();
+                if pos == PAGED_TEXT.len() {
+                    paged = None;
+                    typewriter = Typewriter::new(TEXT, 2);
+                } else {
+                    paged = Some(pos + text.write_str(&PAGED_TEXT[pos..]));
+                }
+            }
+            continue;
+        } else if input.is_just_pressed(Button::A) {
+            if text.is_full() {
+                text.clear();
+            } else if typewriter.is_done() {
+                text.clear();
+                typewriter = Typewriter::new(TEXT, 2);
+            } else {
+                typewriter.finish(&mut text);
+            }
+        }
+
+        typewriter.update(&mut text);
+    }
+}
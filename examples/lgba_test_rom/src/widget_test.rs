@@ -0,0 +1,72 @@
+use lgba::display::{
+    widgets::{ConfirmDialog, Menu, NumberSpinner, ProgressBar},
+    Color, Terminal, TerminalFontBasic,
+};
+
+static FRUITS: &[&str] = &[
+    "Apple",
+    "Banana",
+    "Cherry",
+    "Durian",
+    "Elderberry",
+    "Fig",
+    "Grape",
+    "Honeydew",
+    "Kiwi",
+    "Lemon",
+    "Mango",
+    "Nectarine",
+];
+
+pub fn run() -> ! {
+    let mut terminal = Terminal::new();
+    terminal.set_color(1, Color::BLACK, Color::YELLOW);
+    let active_terminal = terminal.activate::<TerminalFontBasic>();
+    let mut terminal = active_terminal.lock();
+
+    loop {
+        terminal.clear();
+        terminal.write_str("Terminal widget test\n");
+
+        let fruit = Menu::new(FRUITS)
+            .set_position(0, 2)
+            .set_size(30, 5)
+            .set_highlight_color(1)
+            .run(&mut terminal);
+        terminal.set_cursor(0, 8);
+        match fruit {
+            Some(fruit) => write!(terminal.write(), "Selected: {}", FRUITS[fruit]),
+            None => terminal.write_str("Menu cancelled."),
+        }
+
+        let count = NumberSpinner::new("Steps:", 1, 100)
+            .set_position(0, 10)
+            .set_width(30)
+            .set_value(20)
+            .run(&mut terminal)
+            .unwrap_or(20);
+
+        let mut progress = ProgressBar::new();
+        progress.set_position(0, 12).set_width(40);
+        for i in 0..=count {
+            progress
+                .set_progress(i as u32, count as u32)
+                .draw(&mut terminal);
+            for _ in 0..4 {
+                lgba::sys::wait_for_vblank();
+            }
+        }
+
+        let again = ConfirmDialog::new("Run the test again?")
+            .set_position(0, 14)
+            .set_width(40)
+            .run(&mut terminal);
+        if !again {
+            terminal.clear();
+            terminal.write_str("Done. Press ↓○A to reset.");
+            loop {
+                lgba::sys::wait_for_vblank();
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use lgba::{
+    display::{
+        palette, palette::PaletteKind, vram_alloc, Color, Mode0, TerminalFontBasic,
+        TerminalWindow, VramTile,
+    },
+    input::InputState,
+    sys::Button,
+};
+
+/// A checkerboard character, used to show the window coexisting with other graphics.
+static CHECKER_CHAR: [u32; 8] = [
+    0x11112222, 0x11112222, 0x11112222, 0x11112222, 0x22221111, 0x22221111, 0x22221111, 0x22221111,
+];
+
+pub fn run() -> ! {
+    let mut mode = Mode0::new();
+
+    // set up a background layer for the rest of the screen
+    let chars = vram_alloc::alloc_chars(1);
+    let blocks = vram_alloc::alloc_screen_blocks(1);
+    let mut bank = palette::alloc_bank(PaletteKind::Background);
+    bank.set_colors(1, &[Color::from_rgb24(40, 80, 160), Color::from_rgb24(60, 110, 200)]);
+    palette::commit();
+    chars
+        .char_access()
+        .write_char_4bpp(chars.first_char(), &CHECKER_CHAR[..]);
+    let tile = VramTile::default()
+        .with_char(chars.first_char() as u16)
+        .with_palette(bank.id() as u8);
+    for y in 0..32 {
+        blocks.map_access(0).set_tiles(0, y, &[tile; 32]);
+    }
+    mode.layers[3]
+        .set_enabled(true)
+        .set_char_range(chars)
+        .set_screen_blocks(blocks);
+
+    let mut mode = mode.activate();
+    let [text, background, _, _] = &mut mode.layers;
+    let mut window = TerminalWindow::<TerminalFontBasic>::new(text, background, 1, 12, 28, 7);
+    window.set_color(1, Color::BLACK, Color::YELLOW);
+
+    window.write_str("Terminal window test\n");
+    window.write_str(
+        "This window only uses two layers, so the rest of the screen is free for other \
+        graphics. Long lines are wrapped between words, and \x1b[31mcolors\x1b[0m work too.\n",
+    );
+
+    let mut count = 0;
+    let mut input = InputState::new();
+    loop {
+        lgba::sys::wait_for_vblank();
+
+        input.update();
+        if input.is_just_pressed(Button::A) {
+            count += 1;
+            write!(window.write(), "A was pressed {count} time(s). ");
+        }
+    }
+}
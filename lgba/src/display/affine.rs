@@ -0,0 +1,83 @@
+use core::ops::Mul;
+
+#[doc(inline)]
+pub use crate::mmio::display::{GbaFrac16, GbaFrac32};
+
+/// A matrix used to rotate and scale objects and affine background layers.
+///
+/// The matrix maps screen coordinates to texture coordinates, rather than the other way around.
+/// This means that, for example, a matrix that scales by `2.0` halves the displayed size of an
+/// object.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct AffineMatrix {
+    /// The change in the texture X coordinate for every pixel moved right on screen.
+    pub pa: GbaFrac16,
+    /// The change in the texture X coordinate for every pixel moved down on screen.
+    pub pb: GbaFrac16,
+    /// The change in the texture Y coordinate for every pixel moved right on screen.
+    pub pc: GbaFrac16,
+    /// The change in the texture Y coordinate for every pixel moved down on screen.
+    pub pd: GbaFrac16,
+}
+impl AffineMatrix {
+    /// The identity matrix, which leaves the image unchanged.
+    pub const IDENTITY: AffineMatrix =
+        AffineMatrix::new(GbaFrac16::ONE, GbaFrac16::ZERO, GbaFrac16::ZERO, GbaFrac16::ONE);
+
+    /// Creates a new matrix from its parameters.
+    pub const fn new(pa: GbaFrac16, pb: GbaFrac16, pc: GbaFrac16, pd: GbaFrac16) -> Self {
+        AffineMatrix { pa, pb, pc, pd }
+    }
+
+    /// Creates a matrix that scales the texture.
+    ///
+    /// The scale is given in texture pixels per screen pixel, so larger values shrink the image.
+    pub const fn scale(scale_x: GbaFrac16, scale_y: GbaFrac16) -> Self {
+        AffineMatrix::new(scale_x, GbaFrac16::ZERO, GbaFrac16::ZERO, scale_y)
+    }
+
+    /// Creates a matrix that rotates the texture counter-clockwise.
+    ///
+    /// The angle is in units of 1/65536th of a full rotation, and only the upper 8 bits are used.
+    pub fn rotation(angle: u16) -> Self {
+        Self::rotation_scale(angle, GbaFrac16::ONE, GbaFrac16::ONE)
+    }
+
+    /// Creates a matrix that rotates the texture counter-clockwise and then scales it.
+    ///
+    /// The angle is in units of 1/65536th of a full rotation, and only the upper 8 bits are used.
+    /// The scale is given in texture pixels per screen pixel, so larger values shrink the image.
+    pub fn rotation_scale(angle: u16, scale_x: GbaFrac16, scale_y: GbaFrac16) -> Self {
+        let [pa, pb, pc, pd] =
+            crate::sys::obj_affine_set(scale_x.to_raw(), scale_y.to_raw(), angle);
+        AffineMatrix::new(
+            GbaFrac16::from_raw(pa),
+            GbaFrac16::from_raw(pb),
+            GbaFrac16::from_raw(pc),
+            GbaFrac16::from_raw(pd),
+        )
+    }
+
+    /// Transforms a screen-space offset into a texture-space offset using this matrix.
+    pub fn transform(&self, x: GbaFrac32, y: GbaFrac32) -> (GbaFrac32, GbaFrac32) {
+        let (pa, pb) = (GbaFrac32::from(self.pa), GbaFrac32::from(self.pb));
+        let (pc, pd) = (GbaFrac32::from(self.pc), GbaFrac32::from(self.pd));
+        (pa * x + pb * y, pc * x + pd * y)
+    }
+}
+impl Default for AffineMatrix {
+    fn default() -> Self {
+        AffineMatrix::IDENTITY
+    }
+}
+impl Mul for AffineMatrix {
+    type Output = AffineMatrix;
+    fn mul(self, rhs: Self) -> Self::Output {
+        AffineMatrix::new(
+            self.pa * rhs.pa + self.pb * rhs.pc,
+            self.pa * rhs.pb + self.pb * rhs.pd,
+            self.pc * rhs.pa + self.pd * rhs.pc,
+            self.pc * rhs.pb + self.pd * rhs.pd,
+        )
+    }
+}
@@ -0,0 +1,361 @@
+//! Module for playing object animations stored in game data.
+//!
+//! An [`Animation`] is a sequence of frames, each showing a range of characters for a number of
+//! frames of the display. An [`AnimationPlayer`] advances through an animation once per VBlank,
+//! and points an [`Object`] at the characters of the current frame. VBlanks are counted by the
+//! VBlank interrupt, so it must be enabled for animations to play.
+//!
+//! # Data format
+//!
+//! Animations are stored as two files, usually two partitions of the same `lgba_data` root:
+//!
+//! * The frame list begins with a 4 byte header: the size of the object as a byte (the index of
+//!   the size in [`ObjectSize`]), the [`AnimationMode`] as a byte (`0` for [`Once`], `1` for
+//!   [`Loop`] and `2` for [`PingPong`]), and the number of frames as a `u16`. It is followed by a
+//!   `u16` character index and a `u16` duration for each frame. Character indexes are counted
+//!   from the start of the character data, and durations are counted in VBlanks.
+//! * The character data contains 4bpp characters, in the order used with 1D object character
+//!   mapping.
+//!
+//! All numbers are little-endian.
+//!
+//! # Example
+//!
+//! ```toml
+//! [[root]]
+//! name = "anims"
+//! partitions = { frames = "anims/{str}.frames", tiles = "anims/{str}.tiles" }
+//! ```
+//!
+//! ```rust,ignore
+//! let entry = RomData.anims(StrHash!("walk"));
+//! let walk = Animation::from_bytes(entry.frames().as_slice(), entry.tiles().as_slice());
+//! let chars = table.char_access();
+//! let mut player = AnimationPlayer::new(walk, chars, 0, AnimationStorage::Streamed);
+//! loop {
+//!     lgba::sys::wait_for_vblank();
+//!     player.update(&mut object);
+//!     table.commit();
+//! }
+//! ```
+//!
+//! [`Once`]: AnimationMode::Once
+//! [`Loop`]: AnimationMode::Loop
+//! [`PingPong`]: AnimationMode::PingPong
+
+use crate::{
+    display::{
+        objects::{Object, ObjectSize},
+        CharAccess,
+    },
+    sync::Static,
+};
+
+const SIZES: [ObjectSize; 12] = [
+    ObjectSize::Size8x8,
+    ObjectSize::Size16x16,
+    ObjectSize::Size32x32,
+    ObjectSize::Size64x64,
+    ObjectSize::Size16x8,
+    ObjectSize::Size32x8,
+    ObjectSize::Size32x16,
+    ObjectSize::Size64x32,
+    ObjectSize::Size8x16,
+    ObjectSize::Size8x32,
+    ObjectSize::Size16x32,
+    ObjectSize::Size32x64,
+];
+
+static VBLANK_COUNT: Static<u32> = Static::new(0);
+
+/// Counts the VBlanks used to advance animation players.
+pub(crate) fn on_vblank() {
+    VBLANK_COUNT.write(VBLANK_COUNT.read().wrapping_add(1));
+}
+
+/// How an animation continues after its last frame.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum AnimationMode {
+    /// The animation stops on its last frame.
+    Once,
+    /// The animation restarts from its first frame.
+    Loop,
+    /// The animation plays backwards to its first frame, then forwards again.
+    PingPong,
+}
+
+/// A single frame of an animation.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct AnimationFrame {
+    /// The index of the first character of the frame in the animation's character data.
+    pub first_char: u16,
+    /// The number of VBlanks the frame is shown for.
+    pub duration: u16,
+}
+
+/// An object animation, usually loaded from game data.
+#[derive(Copy, Clone, Debug)]
+pub struct Animation {
+    size: ObjectSize,
+    mode: AnimationMode,
+    frames: &'static [u8],
+    chars: &'static [u32],
+}
+impl Animation {
+    /// Loads an animation from a frame list and character data in the format described in the
+    /// [module documentation](self).
+    ///
+    /// This function panics if the data is malformed, or if the character data is not aligned
+    /// to 4 bytes.
+    #[track_caller]
+    pub fn from_bytes(frames: &'static [u8], chars: &'static [u8]) -> Self {
+        if frames.len() < 4
+            || !(chars.as_ptr() as usize).is_multiple_of(4)
+            || !chars.len().is_multiple_of(32)
+        {
+            animation_data_invalid();
+        }
+        let size = match SIZES.get(frames[0] as usize) {
+            Some(size) => *size,
+            None => animation_data_invalid(),
+        };
+        let mode = match frames[1] {
+            0 => AnimationMode::Once,
+            1 => AnimationMode::Loop,
+            2 => AnimationMode::PingPong,
+            _ => animation_data_invalid(),
+        };
+        let frame_count = u16::from_le_bytes([frames[2], frames[3]]) as usize;
+        if frame_count == 0 || frames.len() != 4 + frame_count * 4 {
+            animation_data_invalid();
+        }
+
+        let chars =
+            unsafe { core::slice::from_raw_parts(chars.as_ptr() as *const u32, chars.len() / 4) };
+        let animation = Animation { size, mode, frames: &frames[4..], chars };
+        let char_count = animation.char_count();
+        for i in 0..frame_count {
+            if animation.frame(i).first_char as usize + size.char_count() > char_count {
+                animation_data_invalid();
+            }
+        }
+        animation
+    }
+
+    /// Returns the size of the object this animation is drawn on.
+    pub fn size(&self) -> ObjectSize {
+        self.size
+    }
+
+    /// Returns how this animation continues after its last frame.
+    pub fn mode(&self) -> AnimationMode {
+        self.mode
+    }
+
+    /// Returns the number of frames in this animation.
+    pub fn frame_count(&self) -> usize {
+        self.frames.len() / 4
+    }
+
+    /// Returns a frame of this animation.
+    #[track_caller]
+    pub fn frame(&self, id: usize) -> AnimationFrame {
+        let data = &self.frames[id * 4..id * 4 + 4];
+        AnimationFrame {
+            first_char: u16::from_le_bytes([data[0], data[1]]),
+            duration: u16::from_le_bytes([data[2], data[3]]),
+        }
+    }
+
+    /// Returns the number of characters in this animation's character data.
+    pub fn char_count(&self) -> usize {
+        self.chars.len() / 8
+    }
+
+    fn frame_chars(&self, id: usize) -> &'static [u32] {
+        let start = self.frame(id).first_char as usize * 8;
+        &self.chars[start..start + self.size.char_count() * 8]
+    }
+}
+
+/// How an [`AnimationPlayer`] keeps the characters of an animation in VRAM.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum AnimationStorage {
+    /// Only the current frame is kept in VRAM, and each frame is copied when it is shown.
+    ///
+    /// This uses the characters of a single frame.
+    Streamed,
+    /// The entire animation is copied into VRAM when it is started.
+    ///
+    /// This uses as many characters as the animation's character data, but does not copy any
+    /// data while the animation plays.
+    Resident,
+}
+impl AnimationStorage {
+    /// Returns the number of characters an animation uses with this storage mode.
+    pub fn char_count(self, animation: &Animation) -> usize {
+        match self {
+            AnimationStorage::Streamed => animation.size.char_count(),
+            AnimationStorage::Resident => animation.char_count(),
+        }
+    }
+}
+
+/// Plays an [`Animation`] on an object.
+pub struct AnimationPlayer {
+    animation: Animation,
+    chars: CharAccess,
+    first_char: u16,
+    storage: AnimationStorage,
+    frame: u16,
+    timer: u16,
+    last_vblank: u32,
+    is_reversed: bool,
+    is_finished: bool,
+    is_dirty: bool,
+}
+impl AnimationPlayer {
+    /// Creates a new player, storing the animation's characters starting from `first_char`.
+    ///
+    /// `chars` is usually the character access of the [`ObjectTable`], and the characters used
+    /// can be found with [`AnimationStorage::char_count`].
+    ///
+    /// [`ObjectTable`]: crate::display::objects::ObjectTable
+    pub fn new(
+        animation: Animation,
+        chars: CharAccess,
+        first_char: usize,
+        storage: AnimationStorage,
+    ) -> Self {
+        let mut player = AnimationPlayer {
+            animation,
+            chars,
+            first_char: first_char as u16,
+            storage,
+            frame: 0,
+            timer: 0,
+            last_vblank: 0,
+            is_reversed: false,
+            is_finished: false,
+            is_dirty: true,
+        };
+        player.set_animation(animation);
+        player
+    }
+
+    /// Switches to a different animation, and starts it from the beginning.
+    ///
+    /// The new animation must fit within the characters used by the player.
+    #[track_caller]
+    pub fn set_animation(&mut self, animation: Animation) {
+        self.animation = animation;
+        if self.storage == AnimationStorage::Resident {
+            self.chars
+                .write_char_4bpp(self.first_char as usize, self.animation.chars);
+        }
+        self.restart();
+    }
+
+    /// Returns the animation being played.
+    pub fn animation(&self) -> &Animation {
+        &self.animation
+    }
+
+    /// Starts the animation from its first frame.
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.timer = 0;
+        self.last_vblank = VBLANK_COUNT.read();
+        self.is_reversed = false;
+        self.is_finished = false;
+        self.is_dirty = true;
+    }
+
+    /// Returns the index of the frame currently shown.
+    pub fn frame(&self) -> usize {
+        self.frame as usize
+    }
+
+    /// Returns whether an animation that plays once has reached the end of its last frame.
+    pub fn is_finished(&self) -> bool {
+        self.is_finished
+    }
+
+    fn next_frame(&mut self) {
+        let last = self.animation.frame_count() as u16 - 1;
+        match self.animation.mode {
+            _ if last == 0 => self.is_finished = self.animation.mode == AnimationMode::Once,
+            AnimationMode::Once if self.frame == last => self.is_finished = true,
+            AnimationMode::Loop if self.frame == last => self.frame = 0,
+            AnimationMode::PingPong if self.is_reversed && self.frame == 0 => {
+                self.is_reversed = false;
+                self.frame = 1;
+            }
+            AnimationMode::PingPong if !self.is_reversed && self.frame == last => {
+                self.is_reversed = true;
+                self.frame = last - 1;
+            }
+            _ if self.is_reversed => self.frame -= 1,
+            _ => self.frame += 1,
+        }
+    }
+
+    /// Advances the animation by the VBlanks since it was last updated, and updates the object to
+    /// show the current frame.
+    ///
+    /// This should usually be called once per frame, shortly after VBlank begins. As the animation
+    /// advances once per VBlank, it plays at the same speed even when a frame takes longer than a
+    /// VBlank to process.
+    #[track_caller]
+    pub fn update(&mut self, object: &mut Object) {
+        let now = VBLANK_COUNT.read();
+        let mut elapsed = now.wrapping_sub(self.last_vblank);
+        self.last_vblank = now;
+
+        while elapsed > 0 && !self.is_finished {
+            // a frame with a duration of 0 is still shown for a single VBlank.
+            let duration = self.animation.frame(self.frame as usize).duration.max(1);
+            let remaining = (duration - self.timer) as u32;
+            if elapsed < remaining {
+                self.timer += elapsed as u16;
+                break;
+            }
+            elapsed -= remaining;
+            self.timer = 0;
+            let old_frame = self.frame;
+            self.next_frame();
+            self.is_dirty |= self.frame != old_frame;
+        }
+        self.apply(object);
+    }
+
+    /// Updates the object to show the current frame without advancing the animation.
+    #[track_caller]
+    pub fn apply(&mut self, object: &mut Object) {
+        let frame = self.frame as usize;
+        let tile = match self.storage {
+            AnimationStorage::Streamed => {
+                if self.is_dirty {
+                    let data = self.animation.frame_chars(frame);
+                    self.chars.write_char_4bpp(self.first_char as usize, data);
+                }
+                self.first_char
+            }
+            AnimationStorage::Resident => self.first_char + self.animation.frame(frame).first_char,
+        };
+        self.is_dirty = false;
+
+        if object.size() != self.animation.size {
+            object.set_size(self.animation.size);
+        }
+        if object.tile() != tile {
+            object.set_tile(tile);
+        }
+    }
+}
+
+#[inline(never)]
+#[track_caller]
+fn animation_data_invalid() -> ! {
+    crate::panic_handler::static_panic("Animation data is malformed.")
+}
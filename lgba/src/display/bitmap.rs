@@ -0,0 +1,261 @@
+use crate::{
+    display::{vram::copy_volatile, Color},
+    dma::DmaChannel,
+};
+use core::{ffi::c_void, marker::PhantomData};
+
+/// A helper type used to draw into a 16bpp bitmap in VRAM.
+#[derive(Debug)]
+pub struct Bitmap16<'a> {
+    base: *mut Color,
+    width: usize,
+    height: usize,
+    _phantom: PhantomData<&'a mut [Color]>,
+}
+impl<'a> Bitmap16<'a> {
+    pub(crate) fn new(base: usize, width: usize, height: usize) -> Self {
+        Bitmap16 { base: base as *mut Color, width, height, _phantom: PhantomData }
+    }
+
+    #[track_caller]
+    fn index(&self, x: usize, y: usize) -> usize {
+        if x >= self.width || y >= self.height {
+            bitmap_coordinate_out_of_range(self.width, self.height);
+        }
+        x + y * self.width
+    }
+    #[track_caller]
+    fn check_bounds(&self, x: usize, y: usize, count: usize) {
+        if self.index(x, y) + count > self.width * self.height {
+            bitmap_coordinate_out_of_range(self.width, self.height);
+        }
+    }
+    #[track_caller]
+    fn ptr(&self, x: usize, y: usize) -> *mut Color {
+        unsafe { self.base.add(self.index(x, y)) }
+    }
+
+    /// Returns the width of this bitmap in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of this bitmap in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the color of the pixel at a given coordinate.
+    #[track_caller]
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        unsafe { core::ptr::read_volatile(self.ptr(x, y)) }
+    }
+
+    /// Sets the color of the pixel at a given coordinate.
+    #[track_caller]
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+        unsafe { core::ptr::write_volatile(self.ptr(x, y), color) }
+    }
+
+    /// Sets the pixels starting at a given coordinate.
+    ///
+    /// The list of pixels is laid out horizontally, and will roll over to the start of the next
+    /// row if it reaches the end of a row.
+    #[track_caller]
+    pub fn set_pixels(&mut self, x: usize, y: usize, pixels: &[Color]) {
+        self.check_bounds(x, y, pixels.len());
+        unsafe { copy_volatile(pixels.as_ptr(), self.ptr(x, y), pixels.len()) }
+    }
+
+    /// Sets the pixels starting at a given coordinate using DMA.
+    ///
+    /// The list of pixels is laid out horizontally, and will roll over to the start of the next
+    /// row if it reaches the end of a row.
+    #[track_caller]
+    pub fn set_pixels_dma(
+        &mut self,
+        mut channel: DmaChannel,
+        x: usize,
+        y: usize,
+        pixels: &[Color],
+    ) {
+        self.check_bounds(x, y, pixels.len());
+        unsafe {
+            channel.unsafe_transfer(
+                pixels.as_ptr() as *const c_void,
+                self.ptr(x, y) as *mut c_void,
+                pixels.len() * 2,
+            );
+        }
+    }
+
+    /// Fills a rectangle with a single color.
+    #[track_caller]
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: Color) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        self.index(x + width - 1, y + height - 1);
+        for y in y..y + height {
+            let mut ptr = self.ptr(x, y);
+            for _ in 0..width {
+                unsafe {
+                    core::ptr::write_volatile(ptr, color);
+                    ptr = ptr.add(1);
+                }
+            }
+        }
+    }
+
+    /// Fills the entire bitmap with a single color.
+    pub fn fill(&mut self, color: Color) {
+        self.fill_rect(0, 0, self.width, self.height, color);
+    }
+
+    /// Fills the entire bitmap with a single color using DMA.
+    pub fn fill_dma(&mut self, mut channel: DmaChannel, color: Color) {
+        let color = color.to_raw() as u32 | ((color.to_raw() as u32) << 16);
+        unsafe {
+            channel.unsafe_set(color, self.base as *mut u32, self.width * self.height / 2);
+        }
+    }
+}
+
+/// A helper type used to draw into a paletted 8bpp bitmap in VRAM.
+///
+/// As VRAM does not support 8-bit writes, writing single pixels requires reading and writing the
+/// neighboring pixel as well. Writing pixels in pairs is considerably faster.
+#[derive(Debug)]
+pub struct Bitmap8<'a> {
+    base: *mut u16,
+    width: usize,
+    height: usize,
+    _phantom: PhantomData<&'a mut [u8]>,
+}
+impl<'a> Bitmap8<'a> {
+    pub(crate) fn new(base: usize, width: usize, height: usize) -> Self {
+        Bitmap8 { base: base as *mut u16, width, height, _phantom: PhantomData }
+    }
+
+    #[track_caller]
+    fn index(&self, x: usize, y: usize) -> usize {
+        if x >= self.width || y >= self.height {
+            bitmap_coordinate_out_of_range(self.width, self.height);
+        }
+        x + y * self.width
+    }
+    #[track_caller]
+    fn check_bounds(&self, x: usize, y: usize, count: usize) {
+        if self.index(x, y) + count > self.width * self.height {
+            bitmap_coordinate_out_of_range(self.width, self.height);
+        }
+    }
+    unsafe fn write_byte(&self, idx: usize, color: u8) {
+        let ptr = self.base.add(idx / 2);
+        let prev = core::ptr::read_volatile(ptr);
+        let new = if idx.is_multiple_of(2) {
+            (prev & 0xFF00) | color as u16
+        } else {
+            (prev & 0x00FF) | ((color as u16) << 8)
+        };
+        core::ptr::write_volatile(ptr, new);
+    }
+    unsafe fn write_run(&self, mut idx: usize, mut count: usize, color: u8) {
+        if !idx.is_multiple_of(2) && count != 0 {
+            self.write_byte(idx, color);
+            idx += 1;
+            count -= 1;
+        }
+        let pair = color as u16 | ((color as u16) << 8);
+        let mut ptr = self.base.add(idx / 2);
+        for _ in 0..count / 2 {
+            core::ptr::write_volatile(ptr, pair);
+            ptr = ptr.add(1);
+        }
+        if !count.is_multiple_of(2) {
+            self.write_byte(idx + count - 1, color);
+        }
+    }
+
+    /// Returns the width of this bitmap in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of this bitmap in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the palette index of the pixel at a given coordinate.
+    #[track_caller]
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        let idx = self.index(x, y);
+        let pair = unsafe { core::ptr::read_volatile(self.base.add(idx / 2)) };
+        (pair >> ((idx % 2) * 8)) as u8
+    }
+
+    /// Sets the palette index of the pixel at a given coordinate.
+    #[track_caller]
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: u8) {
+        let idx = self.index(x, y);
+        unsafe { self.write_byte(idx, color) }
+    }
+
+    /// Sets the pixels starting at a given coordinate.
+    ///
+    /// The list of pixels is laid out horizontally, and will roll over to the start of the next
+    /// row if it reaches the end of a row.
+    #[track_caller]
+    pub fn set_pixels(&mut self, x: usize, y: usize, pixels: &[u8]) {
+        self.check_bounds(x, y, pixels.len());
+        let mut idx = self.index(x, y);
+        let mut pixels = pixels;
+        unsafe {
+            if !idx.is_multiple_of(2) && !pixels.is_empty() {
+                self.write_byte(idx, pixels[0]);
+                pixels = &pixels[1..];
+                idx += 1;
+            }
+            let mut ptr = self.base.add(idx / 2);
+            for pair in pixels.chunks_exact(2) {
+                core::ptr::write_volatile(ptr, u16::from_le_bytes([pair[0], pair[1]]));
+                ptr = ptr.add(1);
+            }
+            if !pixels.len().is_multiple_of(2) {
+                self.write_byte(idx + pixels.len() - 1, pixels[pixels.len() - 1]);
+            }
+        }
+    }
+
+    /// Fills a rectangle with a single palette index.
+    #[track_caller]
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: u8) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        self.index(x + width - 1, y + height - 1);
+        for y in y..y + height {
+            unsafe { self.write_run(self.index(x, y), width, color) }
+        }
+    }
+
+    /// Fills the entire bitmap with a single palette index.
+    pub fn fill(&mut self, color: u8) {
+        unsafe { self.write_run(0, self.width * self.height, color) }
+    }
+
+    /// Fills the entire bitmap with a single palette index using DMA.
+    pub fn fill_dma(&mut self, mut channel: DmaChannel, color: u8) {
+        let color = u32::from_le_bytes([color; 4]);
+        unsafe {
+            channel.unsafe_set(color, self.base as *mut u32, self.width * self.height / 4);
+        }
+    }
+}
+
+#[inline(never)]
+#[track_caller]
+fn bitmap_coordinate_out_of_range(width: usize, height: usize) {
+    panic!("Bitmap coordinate out of range: 0..{width}, 0..{height}");
+}
@@ -0,0 +1,389 @@
+//! Module for controlling the color special effects of the display.
+//!
+//! The GBA can either mix two sets of layers together (alpha blending), or brighten or darken one
+//! set of layers toward white or black. Which layers are affected in a given area of the screen
+//! may be further restricted with [`WindowTarget::ColorEffect`].
+//!
+//! Brightness effects can also be faded over a number of frames, which is done by the VBlank
+//! interrupt while the program continues running.
+//!
+//! [`WindowTarget::ColorEffect`]: crate::display::window::WindowTarget::ColorEffect
+
+use crate::{
+    display::{shadow, transition::Transition},
+    mmio::{display::BldCnt, reg::*},
+    sync::Static,
+};
+use enumset::EnumSet;
+
+#[doc(inline)]
+pub use crate::mmio::display::{BlendTarget, BlendingMode};
+
+static FADE: Static<Option<Transition>> = Static::new(None);
+
+/// Updates the brightness if a fade is in progress.
+pub(crate) fn on_vblank() {
+    if let Some(mut fade) = FADE.read() {
+        let (value, done) = fade.step();
+        BLDY.write(value as u16);
+        FADE.write(if done { None } else { Some(fade) });
+    }
+}
+
+/// The color a fade brightens or darkens the screen toward.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub enum FadeColor {
+    /// Fade toward black, using [`BlendingMode::Darken`].
+    Black,
+    /// Fade toward white, using [`BlendingMode::Lighten`].
+    White,
+}
+impl FadeColor {
+    fn mode(self) -> BlendingMode {
+        match self {
+            FadeColor::Black => BlendingMode::Darken,
+            FadeColor::White => BlendingMode::Lighten,
+        }
+    }
+}
+
+/// The maximum value of the blending coefficients and the brightness.
+pub const MAX_COEFFICIENT: u8 = 16;
+
+/// The color special effect configuration of a display mode that is not currently active.
+///
+/// By default, no effect is applied.
+#[derive(Debug)]
+pub struct Blending {
+    cnt: BldCnt,
+    alpha: [u8; 2],
+    brightness: u8,
+}
+impl Blending {
+    pub(crate) fn new() -> Self {
+        Blending { cnt: BldCnt::default(), alpha: [0; 2], brightness: 0 }
+    }
+
+    fn write_cnt(&self) {
+        shadow::write_bld_cnt(self.cnt);
+    }
+    fn write_alpha(&self) {
+        shadow::write_bld_alpha(self.alpha);
+    }
+    fn write_brightness(&self) {
+        FADE.write(None);
+        shadow::write_bld_y(self.brightness as u16);
+    }
+
+    pub(crate) fn activate(&mut self) -> ActiveBlending<'_> {
+        self.write_cnt();
+        self.write_alpha();
+        self.write_brightness();
+        ActiveBlending { blending: self }
+    }
+
+    /// Returns the color special effect used.
+    pub fn mode(&self) -> BlendingMode {
+        self.cnt.mode()
+    }
+
+    /// Sets the color special effect used.
+    pub fn set_mode(&mut self, mode: BlendingMode) -> &mut Self {
+        self.cnt = self.cnt.with_mode(mode);
+        self
+    }
+
+    /// Returns the first targets of the color special effect.
+    pub fn first_targets(&self) -> EnumSet<BlendTarget> {
+        self.cnt.target_a()
+    }
+
+    /// Sets the first targets of the color special effect.
+    ///
+    /// These are the layers that are mixed with the second target in alpha blending mode, or that
+    /// are brightened or darkened in the other modes.
+    pub fn set_first_targets(&mut self, targets: impl Into<EnumSet<BlendTarget>>) -> &mut Self {
+        self.cnt = self.cnt.with_target_a(targets.into());
+        self
+    }
+
+    /// Returns the second targets of the color special effect.
+    pub fn second_targets(&self) -> EnumSet<BlendTarget> {
+        self.cnt.target_b()
+    }
+
+    /// Sets the second targets of the color special effect.
+    ///
+    /// These are only used in alpha blending mode, and only where they are directly below a first
+    /// target.
+    pub fn set_second_targets(&mut self, targets: impl Into<EnumSet<BlendTarget>>) -> &mut Self {
+        self.cnt = self.cnt.with_target_b(targets.into());
+        self
+    }
+
+    /// Returns the alpha blending coefficients of the first and second targets.
+    pub fn alpha(&self) -> (u8, u8) {
+        (self.alpha[0], self.alpha[1])
+    }
+
+    /// Sets the alpha blending coefficients of the first and second targets.
+    ///
+    /// The coefficients are in units of 1/16th, and must be between 0 and 16 inclusive. The
+    /// resulting color is `(first * eva + second * evb) / 16`.
+    #[track_caller]
+    pub fn set_alpha(&mut self, eva: u8, evb: u8) -> &mut Self {
+        if eva > MAX_COEFFICIENT || evb > MAX_COEFFICIENT {
+            coefficient_out_of_range();
+        }
+        self.alpha = [eva, evb];
+        self
+    }
+
+    /// Returns the strength of the brightness effect.
+    ///
+    /// If a fade is in progress, this returns the brightness the fade ends at.
+    pub fn brightness(&self) -> u8 {
+        self.brightness
+    }
+
+    /// Sets the strength of the brightness effect.
+    ///
+    /// The brightness is in units of 1/16th, and must be between 0 and 16 inclusive. At 16, the
+    /// first targets are fully white or black.
+    #[track_caller]
+    pub fn set_brightness(&mut self, brightness: u8) -> &mut Self {
+        if brightness > MAX_COEFFICIENT {
+            coefficient_out_of_range();
+        }
+        self.brightness = brightness;
+        self
+    }
+}
+
+/// The color special effect configuration of an active display mode.
+#[derive(Debug)]
+pub struct ActiveBlending<'a> {
+    blending: &'a mut Blending,
+}
+impl<'a> ActiveBlending<'a> {
+    /// Returns the color special effect used.
+    pub fn mode(&self) -> BlendingMode {
+        self.blending.mode()
+    }
+
+    /// Sets the color special effect used.
+    pub fn set_mode<'b>(&'b mut self, mode: BlendingMode) -> ActiveBlendingEditGuard<'b, 'a> {
+        self.blending.set_mode(mode);
+        ActiveBlendingEditGuard::new(self).mark_cnt_dirty()
+    }
+
+    /// Returns the first targets of the color special effect.
+    pub fn first_targets(&self) -> EnumSet<BlendTarget> {
+        self.blending.first_targets()
+    }
+
+    /// Sets the first targets of the color special effect.
+    ///
+    /// These are the layers that are mixed with the second target in alpha blending mode, or that
+    /// are brightened or darkened in the other modes.
+    pub fn set_first_targets<'b>(
+        &'b mut self,
+        targets: impl Into<EnumSet<BlendTarget>>,
+    ) -> ActiveBlendingEditGuard<'b, 'a> {
+        self.blending.set_first_targets(targets);
+        ActiveBlendingEditGuard::new(self).mark_cnt_dirty()
+    }
+
+    /// Returns the second targets of the color special effect.
+    pub fn second_targets(&self) -> EnumSet<BlendTarget> {
+        self.blending.second_targets()
+    }
+
+    /// Sets the second targets of the color special effect.
+    ///
+    /// These are only used in alpha blending mode, and only where they are directly below a first
+    /// target.
+    pub fn set_second_targets<'b>(
+        &'b mut self,
+        targets: impl Into<EnumSet<BlendTarget>>,
+    ) -> ActiveBlendingEditGuard<'b, 'a> {
+        self.blending.set_second_targets(targets);
+        ActiveBlendingEditGuard::new(self).mark_cnt_dirty()
+    }
+
+    /// Returns the alpha blending coefficients of the first and second targets.
+    pub fn alpha(&self) -> (u8, u8) {
+        self.blending.alpha()
+    }
+
+    /// Sets the alpha blending coefficients of the first and second targets.
+    ///
+    /// The coefficients are in units of 1/16th, and must be between 0 and 16 inclusive. The
+    /// resulting color is `(first * eva + second * evb) / 16`.
+    #[track_caller]
+    pub fn set_alpha<'b>(&'b mut self, eva: u8, evb: u8) -> ActiveBlendingEditGuard<'b, 'a> {
+        self.blending.set_alpha(eva, evb);
+        ActiveBlendingEditGuard::new(self).mark_alpha_dirty()
+    }
+
+    /// Returns the strength of the brightness effect.
+    ///
+    /// If a fade is in progress, this returns the brightness the fade ends at.
+    pub fn brightness(&self) -> u8 {
+        self.blending.brightness()
+    }
+
+    /// Sets the strength of the brightness effect, stopping any fade in progress.
+    ///
+    /// The brightness is in units of 1/16th, and must be between 0 and 16 inclusive. At 16, the
+    /// first targets are fully white or black.
+    #[track_caller]
+    pub fn set_brightness<'b>(&'b mut self, brightness: u8) -> ActiveBlendingEditGuard<'b, 'a> {
+        self.blending.set_brightness(brightness);
+        ActiveBlendingEditGuard::new(self).mark_brightness_dirty()
+    }
+
+    /// Fades the brightness to a given value over a number of frames.
+    ///
+    /// This uses the current color special effect, which should be [`BlendingMode::Lighten`] or
+    /// [`BlendingMode::Darken`]. Any fade already in progress continues from where it stopped.
+    #[track_caller]
+    pub fn fade_brightness(&mut self, brightness: u8, frames: u16) {
+        let from = FADE
+            .read()
+            .map_or(self.blending.brightness, |fade| fade.current());
+        self.blending.set_brightness(brightness);
+        FADE.write(Some(Transition::new(from, brightness, frames)));
+    }
+
+    /// Fades the first targets in from a given color over a number of frames.
+    ///
+    /// This sets the color special effect to the one matching the color, and makes all layers
+    /// first targets. If that effect is not already in use, the fade starts fully faded out.
+    pub fn fade_in(&mut self, color: FadeColor, frames: u16) {
+        self.start_fade(color, MAX_COEFFICIENT, 0, frames);
+    }
+
+    /// Fades the first targets out to a given color over a number of frames.
+    ///
+    /// This sets the color special effect to the one matching the color, and makes all layers
+    /// first targets. If that effect is not already in use, the fade starts fully faded in.
+    pub fn fade_out(&mut self, color: FadeColor, frames: u16) {
+        self.start_fade(color, 0, MAX_COEFFICIENT, frames);
+    }
+
+    fn start_fade(&mut self, color: FadeColor, from: u8, to: u8, frames: u16) {
+        if self.blending.mode() != color.mode() {
+            self.set_mode(color.mode()).set_brightness(from);
+        }
+        self.set_first_targets(EnumSet::all());
+        self.fade_brightness(to, frames);
+    }
+
+    /// Returns whether a fade is currently in progress.
+    pub fn is_fading(&self) -> bool {
+        FADE.read().is_some()
+    }
+
+    /// Waits until the current fade is complete.
+    pub fn wait_fade(&self) {
+        while self.is_fading() {
+            crate::sys::wait_for_vblank();
+        }
+    }
+}
+impl<'a> Drop for ActiveBlending<'a> {
+    fn drop(&mut self) {
+        if let Some(fade) = FADE.replace(None) {
+            self.blending.brightness = fade.target();
+        }
+    }
+}
+
+/// A temporary guard created to allow chaining operations on [`ActiveBlending`], and only writing
+/// once to the memory mapped IO.
+#[derive(Debug)]
+pub struct ActiveBlendingEditGuard<'a, 'b: 'a> {
+    blending: &'a mut ActiveBlending<'b>,
+    is_cnt_dirty: bool,
+    is_alpha_dirty: bool,
+    is_brightness_dirty: bool,
+}
+impl<'a, 'b: 'a> ActiveBlendingEditGuard<'a, 'b> {
+    fn new(blending: &'a mut ActiveBlending<'b>) -> Self {
+        ActiveBlendingEditGuard {
+            blending,
+            is_cnt_dirty: false,
+            is_alpha_dirty: false,
+            is_brightness_dirty: false,
+        }
+    }
+
+    fn mark_cnt_dirty(mut self) -> Self {
+        self.is_cnt_dirty = true;
+        self
+    }
+    fn mark_alpha_dirty(mut self) -> Self {
+        self.is_alpha_dirty = true;
+        self
+    }
+    fn mark_brightness_dirty(mut self) -> Self {
+        self.is_brightness_dirty = true;
+        self
+    }
+
+    /// Sets the color special effect used.
+    pub fn set_mode(self, mode: BlendingMode) -> Self {
+        self.blending.blending.set_mode(mode);
+        self.mark_cnt_dirty()
+    }
+
+    /// Sets the first targets of the color special effect.
+    pub fn set_first_targets(self, targets: impl Into<EnumSet<BlendTarget>>) -> Self {
+        self.blending.blending.set_first_targets(targets);
+        self.mark_cnt_dirty()
+    }
+
+    /// Sets the second targets of the color special effect.
+    pub fn set_second_targets(self, targets: impl Into<EnumSet<BlendTarget>>) -> Self {
+        self.blending.blending.set_second_targets(targets);
+        self.mark_cnt_dirty()
+    }
+
+    /// Sets the alpha blending coefficients of the first and second targets.
+    ///
+    /// The coefficients must be between 0 and 16 inclusive.
+    #[track_caller]
+    pub fn set_alpha(self, eva: u8, evb: u8) -> Self {
+        self.blending.blending.set_alpha(eva, evb);
+        self.mark_alpha_dirty()
+    }
+
+    /// Sets the strength of the brightness effect, stopping any fade in progress.
+    ///
+    /// The brightness must be between 0 and 16 inclusive.
+    #[track_caller]
+    pub fn set_brightness(self, brightness: u8) -> Self {
+        self.blending.blending.set_brightness(brightness);
+        self.mark_brightness_dirty()
+    }
+}
+impl<'a, 'b: 'a> Drop for ActiveBlendingEditGuard<'a, 'b> {
+    fn drop(&mut self) {
+        if self.is_cnt_dirty {
+            self.blending.blending.write_cnt();
+        }
+        if self.is_alpha_dirty {
+            self.blending.blending.write_alpha();
+        }
+        if self.is_brightness_dirty {
+            self.blending.blending.write_brightness();
+        }
+    }
+}
+
+#[inline(never)]
+#[track_caller]
+fn coefficient_out_of_range() -> ! {
+    crate::panic_handler::static_panic("Blending coefficients must be between 0 and 16!")
+}
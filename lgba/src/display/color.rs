@@ -0,0 +1,206 @@
+#[doc(inline)]
+pub use crate::mmio::display::Color;
+
+impl Color {
+    /// Black.
+    pub const BLACK: Color = Color::new(0, 0, 0);
+    /// White.
+    pub const WHITE: Color = Color::new(31, 31, 31);
+    /// Red.
+    pub const RED: Color = Color::new(31, 0, 0);
+    /// Green.
+    pub const GREEN: Color = Color::new(0, 31, 0);
+    /// Blue.
+    pub const BLUE: Color = Color::new(0, 0, 31);
+    /// Yellow.
+    pub const YELLOW: Color = Color::new(31, 31, 0);
+    /// Cyan.
+    pub const CYAN: Color = Color::new(0, 31, 31);
+    /// Magenta.
+    pub const MAGENTA: Color = Color::new(31, 0, 31);
+    /// Medium gray.
+    pub const GRAY: Color = Color::new(16, 16, 16);
+
+    /// The largest amount that can be passed to [`Color::lerp`].
+    pub const LERP_MAX: u8 = 32;
+
+    /// Creates a color from three 5-bit components.
+    ///
+    /// This function panics if any component is 32 or more.
+    #[inline(always)]
+    #[track_caller]
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        if r >= 32 || g >= 32 || b >= 32 {
+            color_not_valid()
+        }
+        Color(r as u16 | ((g as u16) << 5) | ((b as u16) << 10))
+    }
+
+    /// Creates a color from three 8-bit components, converting each to a 5-bit value.
+    #[inline(always)]
+    pub const fn from_rgb24(r: u8, g: u8, b: u8) -> Self {
+        Color::new(convert_24bpp_15bpp(r), convert_24bpp_15bpp(g), convert_24bpp_15bpp(b))
+    }
+
+    /// Creates a color from a 24-bit `0xRRGGBB` value, as used in HTML colors.
+    #[inline(always)]
+    pub const fn from_hex(rgb: u32) -> Self {
+        Color::from_rgb24((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+    }
+
+    /// Creates a color from its raw RGB555 representation.
+    ///
+    /// The unused highest bit is cleared.
+    #[inline(always)]
+    pub const fn from_raw(raw: u16) -> Self {
+        Color(raw & 0x7FFF)
+    }
+
+    /// Returns the raw RGB555 representation of this color.
+    #[inline(always)]
+    pub const fn to_raw(self) -> u16 {
+        self.0
+    }
+
+    /// Returns the 5-bit red component of this color.
+    pub const fn r(self) -> u8 {
+        (self.0 & 31) as u8
+    }
+
+    /// Returns the 5-bit green component of this color.
+    pub const fn g(self) -> u8 {
+        ((self.0 >> 5) & 31) as u8
+    }
+
+    /// Returns the 5-bit blue component of this color.
+    pub const fn b(self) -> u8 {
+        ((self.0 >> 10) & 31) as u8
+    }
+
+    /// Returns the components of this color expanded to 8 bits each.
+    pub const fn to_rgb24(self) -> (u8, u8, u8) {
+        (
+            convert_15bpp_24bpp(self.r()),
+            convert_15bpp_24bpp(self.g()),
+            convert_15bpp_24bpp(self.b()),
+        )
+    }
+
+    /// Linearly interpolates between this color and another color.
+    ///
+    /// The amount is in units of 1/32nd, and must be between 0 and 32 inclusive. At 0, this
+    /// color is returned unchanged, and at 32, the other color is returned.
+    #[track_caller]
+    pub const fn lerp(self, other: Color, amount: u8) -> Self {
+        if amount > Color::LERP_MAX {
+            lerp_amount_not_valid()
+        }
+        const fn mix(from: u8, to: u8, amount: u8) -> u8 {
+            let delta = (to as i32 - from as i32) * amount as i32 / Color::LERP_MAX as i32;
+            (from as i32 + delta) as u8
+        }
+        Color::new(
+            mix(self.r(), other.r(), amount),
+            mix(self.g(), other.g(), amount),
+            mix(self.b(), other.b(), amount),
+        )
+    }
+
+    /// Creates a color from a hue, saturation and value.
+    ///
+    /// The hue is in degrees, and wraps around at 360. The saturation and value range from 0 to
+    /// 255 inclusive.
+    pub const fn from_hsv(hue: u16, saturation: u8, value: u8) -> Self {
+        let hue = (hue % 360) as u32;
+        let (s, v) = (saturation as u32, value as u32);
+        let f = (hue % 60) * 255 / 60;
+        let p = (v * (255 - s) / 255) as u8;
+        let q = (v * (255 - s * f / 255) / 255) as u8;
+        let t = (v * (255 - s * (255 - f) / 255) / 255) as u8;
+        let v = v as u8;
+        match hue / 60 {
+            0 => Color::from_rgb24(v, t, p),
+            1 => Color::from_rgb24(q, v, p),
+            2 => Color::from_rgb24(p, v, t),
+            3 => Color::from_rgb24(p, q, v),
+            4 => Color::from_rgb24(t, p, v),
+            _ => Color::from_rgb24(v, p, q),
+        }
+    }
+
+    /// Returns the hue, saturation and value of this color.
+    ///
+    /// The hue is in degrees from 0 to 359, and the saturation and value range from 0 to 255
+    /// inclusive.
+    pub const fn to_hsv(self) -> (u16, u8, u8) {
+        let (r, g, b) = self.to_rgb24();
+        let (r, g, b) = (r as i32, g as i32, b as i32);
+        let max = if r > g {
+            if r > b {
+                r
+            } else {
+                b
+            }
+        } else if g > b {
+            g
+        } else {
+            b
+        };
+        let min = if r < g {
+            if r < b {
+                r
+            } else {
+                b
+            }
+        } else if g < b {
+            g
+        } else {
+            b
+        };
+        let delta = max - min;
+
+        let saturation = if max == 0 { 0 } else { delta * 255 / max };
+        let hue = if delta == 0 {
+            0
+        } else if max == r {
+            60 * (g - b) / delta
+        } else if max == g {
+            120 + 60 * (b - r) / delta
+        } else {
+            240 + 60 * (r - g) / delta
+        };
+        (hue.rem_euclid(360) as u16, saturation as u8, max as u8)
+    }
+}
+impl From<Color> for u16 {
+    fn from(value: Color) -> Self {
+        value.to_raw()
+    }
+}
+impl From<u16> for Color {
+    fn from(value: u16) -> Self {
+        Color::from_raw(value)
+    }
+}
+
+#[inline(always)]
+const fn convert_24bpp_15bpp(ch: u8) -> u8 {
+    ch.saturating_add(16) / 8
+}
+
+#[inline(always)]
+const fn convert_15bpp_24bpp(ch: u8) -> u8 {
+    (ch << 3) | (ch >> 2)
+}
+
+#[inline(never)]
+#[track_caller]
+const fn color_not_valid() {
+    panic!("Color data must be in the range 0..31");
+}
+
+#[inline(never)]
+#[track_caller]
+const fn lerp_amount_not_valid() {
+    panic!("Color interpolation amounts must be in the range 0..=32");
+}
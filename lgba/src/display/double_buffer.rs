@@ -0,0 +1,224 @@
+//! Module for double-buffered tile maps.
+//!
+//! Tile maps written through [`ActiveTileLayer::map_access`] are displayed as soon as they are
+//! written, so rewriting a large part of a map can show a half-drawn frame. A
+//! [`DoubleBufferedMap`] instead gives a tile layer two sets of screen blocks. Tiles are written
+//! into the set that is not displayed, and [`present`] swaps the two sets during the next VBlank,
+//! so the entire change appears at once.
+//!
+//! After a swap, the new back buffer still contains the tiles from before the previous swap. If
+//! the map is edited a little at a time rather than redrawn entirely, the map can be configured
+//! to copy the displayed tiles into the back buffer after each swap with
+//! [`set_copy_on_present`].
+//!
+//! # Example
+//!
+//! ```rust
+//! use lgba::{display::double_buffer::*, dma::DmaChannelId};
+//!
+//! # fn example(mode: &mut lgba::display::ActiveMode0, tile: lgba::display::VramTile) {
+//! let mut map = DoubleBufferedMap::new(&mut mode.layers[0], DmaChannelId::Dma3.create());
+//! map.map_access(0).set_tile(0, 0, tile);
+//! map.present();
+//! # }
+//! ```
+//!
+//! [`present`]: DoubleBufferedMap::present
+//! [`set_copy_on_present`]: DoubleBufferedMap::set_copy_on_present
+
+use crate::{
+    display::{
+        shadow, vram::MapAccess, vram_alloc, vram_alloc::ScreenBlocks, ActiveTileLayer, VramTile,
+    },
+    dma::DmaChannel,
+    mmio::reg::VRAM_BASE,
+    sync::Mutex,
+};
+use core::ffi::c_void;
+
+struct BufferState {
+    channel: DmaChannel,
+    map_count: u8,
+    copy_on_present: bool,
+    pending: Option<(u8, u8)>,
+}
+impl BufferState {
+    /// Switches the layer to the pending front buffer, and updates the back buffer if needed.
+    fn swap(&mut self, layer: usize) {
+        if let Some((front, back)) = self.pending {
+            if !shadow::try_write_bg_tile_base_from_vblank(layer, front as usize) {
+                return;
+            }
+            self.pending = None;
+            if self.copy_on_present {
+                unsafe {
+                    self.channel.unsafe_transfer(
+                        block_ptr(front) as *const c_void,
+                        block_ptr(back) as *mut c_void,
+                        self.map_count as usize * 2048,
+                    );
+                }
+            }
+        }
+    }
+}
+
+static BUFFERS: [Mutex<Option<BufferState>>; 4] = [const { Mutex::new(None) }; 4];
+
+/// Swaps the buffers of all double-buffered maps with a pending swap.
+///
+/// If a double-buffered map is currently being edited, this is retried on the next VBlank.
+pub(crate) fn on_vblank() {
+    for (layer, buffer) in BUFFERS.iter().enumerate() {
+        if let Some(mut buffer) = buffer.try_lock() {
+            if let Some(buffer) = &mut *buffer {
+                buffer.swap(layer);
+            }
+        }
+    }
+}
+
+fn block_ptr(block: u8) -> *mut VramTile {
+    (VRAM_BASE + 2048 * block as usize) as *mut VramTile
+}
+
+/// A tile layer with two tile maps, one which is displayed, and one which can be edited.
+///
+/// The layer uses screen blocks allocated by the double-buffered map, with enough room for two
+/// copies of its current tile map size. Its other settings are left unchanged, and the tile map
+/// size should not be changed while the map is alive. The double-buffered map keeps the layer
+/// borrowed for as long as it is alive, and disables it when dropped, as its screen blocks are
+/// freed.
+pub struct DoubleBufferedMap<'a, 'b> {
+    layer: &'a mut ActiveTileLayer<'b>,
+    map_count: usize,
+    front: usize,
+    blocks: ScreenBlocks,
+}
+impl<'a, 'b> DoubleBufferedMap<'a, 'b> {
+    /// Creates a new double-buffered map on a tile layer, with both buffers cleared.
+    ///
+    /// The DMA channel is used to clear the buffers, and to copy tiles between them. This
+    /// function panics if there are not enough free screen blocks, or if another double-buffered
+    /// map already uses this layer.
+    #[track_caller]
+    pub fn new(layer: &'a mut ActiveTileLayer<'b>, mut channel: DmaChannel) -> Self {
+        let mut slot = BUFFERS[layer.id() as usize].lock();
+        if slot.is_some() {
+            double_buffered_map_in_use();
+        }
+
+        let map_count = layer.tile_map_size().map_count();
+        let blocks = vram_alloc::alloc_screen_blocks(map_count * 2);
+        unsafe {
+            channel.unsafe_set(
+                VramTile::default(),
+                block_ptr(blocks.tile_base() as u8),
+                map_count * 2 * 1024,
+            );
+        }
+        layer.set_tile_base(blocks.tile_base());
+        *slot = Some(BufferState {
+            channel,
+            map_count: map_count as u8,
+            copy_on_present: false,
+            pending: None,
+        });
+        drop(slot);
+
+        DoubleBufferedMap { layer, map_count, front: 0, blocks }
+    }
+
+    fn buffer_base(&self, buffer: usize) -> usize {
+        self.blocks.tile_base() + buffer * self.map_count
+    }
+
+    #[track_caller]
+    fn buffer_access(&self, buffer: usize, screen: usize) -> MapAccess {
+        if screen >= self.map_count {
+            invalid_buffer_screen();
+        }
+        self.blocks.map_access(buffer * self.map_count + screen)
+    }
+
+    /// Returns a tile map access for the buffer that is not displayed.
+    ///
+    /// If a swap is pending, this waits until the next VBlank for it to complete.
+    #[track_caller]
+    pub fn map_access(&self, screen: usize) -> MapAccess {
+        self.wait_present();
+        self.buffer_access(self.front ^ 1, screen)
+    }
+
+    /// Returns a tile map access for the buffer that is displayed.
+    ///
+    /// If a swap is pending, this waits until the next VBlank for it to complete.
+    #[track_caller]
+    pub fn front_map_access(&self, screen: usize) -> MapAccess {
+        self.wait_present();
+        self.buffer_access(self.front, screen)
+    }
+
+    /// Swaps the displayed buffer with the back buffer during the next VBlank.
+    ///
+    /// If a swap is already pending, this waits until the next VBlank for it to complete first.
+    pub fn present(&mut self) {
+        self.wait_present();
+        self.front ^= 1;
+        let front = self.buffer_base(self.front);
+        let back = self.buffer_base(self.front ^ 1);
+
+        let mut slot = BUFFERS[self.layer.id() as usize].lock();
+        slot.as_mut().unwrap().pending = Some((front as u8, back as u8));
+        drop(slot);
+        self.layer.set_tile_base_untracked(front);
+    }
+
+    /// Returns whether a swap is still waiting for the next VBlank.
+    pub fn is_present_pending(&self) -> bool {
+        let slot = BUFFERS[self.layer.id() as usize].lock();
+        slot.as_ref().unwrap().pending.is_some()
+    }
+
+    /// Waits until the current swap has completed.
+    pub fn wait_present(&self) {
+        while self.is_present_pending() {
+            crate::sys::wait_for_vblank();
+        }
+    }
+
+    /// Returns whether the displayed tiles are copied into the back buffer after each swap.
+    pub fn copy_on_present(&self) -> bool {
+        let slot = BUFFERS[self.layer.id() as usize].lock();
+        slot.as_ref().unwrap().copy_on_present
+    }
+
+    /// Sets whether the displayed tiles are copied into the back buffer after each swap.
+    ///
+    /// This allows the back buffer to be edited incrementally, at the cost of copying the tile
+    /// map during each VBlank with a swap.
+    pub fn set_copy_on_present(&mut self, value: bool) -> &mut Self {
+        let mut slot = BUFFERS[self.layer.id() as usize].lock();
+        slot.as_mut().unwrap().copy_on_present = value;
+        drop(slot);
+        self
+    }
+}
+impl<'a, 'b> Drop for DoubleBufferedMap<'a, 'b> {
+    fn drop(&mut self) {
+        *BUFFERS[self.layer.id() as usize].lock() = None;
+        self.layer.set_enabled(false);
+    }
+}
+
+#[inline(never)]
+#[track_caller]
+fn invalid_buffer_screen() -> ! {
+    crate::panic_handler::static_panic("Screen id out of range for the layer's tile map size!")
+}
+
+#[inline(never)]
+#[track_caller]
+fn double_buffered_map_in_use() -> ! {
+    crate::panic_handler::static_panic("Layer is already used by a double-buffered map!")
+}
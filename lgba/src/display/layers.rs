@@ -1,5 +1,10 @@
 use crate::{
-    display::{vram::MapAccess, CharAccess},
+    display::{
+        edit_disp_cnt, shadow,
+        vram::{AffineMapAccess, MapAccess},
+        vram_alloc::{CharRange, ScreenBlocks},
+        AffineMatrix, CharAccess, GbaFrac32,
+    },
     mmio::{display::BgCnt, reg::*},
 };
 
@@ -14,6 +19,15 @@ pub enum LayerId {
     Layer3,
 }
 
+fn write_layer_enabled(id: LayerId, enabled: bool) {
+    edit_disp_cnt(|disp_cnt| match id {
+        LayerId::Layer0 => disp_cnt.with_display_bg0(enabled),
+        LayerId::Layer1 => disp_cnt.with_display_bg1(enabled),
+        LayerId::Layer2 => disp_cnt.with_display_bg2(enabled),
+        LayerId::Layer3 => disp_cnt.with_display_bg3(enabled),
+    })
+}
+
 /// The tile size of a layer.
 #[derive(Copy, Clone, Debug)]
 #[repr(u8)]
@@ -166,30 +180,33 @@ pub struct TileLayer {
     h_offset: i16,
     v_offset: i16,
     is_enabled: bool,
+    chars: Option<CharRange>,
+    blocks: Option<ScreenBlocks>,
 }
 impl TileLayer {
     pub(crate) fn new(id: LayerId) -> TileLayer {
-        TileLayer { id, cnt: BgCnt::default(), h_offset: 0, v_offset: 0, is_enabled: false }
+        TileLayer {
+            id,
+            cnt: BgCnt::default(),
+            h_offset: 0,
+            v_offset: 0,
+            is_enabled: false,
+            chars: None,
+            blocks: None,
+        }
     }
 
     fn write_cnt(&self) {
-        BG_CNT.index(self.id as usize).write(self.cnt);
+        shadow::write_bg_cnt(self.id as usize, self.cnt);
     }
     fn write_hoff(&self) {
-        BG_HOFS.index(self.id as usize).write(-self.h_offset);
+        shadow::write_bg_hofs(self.id as usize, -self.h_offset);
     }
     fn write_voff(&self) {
-        BG_VOFS.index(self.id as usize).write(-self.v_offset);
+        shadow::write_bg_vofs(self.id as usize, -self.v_offset);
     }
     pub(crate) fn write_enabled_from_guard(&self) {
-        let mut disp_cnt = DISPCNT.read();
-        match self.id {
-            LayerId::Layer0 => disp_cnt = disp_cnt.with_display_bg0(self.is_enabled),
-            LayerId::Layer1 => disp_cnt = disp_cnt.with_display_bg1(self.is_enabled),
-            LayerId::Layer2 => disp_cnt = disp_cnt.with_display_bg2(self.is_enabled),
-            LayerId::Layer3 => disp_cnt = disp_cnt.with_display_bg3(self.is_enabled),
-        }
-        DISPCNT.write(disp_cnt)
+        write_layer_enabled(self.id, self.is_enabled);
     }
     fn write_all(&self) {
         if self.is_enabled {
@@ -199,6 +216,15 @@ impl TileLayer {
         }
     }
 
+    /// Frees the VRAM owned by this layer, and disables it if it owned any.
+    pub(crate) fn release_vram(&mut self) {
+        if self.chars.is_some() || self.blocks.is_some() {
+            self.chars = None;
+            self.blocks = None;
+            self.is_enabled = false;
+        }
+    }
+
     pub(crate) fn activate(&mut self) -> ActiveTileLayer {
         self.write_all();
         ActiveTileLayer { layer: self }
@@ -248,9 +274,11 @@ impl TileLayer {
 
     /// Sets the character data base for this layer.
     ///
-    /// The value must be between 0 and 3, inclusive.
+    /// The value must be between 0 and 3, inclusive. Any character range owned by this layer is
+    /// freed.
     pub fn set_char_base(&mut self, value: usize) -> &mut Self {
         self.cnt = self.cnt.with_char_base(value);
+        self.chars = None;
         self
     }
 
@@ -282,8 +310,46 @@ impl TileLayer {
     }
 
     /// Sets the tile map data base for this layer.
+    ///
+    /// Any screen blocks owned by this layer are freed.
     pub fn set_tile_base(&mut self, value: usize) -> &mut Self {
         self.cnt = self.cnt.with_tile_map_base(value);
+        self.blocks = None;
+        self
+    }
+
+    /// The character range owned by this layer, if one was set with `set_char_range`.
+    pub fn char_range(&self) -> Option<&CharRange> {
+        self.chars.as_ref()
+    }
+
+    /// Sets the character base of this layer to the one used by an allocated character range.
+    ///
+    /// The layer takes ownership of the range, which is freed when the character base is changed
+    /// again, or when the layer is dropped.
+    pub fn set_char_range(&mut self, chars: CharRange) -> &mut Self {
+        self.set_char_base(chars.char_base());
+        self.chars = Some(chars);
+        self
+    }
+
+    /// The screen blocks owned by this layer, if they were set with `set_screen_blocks`.
+    pub fn screen_blocks(&self) -> Option<&ScreenBlocks> {
+        self.blocks.as_ref()
+    }
+
+    /// Sets the tile map base of this layer to the start of an allocated range of screen blocks.
+    ///
+    /// The layer takes ownership of the screen blocks, which are freed when the tile map base is
+    /// changed again, or when the layer is dropped. This function panics if the range is too small
+    /// for the current tile map size, so the size should be set first.
+    #[track_caller]
+    pub fn set_screen_blocks(&mut self, blocks: ScreenBlocks) -> &mut Self {
+        if blocks.count() < self.tile_map_size().map_count() {
+            screen_blocks_too_small();
+        }
+        self.set_tile_base(blocks.tile_base());
+        self.blocks = Some(blocks);
         self
     }
 
@@ -315,6 +381,24 @@ pub struct ActiveTileLayer<'a> {
     layer: &'a mut TileLayer,
 }
 impl<'a> ActiveTileLayer<'a> {
+    pub(crate) fn id(&self) -> LayerId {
+        self.layer.id
+    }
+
+    /// Changes the stored offset of this layer, for code that writes the registers itself.
+    pub(crate) fn set_offset_untracked(&mut self, x: i16, y: i16) {
+        self.layer.set_offset(x, y);
+    }
+
+    /// Changes the stored tile map base of this layer, for code that writes the registers itself.
+    pub(crate) fn set_tile_base_untracked(&mut self, value: usize) {
+        self.layer.set_tile_base(value);
+    }
+
+    pub(crate) fn release_vram(&mut self) {
+        self.layer.release_vram();
+    }
+
     /// Returns a character access appropriate for this layer.
     pub fn char_access(&self) -> CharAccess {
         self.layer.char_access()
@@ -393,6 +477,39 @@ impl<'a> ActiveTileLayer<'a> {
         ActiveTileLayerEditGuard::new(self).mark_cnt_dirty()
     }
 
+    /// The character range owned by this layer, if one was set with `set_char_range`.
+    pub fn char_range(&self) -> Option<&CharRange> {
+        self.layer.char_range()
+    }
+
+    /// Sets the character base of this layer to the one used by an allocated character range.
+    ///
+    /// The layer takes ownership of the range, which is freed when the character base is changed
+    /// again, or when the layer is dropped.
+    pub fn set_char_range<'b>(&'b mut self, chars: CharRange) -> ActiveTileLayerEditGuard<'b, 'a> {
+        self.layer.set_char_range(chars);
+        ActiveTileLayerEditGuard::new(self).mark_cnt_dirty()
+    }
+
+    /// The screen blocks owned by this layer, if they were set with `set_screen_blocks`.
+    pub fn screen_blocks(&self) -> Option<&ScreenBlocks> {
+        self.layer.screen_blocks()
+    }
+
+    /// Sets the tile map base of this layer to the start of an allocated range of screen blocks.
+    ///
+    /// The layer takes ownership of the screen blocks, which are freed when the tile map base is
+    /// changed again, or when the layer is dropped. This function panics if the range is too small
+    /// for the current tile map size.
+    #[track_caller]
+    pub fn set_screen_blocks<'b>(
+        &'b mut self,
+        blocks: ScreenBlocks,
+    ) -> ActiveTileLayerEditGuard<'b, 'a> {
+        self.layer.set_screen_blocks(blocks);
+        ActiveTileLayerEditGuard::new(self).mark_cnt_dirty()
+    }
+
     /// The tile map size for this layer.
     pub fn tile_map_size(&self) -> TileLayerSize {
         self.layer.tile_map_size()
@@ -482,6 +599,21 @@ impl<'a, 'b: 'a> ActiveTileLayerEditGuard<'a, 'b> {
         self.mark_cnt_dirty()
     }
 
+    /// Sets the character base of this layer to the one used by an allocated character range.
+    pub fn set_char_range(self, chars: CharRange) -> Self {
+        self.layer.layer.set_char_range(chars);
+        self.mark_cnt_dirty()
+    }
+
+    /// Sets the tile map base of this layer to the start of an allocated range of screen blocks.
+    ///
+    /// This function panics if the range is too small for the current tile map size.
+    #[track_caller]
+    pub fn set_screen_blocks(self, blocks: ScreenBlocks) -> Self {
+        self.layer.layer.set_screen_blocks(blocks);
+        self.mark_cnt_dirty()
+    }
+
     /// Sets the tile map size for this layer.
     pub fn set_tile_map_size(self, value: TileLayerSize) -> Self {
         self.layer.layer.set_tile_map_size(value);
@@ -500,6 +632,656 @@ impl<'a, 'b: 'a> Drop for ActiveTileLayerEditGuard<'a, 'b> {
             self.layer.layer.write_voff();
         }
         if self.is_enabled_dirty {
+            // registers are not written while a layer is disabled, so write everything now.
+            self.layer.layer.write_all();
+            self.layer.layer.write_enabled_from_guard();
+        }
+    }
+}
+
+/// The size of an affine layer.
+#[derive(Copy, Clone, Debug)]
+#[repr(u8)]
+pub enum AffineLayerSize {
+    Map128x128,
+    Map256x256,
+    Map512x512,
+    Map1024x1024,
+}
+impl AffineLayerSize {
+    /// Returns the width and height of this layer size in tiles.
+    pub fn tile_length(&self) -> usize {
+        16 << (*self as usize)
+    }
+
+    /// Returns the number of 2KiB tile map blocks used by this layer size.
+    pub fn map_count(&self) -> usize {
+        match self {
+            AffineLayerSize::Map128x128 | AffineLayerSize::Map256x256 => 1,
+            AffineLayerSize::Map512x512 => 2,
+            AffineLayerSize::Map1024x1024 => 8,
+        }
+    }
+}
+
+/// An affine tile layer that is not currently active.
+///
+/// Affine layers can be rotated and scaled, but always use 256-color characters, and their tile
+/// maps contain only one byte per tile.
+#[derive(Debug)]
+pub struct AffineTileLayer {
+    pub(crate) id: LayerId,
+    cnt: BgCnt,
+    matrix: AffineMatrix,
+    ref_x: GbaFrac32,
+    ref_y: GbaFrac32,
+    is_enabled: bool,
+    chars: Option<CharRange>,
+    blocks: Option<ScreenBlocks>,
+}
+impl AffineTileLayer {
+    pub(crate) fn new(id: LayerId) -> AffineTileLayer {
+        AffineTileLayer {
+            id,
+            cnt: BgCnt::default(),
+            matrix: AffineMatrix::IDENTITY,
+            ref_x: GbaFrac32::ZERO,
+            ref_y: GbaFrac32::ZERO,
+            is_enabled: false,
+            chars: None,
+            blocks: None,
+        }
+    }
+
+    fn affine_id(&self) -> usize {
+        self.id as usize - 2
+    }
+    fn write_cnt(&self) {
+        shadow::write_bg_cnt(self.id as usize, self.cnt);
+    }
+    fn write_matrix(&self) {
+        shadow::write_bg_matrix(self.affine_id(), self.matrix);
+    }
+    fn write_reference(&self) {
+        shadow::write_bg_reference(self.affine_id(), self.ref_x, self.ref_y);
+    }
+    fn write_enabled_from_guard(&self) {
+        write_layer_enabled(self.id, self.is_enabled);
+    }
+    fn write_all(&self) {
+        if self.is_enabled {
+            self.write_cnt();
+            self.write_matrix();
+            self.write_reference();
+        }
+    }
+
+    /// Frees the VRAM owned by this layer, and disables it if it owned any.
+    pub(crate) fn release_vram(&mut self) {
+        if self.chars.is_some() || self.blocks.is_some() {
+            self.chars = None;
+            self.blocks = None;
+            self.is_enabled = false;
+        }
+    }
+
+    pub(crate) fn activate(&mut self) -> ActiveAffineTileLayer<'_> {
+        self.write_all();
+        ActiveAffineTileLayer { layer: self }
+    }
+
+    /// Returns a character access appropriate for this layer.
+    ///
+    /// As affine layers always use 256-color characters, the character used for tile `n` in the
+    /// tile map is found at character ID `2 * n`.
+    pub fn char_access(&self) -> CharAccess {
+        let base = VRAM_BASE + 16 * 1024 * self.cnt.char_base();
+        let available_chars = core::cmp::min(512, (VRAM_END - base) / 32);
+        CharAccess::new(base, 0, available_chars)
+    }
+
+    /// Returns a tile map access appropriate for this layer.
+    #[track_caller]
+    pub fn map_access(&self) -> AffineMapAccess {
+        let base = VRAM_BASE + 2048 * self.tile_base();
+        let size = self.tile_map_size();
+        if base + size.tile_length() * size.tile_length() > VRAM_END {
+            affine_map_out_of_bounds();
+        }
+        AffineMapAccess::new(base, 4 + size as usize)
+    }
+
+    /// Whether this layer is enabled.
+    pub fn enabled(&self) -> bool {
+        self.is_enabled
+    }
+
+    /// Sets whether this layer is enabled.
+    pub fn set_enabled(&mut self, enabled: bool) -> &mut Self {
+        self.is_enabled = enabled;
+        self
+    }
+
+    /// The transformation matrix of this layer.
+    pub fn matrix(&self) -> AffineMatrix {
+        self.matrix
+    }
+
+    /// Sets the transformation matrix of this layer.
+    pub fn set_matrix(&mut self, matrix: AffineMatrix) -> &mut Self {
+        self.matrix = matrix;
+        self
+    }
+
+    /// The texture coordinate displayed at the top-left corner of the screen.
+    pub fn reference_point(&self) -> (GbaFrac32, GbaFrac32) {
+        (self.ref_x, self.ref_y)
+    }
+
+    /// Sets the texture coordinate displayed at the top-left corner of the screen.
+    pub fn set_reference_point(&mut self, x: GbaFrac32, y: GbaFrac32) -> &mut Self {
+        self.ref_x = x;
+        self.ref_y = y;
+        self
+    }
+
+    /// Sets the transformation matrix of this layer, and positions the layer such that the texture
+    /// coordinate `texture` is displayed at the screen coordinate `screen`.
+    ///
+    /// This is useful for rotating or scaling a layer around a particular point.
+    pub fn set_transform(
+        &mut self,
+        matrix: AffineMatrix,
+        texture: (GbaFrac32, GbaFrac32),
+        screen: (i16, i16),
+    ) -> &mut Self {
+        let screen_x = GbaFrac32::from_int(screen.0 as i32);
+        let screen_y = GbaFrac32::from_int(screen.1 as i32);
+        let (offset_x, offset_y) = matrix.transform(screen_x, screen_y);
+        self.matrix = matrix;
+        self.ref_x = texture.0 - offset_x;
+        self.ref_y = texture.1 - offset_y;
+        self
+    }
+
+    /// The priority of this layer.
+    ///
+    /// 0 is the highest, and 3 is the lowest.
+    pub fn bg_priority(&self) -> u32 {
+        self.cnt.bg_priority()
+    }
+
+    /// Sets whether the priority of this layer.
+    ///
+    /// 0 is the highest, and 3 is the lowest. This function panics if any other values are used.
+    pub fn set_bg_priority(&mut self, value: u32) -> &mut Self {
+        self.cnt = self.cnt.with_bg_priority(value);
+        self
+    }
+
+    /// The character data base for this layer.
+    ///
+    /// The value must be between 0 and 3, inclusive.
+    pub fn char_base(&self) -> usize {
+        self.cnt.char_base()
+    }
+
+    /// Sets the character data base for this layer.
+    ///
+    /// The value must be between 0 and 3, inclusive. Any character range owned by this layer is
+    /// freed.
+    pub fn set_char_base(&mut self, value: usize) -> &mut Self {
+        self.cnt = self.cnt.with_char_base(value);
+        self.chars = None;
+        self
+    }
+
+    /// Whether the mosaic effect is enabled for this layer.
+    pub fn mosaic_enabled(&self) -> bool {
+        self.cnt.enable_mosaic()
+    }
+
+    /// Sets whether the mosaic effect is enabled for this layer.
+    pub fn set_mosaic_enabled(&mut self, value: bool) -> &mut Self {
+        self.cnt = self.cnt.with_mosaic(value);
+        self
+    }
+
+    /// Whether the tile map repeats outside of its bounds, rather than being transparent.
+    pub fn wraparound(&self) -> bool {
+        self.cnt.affine_wrap()
+    }
+
+    /// Sets whether the tile map repeats outside of its bounds, rather than being transparent.
+    pub fn set_wraparound(&mut self, value: bool) -> &mut Self {
+        self.cnt = self.cnt.with_affine_wrap(value);
+        self
+    }
+
+    /// The tile map data base for this layer.
+    pub fn tile_base(&self) -> usize {
+        self.cnt.tile_map_base()
+    }
+
+    /// Sets the tile map data base for this layer.
+    ///
+    /// Any screen blocks owned by this layer are freed.
+    pub fn set_tile_base(&mut self, value: usize) -> &mut Self {
+        self.cnt = self.cnt.with_tile_map_base(value);
+        self.blocks = None;
+        self
+    }
+
+    /// The character range owned by this layer, if one was set with `set_char_range`.
+    pub fn char_range(&self) -> Option<&CharRange> {
+        self.chars.as_ref()
+    }
+
+    /// Sets the character base of this layer to the one used by an allocated character range.
+    ///
+    /// The layer takes ownership of the range, which is freed when the character base is changed
+    /// again, or when the layer is dropped.
+    pub fn set_char_range(&mut self, chars: CharRange) -> &mut Self {
+        self.set_char_base(chars.char_base());
+        self.chars = Some(chars);
+        self
+    }
+
+    /// The screen blocks owned by this layer, if they were set with `set_screen_blocks`.
+    pub fn screen_blocks(&self) -> Option<&ScreenBlocks> {
+        self.blocks.as_ref()
+    }
+
+    /// Sets the tile map base of this layer to the start of an allocated range of screen blocks.
+    ///
+    /// The layer takes ownership of the screen blocks, which are freed when the tile map base is
+    /// changed again, or when the layer is dropped. This function panics if the range is too small
+    /// for the current tile map size, so the size should be set first.
+    #[track_caller]
+    pub fn set_screen_blocks(&mut self, blocks: ScreenBlocks) -> &mut Self {
+        if blocks.count() < self.tile_map_size().map_count() {
+            screen_blocks_too_small();
+        }
+        self.set_tile_base(blocks.tile_base());
+        self.blocks = Some(blocks);
+        self
+    }
+
+    /// The tile map size for this layer.
+    pub fn tile_map_size(&self) -> AffineLayerSize {
+        match self.cnt.screen_size() {
+            0 => AffineLayerSize::Map128x128,
+            1 => AffineLayerSize::Map256x256,
+            2 => AffineLayerSize::Map512x512,
+            3 => AffineLayerSize::Map1024x1024,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Sets the tile map size for this layer.
+    pub fn set_tile_map_size(&mut self, value: AffineLayerSize) -> &mut Self {
+        self.cnt = self.cnt.with_screen_size(value as u32);
+        self
+    }
+}
+
+#[inline(never)]
+#[track_caller]
+fn screen_blocks_too_small() -> ! {
+    crate::panic_handler::static_panic("Screen blocks are too small for the tile map size!")
+}
+
+#[inline(never)]
+#[track_caller]
+fn affine_map_out_of_bounds() -> ! {
+    crate::panic_handler::static_panic("Affine tile map extends past the end of VRAM!")
+}
+
+#[derive(Debug)]
+pub struct ActiveAffineTileLayer<'a> {
+    layer: &'a mut AffineTileLayer,
+}
+impl<'a> ActiveAffineTileLayer<'a> {
+    pub(crate) fn release_vram(&mut self) {
+        self.layer.release_vram();
+    }
+
+    /// Returns a character access appropriate for this layer.
+    ///
+    /// As affine layers always use 256-color characters, the character used for tile `n` in the
+    /// tile map is found at character ID `2 * n`.
+    pub fn char_access(&self) -> CharAccess {
+        self.layer.char_access()
+    }
+
+    /// Returns a tile map access appropriate for this layer.
+    #[track_caller]
+    pub fn map_access(&self) -> AffineMapAccess {
+        self.layer.map_access()
+    }
+
+    /// Whether this layer is enabled.
+    pub fn enabled(&self) -> bool {
+        self.layer.enabled()
+    }
+
+    /// Sets whether this layer is enabled.
+    pub fn set_enabled<'b>(&'b mut self, enabled: bool) -> ActiveAffineTileLayerEditGuard<'b, 'a> {
+        self.layer.set_enabled(enabled);
+        ActiveAffineTileLayerEditGuard::new(self).mark_enabled_dirty()
+    }
+
+    /// The transformation matrix of this layer.
+    pub fn matrix(&self) -> AffineMatrix {
+        self.layer.matrix()
+    }
+
+    /// Sets the transformation matrix of this layer.
+    pub fn set_matrix<'b>(
+        &'b mut self,
+        matrix: AffineMatrix,
+    ) -> ActiveAffineTileLayerEditGuard<'b, 'a> {
+        self.layer.set_matrix(matrix);
+        ActiveAffineTileLayerEditGuard::new(self).mark_matrix_dirty()
+    }
+
+    /// The texture coordinate displayed at the top-left corner of the screen.
+    pub fn reference_point(&self) -> (GbaFrac32, GbaFrac32) {
+        self.layer.reference_point()
+    }
+
+    /// Sets the texture coordinate displayed at the top-left corner of the screen.
+    pub fn set_reference_point<'b>(
+        &'b mut self,
+        x: GbaFrac32,
+        y: GbaFrac32,
+    ) -> ActiveAffineTileLayerEditGuard<'b, 'a> {
+        self.layer.set_reference_point(x, y);
+        ActiveAffineTileLayerEditGuard::new(self).mark_reference_dirty()
+    }
+
+    /// Sets the transformation matrix of this layer, and positions the layer such that the texture
+    /// coordinate `texture` is displayed at the screen coordinate `screen`.
+    ///
+    /// This is useful for rotating or scaling a layer around a particular point.
+    pub fn set_transform<'b>(
+        &'b mut self,
+        matrix: AffineMatrix,
+        texture: (GbaFrac32, GbaFrac32),
+        screen: (i16, i16),
+    ) -> ActiveAffineTileLayerEditGuard<'b, 'a> {
+        self.layer.set_transform(matrix, texture, screen);
+        ActiveAffineTileLayerEditGuard::new(self)
+            .mark_matrix_dirty()
+            .mark_reference_dirty()
+    }
+
+    /// The priority of this layer.
+    ///
+    /// 0 is the highest, and 3 is the lowest.
+    pub fn bg_priority(&self) -> u32 {
+        self.layer.bg_priority()
+    }
+
+    /// Sets whether the priority of this layer.
+    ///
+    /// 0 is the highest, and 3 is the lowest. This function panics if any other values are used.
+    pub fn set_bg_priority<'b>(
+        &'b mut self,
+        value: u32,
+    ) -> ActiveAffineTileLayerEditGuard<'b, 'a> {
+        self.layer.set_bg_priority(value);
+        ActiveAffineTileLayerEditGuard::new(self).mark_cnt_dirty()
+    }
+
+    /// The character data base for this layer.
+    ///
+    /// The value must be between 0 and 3, inclusive.
+    pub fn char_base(&self) -> usize {
+        self.layer.char_base()
+    }
+
+    /// Sets the character data base for this layer.
+    ///
+    /// The value must be between 0 and 3, inclusive.
+    pub fn set_char_base<'b>(
+        &'b mut self,
+        value: usize,
+    ) -> ActiveAffineTileLayerEditGuard<'b, 'a> {
+        self.layer.set_char_base(value);
+        ActiveAffineTileLayerEditGuard::new(self).mark_cnt_dirty()
+    }
+
+    /// Whether the mosaic effect is enabled for this layer.
+    pub fn mosaic_enabled(&self) -> bool {
+        self.layer.mosaic_enabled()
+    }
+
+    /// Sets whether the mosaic effect is enabled for this layer.
+    pub fn set_mosaic_enabled<'b>(
+        &'b mut self,
+        value: bool,
+    ) -> ActiveAffineTileLayerEditGuard<'b, 'a> {
+        self.layer.set_mosaic_enabled(value);
+        ActiveAffineTileLayerEditGuard::new(self).mark_cnt_dirty()
+    }
+
+    /// Whether the tile map repeats outside of its bounds, rather than being transparent.
+    pub fn wraparound(&self) -> bool {
+        self.layer.wraparound()
+    }
+
+    /// Sets whether the tile map repeats outside of its bounds, rather than being transparent.
+    pub fn set_wraparound<'b>(
+        &'b mut self,
+        value: bool,
+    ) -> ActiveAffineTileLayerEditGuard<'b, 'a> {
+        self.layer.set_wraparound(value);
+        ActiveAffineTileLayerEditGuard::new(self).mark_cnt_dirty()
+    }
+
+    /// The tile map data base for this layer.
+    pub fn tile_base(&self) -> usize {
+        self.layer.tile_base()
+    }
+
+    /// Sets the tile map data base for this layer.
+    pub fn set_tile_base<'b>(
+        &'b mut self,
+        value: usize,
+    ) -> ActiveAffineTileLayerEditGuard<'b, 'a> {
+        self.layer.set_tile_base(value);
+        ActiveAffineTileLayerEditGuard::new(self).mark_cnt_dirty()
+    }
+
+    /// The character range owned by this layer, if one was set with `set_char_range`.
+    pub fn char_range(&self) -> Option<&CharRange> {
+        self.layer.char_range()
+    }
+
+    /// Sets the character base of this layer to the one used by an allocated character range.
+    ///
+    /// The layer takes ownership of the range, which is freed when the character base is changed
+    /// again, or when the layer is dropped.
+    pub fn set_char_range<'b>(
+        &'b mut self,
+        chars: CharRange,
+    ) -> ActiveAffineTileLayerEditGuard<'b, 'a> {
+        self.layer.set_char_range(chars);
+        ActiveAffineTileLayerEditGuard::new(self).mark_cnt_dirty()
+    }
+
+    /// The screen blocks owned by this layer, if they were set with `set_screen_blocks`.
+    pub fn screen_blocks(&self) -> Option<&ScreenBlocks> {
+        self.layer.screen_blocks()
+    }
+
+    /// Sets the tile map base of this layer to the start of an allocated range of screen blocks.
+    ///
+    /// The layer takes ownership of the screen blocks, which are freed when the tile map base is
+    /// changed again, or when the layer is dropped. This function panics if the range is too small
+    /// for the current tile map size.
+    #[track_caller]
+    pub fn set_screen_blocks<'b>(
+        &'b mut self,
+        blocks: ScreenBlocks,
+    ) -> ActiveAffineTileLayerEditGuard<'b, 'a> {
+        self.layer.set_screen_blocks(blocks);
+        ActiveAffineTileLayerEditGuard::new(self).mark_cnt_dirty()
+    }
+
+    /// The tile map size for this layer.
+    pub fn tile_map_size(&self) -> AffineLayerSize {
+        self.layer.tile_map_size()
+    }
+
+    /// Sets the tile map size for this layer.
+    pub fn set_tile_map_size<'b>(
+        &'b mut self,
+        value: AffineLayerSize,
+    ) -> ActiveAffineTileLayerEditGuard<'b, 'a> {
+        self.layer.set_tile_map_size(value);
+        ActiveAffineTileLayerEditGuard::new(self).mark_cnt_dirty()
+    }
+}
+
+/// A temporary guard created to allow chaining operations on a [`ActiveAffineTileLayer`], and
+/// only writing once to the memory mapped IO.
+#[derive(Debug)]
+pub struct ActiveAffineTileLayerEditGuard<'a, 'b: 'a> {
+    layer: &'a mut ActiveAffineTileLayer<'b>,
+    is_cnt_dirty: bool,
+    is_matrix_dirty: bool,
+    is_reference_dirty: bool,
+    is_enabled_dirty: bool,
+}
+impl<'a, 'b: 'a> ActiveAffineTileLayerEditGuard<'a, 'b> {
+    pub(crate) fn new(layer: &'a mut ActiveAffineTileLayer<'b>) -> Self {
+        ActiveAffineTileLayerEditGuard {
+            layer,
+            is_cnt_dirty: false,
+            is_matrix_dirty: false,
+            is_reference_dirty: false,
+            is_enabled_dirty: false,
+        }
+    }
+
+    fn mark_cnt_dirty(mut self) -> Self {
+        self.is_cnt_dirty = true;
+        self
+    }
+    fn mark_matrix_dirty(mut self) -> Self {
+        self.is_matrix_dirty = true;
+        self
+    }
+    fn mark_reference_dirty(mut self) -> Self {
+        self.is_reference_dirty = true;
+        self
+    }
+    fn mark_enabled_dirty(mut self) -> Self {
+        self.is_enabled_dirty = true;
+        self
+    }
+
+    /// Sets whether this layer is enabled.
+    pub fn set_enabled(self, enabled: bool) -> Self {
+        self.layer.layer.set_enabled(enabled);
+        self.mark_enabled_dirty()
+    }
+
+    /// Sets the transformation matrix of this layer.
+    pub fn set_matrix(self, matrix: AffineMatrix) -> Self {
+        self.layer.layer.set_matrix(matrix);
+        self.mark_matrix_dirty()
+    }
+
+    /// Sets the texture coordinate displayed at the top-left corner of the screen.
+    pub fn set_reference_point(self, x: GbaFrac32, y: GbaFrac32) -> Self {
+        self.layer.layer.set_reference_point(x, y);
+        self.mark_reference_dirty()
+    }
+
+    /// Sets the transformation matrix of this layer, and positions the layer such that the texture
+    /// coordinate `texture` is displayed at the screen coordinate `screen`.
+    pub fn set_transform(
+        self,
+        matrix: AffineMatrix,
+        texture: (GbaFrac32, GbaFrac32),
+        screen: (i16, i16),
+    ) -> Self {
+        self.layer.layer.set_transform(matrix, texture, screen);
+        self.mark_matrix_dirty().mark_reference_dirty()
+    }
+
+    /// Sets whether the priority of this layer.
+    ///
+    /// 0 is the highest, and 3 is the lowest. This function panics if any other values are used.
+    pub fn set_bg_priority(self, value: u32) -> Self {
+        self.layer.layer.set_bg_priority(value);
+        self.mark_cnt_dirty()
+    }
+
+    /// Sets the character data base for this layer.
+    ///
+    /// The value must be between 0 and 3, inclusive.
+    pub fn set_char_base(self, value: usize) -> Self {
+        self.layer.layer.set_char_base(value);
+        self.mark_cnt_dirty()
+    }
+
+    /// Sets whether the mosaic effect is enabled for this layer.
+    pub fn set_mosaic_enabled(self, value: bool) -> Self {
+        self.layer.layer.set_mosaic_enabled(value);
+        self.mark_cnt_dirty()
+    }
+
+    /// Sets whether the tile map repeats outside of its bounds, rather than being transparent.
+    pub fn set_wraparound(self, value: bool) -> Self {
+        self.layer.layer.set_wraparound(value);
+        self.mark_cnt_dirty()
+    }
+
+    /// Sets the tile map data base for this layer.
+    pub fn set_tile_base(self, value: usize) -> Self {
+        self.layer.layer.set_tile_base(value);
+        self.mark_cnt_dirty()
+    }
+
+    /// Sets the character base of this layer to the one used by an allocated character range.
+    pub fn set_char_range(self, chars: CharRange) -> Self {
+        self.layer.layer.set_char_range(chars);
+        self.mark_cnt_dirty()
+    }
+
+    /// Sets the tile map base of this layer to the start of an allocated range of screen blocks.
+    ///
+    /// This function panics if the range is too small for the current tile map size.
+    #[track_caller]
+    pub fn set_screen_blocks(self, blocks: ScreenBlocks) -> Self {
+        self.layer.layer.set_screen_blocks(blocks);
+        self.mark_cnt_dirty()
+    }
+
+    /// Sets the tile map size for this layer.
+    pub fn set_tile_map_size(self, value: AffineLayerSize) -> Self {
+        self.layer.layer.set_tile_map_size(value);
+        self.mark_cnt_dirty()
+    }
+}
+impl<'a, 'b: 'a> Drop for ActiveAffineTileLayerEditGuard<'a, 'b> {
+    fn drop(&mut self) {
+        if self.is_cnt_dirty {
+            self.layer.layer.write_cnt();
+        }
+        if self.is_matrix_dirty {
+            self.layer.layer.write_matrix();
+        }
+        if self.is_reference_dirty {
+            self.layer.layer.write_reference();
+        }
+        if self.is_enabled_dirty {
+            // registers are not written while a layer is disabled, so write everything now.
+            self.layer.layer.write_all();
             self.layer.layer.write_enabled_from_guard();
         }
     }
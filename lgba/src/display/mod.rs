@@ -1,42 +1,91 @@
 //! Module containing interfaces to the GBA's graphics chip.
 
+mod affine;
+mod bitmap;
+mod color;
 mod layers;
 mod modes;
+mod shadow;
 mod terminal;
+mod transition;
 mod vram;
 
-use crate::mmio::reg::{DISPSTAT, VCOUNT};
+pub mod animation;
+pub mod blend;
+pub mod double_buffer;
+pub mod mosaic;
+pub mod objects;
+pub mod palette;
+pub mod raster;
+pub mod scanline;
+pub mod streaming;
+pub mod vram_alloc;
+pub mod vwf;
+pub mod window;
 
-pub use layers::{ActiveTileLayer, ActiveTileLayerEditGuard, TileLayer};
+use crate::mmio::{
+    display::DispCnt,
+    reg::{DISPSTAT, VCOUNT},
+};
+
+pub use affine::{AffineMatrix, GbaFrac16, GbaFrac32};
+pub use bitmap::{Bitmap16, Bitmap8};
+pub use color::Color;
+pub use layers::{
+    ActiveAffineTileLayer, ActiveAffineTileLayerEditGuard, ActiveTileLayer,
+    ActiveTileLayerEditGuard, AffineLayerSize, AffineTileLayer, TileLayer, TileLayerSize,
+};
+#[cfg(feature = "terminal_font")]
+#[doc(cfg(feature = "terminal_font"))]
+pub use lgba_macros::terminal_font;
+pub use modes::{
+    ActivateError, ActiveDisplayMode, ActiveMode0, ActiveMode1, ActiveMode2, ActiveMode3,
+    ActiveMode4, ActiveMode5, DisplayMode, Mode0, Mode1, Mode2, Mode3, Mode4, Mode5,
+};
+pub use shadow::{commit, is_commit_pending, wait_commit};
 pub use terminal::{
-    ActiveTerminal, ActiveTerminalAccess, ActiveTerminalWrite, Terminal, TerminalFont,
-    TerminalFontAscii, TerminalFontBasic, TerminalFontFull,
+    widgets, ActiveTerminal, ActiveTerminalAccess, ActiveTerminalWrite, Terminal, TerminalFont,
+    TerminalFontAscii, TerminalFontBasic, TerminalFontFull, TerminalScrollback, TerminalWindow,
+    TerminalWindowWrite,
 };
-pub use vram::{CharAccess, CharData, VramTile};
+pub use vram::{AffineMapAccess, CharAccess, CharData, MapAccess, VramTile};
 
 /// Packs three 5-bit color components into a GBA color.
+///
+/// This is equivalent to [`Color::new`].
 #[inline(always)]
 #[track_caller]
-pub const fn rgb(r: u8, g: u8, b: u8) -> u16 {
-    if r >= 32 || g >= 32 || b >= 32 {
-        color_not_valid()
-    }
-    r as u16 | ((g as u16) << 5) | ((b as u16) << 10)
-}
-#[inline(always)]
-const fn convert_24bpp_15bpp(ch: u8) -> u8 {
-    ch.saturating_add(16) / 8
+pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color::new(r, g, b)
 }
+
 /// Packs three 8-bit color components into a GBA color.
+///
+/// This is equivalent to [`Color::from_rgb24`].
 #[inline(always)]
-pub const fn rgb_24bpp(r: u8, g: u8, b: u8) -> u16 {
-    rgb(convert_24bpp_15bpp(r), convert_24bpp_15bpp(g), convert_24bpp_15bpp(b))
+pub const fn rgb_24bpp(r: u8, g: u8, b: u8) -> Color {
+    Color::from_rgb24(r, g, b)
 }
 
-#[inline(never)]
-#[track_caller]
-const fn color_not_valid() {
-    panic!("Color data must be in the range 0..31");
+/// Runs the display tasks that must happen at the start of VBlank.
+pub(crate) fn on_vblank() {
+    shadow::on_vblank();
+    scanline::on_vblank();
+    streaming::on_vblank();
+    double_buffer::on_vblank();
+    modes::on_vblank();
+    blend::on_vblank();
+    mosaic::on_vblank();
+    objects::on_vblank();
+    palette::on_vblank();
+    animation::on_vblank();
+}
+
+/// Modifies the display control register, preventing interrupts from changing it in the middle.
+///
+/// If the active mode uses deferred commits, this modifies the shadow copy of the register.
+pub(crate) fn edit_disp_cnt(func: impl FnOnce(DispCnt) -> DispCnt) {
+    shadow::edit_disp_cnt(func);
 }
 
 /// Returns whether the graphics chip is currently in a vertical blank period.
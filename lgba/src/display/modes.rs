@@ -1,16 +1,342 @@
 use crate::{
-    display::layers::{ActiveTileLayer, LayerId, TileLayer},
-    mmio::{display::DispMode, reg::DISPCNT},
-    sync::{RawMutex, RawMutexGuard},
+    display::{
+        bitmap::{Bitmap16, Bitmap8},
+        blend::{ActiveBlending, Blending},
+        edit_disp_cnt,
+        layers::{ActiveAffineTileLayer, ActiveTileLayer, AffineTileLayer, LayerId, TileLayer},
+        mosaic::{ActiveMosaic, Mosaic},
+        shadow, vram_alloc,
+        vram_alloc::ScreenBlocks,
+        window::{ActiveWindows, Windows},
+        AffineMatrix,
+    },
+    mmio::{
+        display::{DispCnt, DispMode, GbaFrac32},
+        reg::*,
+    },
+    sync::{RawMutex, RawMutexGuard, Static},
 };
 
 static MAIN_GFX_LOCK: RawMutex = RawMutex::new();
+static PAGE_FLIP_PENDING: Static<bool> = Static::new(false);
 
+/// Flips the displayed page of the bitmap modes if a flip is pending.
+pub(crate) fn on_vblank() {
+    if PAGE_FLIP_PENDING.read() {
+        let disp_cnt = DISPCNT.read();
+        DISPCNT.write(disp_cnt.with_active_frame(disp_cnt.active_frame() ^ 1));
+        PAGE_FLIP_PENDING.write(false);
+    }
+}
+
+pub(crate) mod sealed {
+    pub trait Sealed {
+        /// Whether this mode needs all of background VRAM to be free.
+        fn uses_all_vram(&self) -> bool {
+            false
+        }
+
+        /// Frees the VRAM owned by the layers of this mode, and disables those layers.
+        fn release_layer_vram(&mut self) {}
+    }
+}
+
+/// The reason a display mode could not be activated.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ActivateError {
+    /// Another display mode is already active.
+    ModeActive,
+    /// The mode needs background VRAM that is still allocated elsewhere.
+    VramInUse,
+}
+
+/// A display mode that can be activated.
+///
+/// This allows code such as a screen manager to switch between display modes without knowing
+/// which mode is in use. Only one display mode may be active at a time.
+pub trait DisplayMode: sealed::Sealed {
+    /// The type of this mode while it is active.
+    type Active<'a>: ActiveDisplayMode
+    where Self: 'a;
+
+    /// Activates this mode.
+    ///
+    /// This function panics if another display mode is already active.
+    #[track_caller]
+    fn activate(&mut self) -> Self::Active<'_>;
+
+    /// Activates this mode, or returns an error if it cannot be activated.
+    fn try_activate(&mut self) -> Result<Self::Active<'_>, ActivateError>;
+
+    /// Activates this mode without checking whether another display mode is already active.
+    ///
+    /// This should not be used except in very special circumstances, such as in a panic handler.
+    fn activate_no_lock(&mut self) -> Self::Active<'_>;
+
+    /// Deactivates another display mode, and activates this mode in its place.
+    ///
+    /// The screen is blanked when the previous mode is deactivated, and stays blank while this
+    /// mode is set up. The background layer registers are reset in between, so layers that this
+    /// mode does not use do not keep settings left behind by the previous mode.
+    ///
+    /// The bitmap modes need all of background VRAM, so when switching to one of them, the
+    /// character ranges and screen blocks owned by the layers of the previous mode are freed, and
+    /// those layers are disabled until they are reconfigured. If background VRAM is still
+    /// allocated elsewhere, this returns [`ActivateError::VramInUse`], and the screen stays blank.
+    fn switch_from(
+        &mut self,
+        mut previous: impl ActiveDisplayMode,
+    ) -> Result<Self::Active<'_>, ActivateError> {
+        if self.uses_all_vram() {
+            previous.release_layer_vram();
+        }
+        drop(previous);
+        self.try_activate()
+    }
+}
+
+/// A display mode that is currently active.
+///
+/// The mode is deactivated and the screen is blanked when this value is dropped.
+pub trait ActiveDisplayMode: sealed::Sealed {}
+
+/// Resets the registers of all background layers, so they do not keep the settings of a
+/// previously active mode.
+fn reset_layers() {
+    for i in 0..4 {
+        BG_CNT.index(i).write(Default::default());
+        BG_HOFS.index(i).write(0);
+        BG_VOFS.index(i).write(0);
+    }
+    for i in 0..2 {
+        BG_PA.index(i).write(AffineMatrix::IDENTITY.pa);
+        BG_PB.index(i).write(AffineMatrix::IDENTITY.pb);
+        BG_PC.index(i).write(AffineMatrix::IDENTITY.pc);
+        BG_PD.index(i).write(AffineMatrix::IDENTITY.pd);
+        BG_X.index(i).write(GbaFrac32::ZERO);
+        BG_Y.index(i).write(GbaFrac32::ZERO);
+    }
+}
+
+/// Settings shared between all display modes.
+#[derive(Debug)]
+struct ModeSettings {
+    force_blank: bool,
+    objects_enabled: bool,
+    objects_1d_mapping: bool,
+    deferred_commit: bool,
+}
+impl ModeSettings {
+    fn new() -> Self {
+        ModeSettings {
+            force_blank: false,
+            objects_enabled: false,
+            objects_1d_mapping: true,
+            deferred_commit: false,
+        }
+    }
+
+    fn apply(&self, cnt: DispCnt) -> DispCnt {
+        cnt.with_forced_blank(self.force_blank)
+            .with_display_obj(self.objects_enabled)
+            .with_obj_char_1d(self.objects_1d_mapping)
+    }
+}
+
+macro_rules! mode_settings_inactive {
+    () => {
+        /// Sets whether the screen is forced to a blank state.
+        pub fn set_force_blank(&mut self, force_blank: bool) {
+            self.settings.force_blank = force_blank;
+        }
+
+        /// Whether objects are rendered in this mode.
+        pub fn objects_enabled(&self) -> bool {
+            self.settings.objects_enabled
+        }
+
+        /// Sets whether objects are rendered in this mode.
+        pub fn set_objects_enabled(&mut self, enabled: bool) {
+            self.settings.objects_enabled = enabled;
+        }
+
+        /// Whether object character data is laid out linearly rather than in a 32x32 grid.
+        ///
+        /// This defaults to `true`.
+        pub fn objects_1d_mapping(&self) -> bool {
+            self.settings.objects_1d_mapping
+        }
+
+        /// Sets whether object character data is laid out linearly rather than in a 32x32 grid.
+        pub fn set_objects_1d_mapping(&mut self, value: bool) {
+            self.settings.objects_1d_mapping = value;
+        }
+
+        /// Whether changes to this mode are only displayed after a call to [`commit`].
+        ///
+        /// This defaults to `false`.
+        ///
+        /// [`commit`]: crate::display::commit
+        pub fn deferred_commit(&self) -> bool {
+            self.settings.deferred_commit
+        }
+
+        /// Sets whether changes to this mode are only displayed after a call to [`commit`].
+        ///
+        /// In this mode, changes to the display control register, background layers, windows,
+        /// color effects and mosaic are recorded rather than written immediately, and are written
+        /// together during the VBlank after the next commit. This avoids tearing when the display
+        /// is changed mid-frame. Brightness fades and mosaic transitions are not deferred.
+        ///
+        /// [`commit`]: crate::display::commit
+        pub fn set_deferred_commit(&mut self, value: bool) {
+            self.settings.deferred_commit = value;
+        }
+    };
+}
+macro_rules! mode_settings_active {
+    () => {
+        /// Sets whether the screen is forced to a blank state.
+        pub fn set_force_blank(&mut self, force_blank: bool) {
+            let prev = self.settings.force_blank;
+            self.settings.force_blank = force_blank;
+            if prev != force_blank {
+                edit_disp_cnt(|cnt| cnt.with_forced_blank(force_blank));
+            }
+        }
+
+        /// Whether objects are rendered in this mode.
+        pub fn objects_enabled(&self) -> bool {
+            self.settings.objects_enabled
+        }
+
+        /// Sets whether objects are rendered in this mode.
+        pub fn set_objects_enabled(&mut self, enabled: bool) {
+            self.settings.objects_enabled = enabled;
+            edit_disp_cnt(|cnt| cnt.with_display_obj(enabled));
+        }
+
+        /// Whether object character data is laid out linearly rather than in a 32x32 grid.
+        pub fn objects_1d_mapping(&self) -> bool {
+            self.settings.objects_1d_mapping
+        }
+
+        /// Sets whether object character data is laid out linearly rather than in a 32x32 grid.
+        pub fn set_objects_1d_mapping(&mut self, value: bool) {
+            self.settings.objects_1d_mapping = value;
+            edit_disp_cnt(|cnt| cnt.with_obj_char_1d(value));
+        }
+
+        /// Whether changes to this mode are only displayed after a call to [`commit`].
+        ///
+        /// [`commit`]: crate::display::commit
+        pub fn deferred_commit(&self) -> bool {
+            self.settings.deferred_commit
+        }
+
+        /// Sets whether changes to this mode are only displayed after a call to [`commit`].
+        ///
+        /// Disabling this writes any changes that have not been committed yet immediately.
+        ///
+        /// [`commit`]: crate::display::commit
+        pub fn set_deferred_commit(&mut self, value: bool) {
+            self.settings.deferred_commit = value;
+            shadow::set_deferred(value);
+        }
+    };
+}
+
+macro_rules! mode_activate {
+    ($active:ident) => {
+        /// Activates this mode.
+        ///
+        /// This checks a global lock to avoid situations where two graphics modes are active at
+        /// the same time.
+        #[track_caller]
+        pub fn activate(&mut self) -> $active<'_> {
+            match self.try_activate() {
+                Ok(active_mode) => active_mode,
+                Err(ActivateError::ModeActive) => graphics_in_use(),
+                Err(ActivateError::VramInUse) => bitmap_vram_in_use(),
+            }
+        }
+
+        /// Activates this mode, or returns an error if it cannot be activated.
+        pub fn try_activate(&mut self) -> Result<$active<'_>, ActivateError> {
+            let lock = MAIN_GFX_LOCK.try_lock().ok_or(ActivateError::ModeActive)?;
+            self.activate_checked(Some(lock))
+        }
+
+        /// Activates this mode without locking the screen.
+        ///
+        /// This should not be used except in very special circumstances, such as in a panic
+        /// handler that may need to be called in a context where the graphics are already locked.
+        ///
+        /// There is no risk of memory unsafety while using this, but a great risk of very glitchy
+        /// graphics problems.
+        pub fn activate_no_lock(&mut self) -> $active<'_> {
+            match self.activate_checked(None) {
+                Ok(active_mode) => active_mode,
+                Err(_) => unreachable!(),
+            }
+        }
+
+        fn activate_checked(
+            &mut self,
+            lock: Option<RawMutexGuard<'static>>,
+        ) -> Result<$active<'_>, ActivateError> {
+            reset_layers();
+            let active_mode = self.activate_raw(lock)?;
+            shadow::set_deferred(active_mode.settings.deferred_commit);
+            Ok(active_mode)
+        }
+    };
+}
+macro_rules! display_mode {
+    ($mode:ident, $active:ident) => {
+        impl DisplayMode for $mode {
+            type Active<'a> = $active<'a>;
+
+            #[track_caller]
+            fn activate(&mut self) -> $active<'_> {
+                $mode::activate(self)
+            }
+
+            fn try_activate(&mut self) -> Result<$active<'_>, ActivateError> {
+                $mode::try_activate(self)
+            }
+
+            fn activate_no_lock(&mut self) -> $active<'_> {
+                $mode::activate_no_lock(self)
+            }
+        }
+
+        impl<'a> ActiveDisplayMode for $active<'a> {}
+    };
+}
+macro_rules! mode_drop {
+    ($active:ident) => {
+        impl<'a> Drop for $active<'a> {
+            fn drop(&mut self) {
+                // force blank when there's no active graphics mode
+                shadow::set_deferred(false);
+                PAGE_FLIP_PENDING.write(false);
+                edit_disp_cnt(|cnt| cnt.with_forced_blank(true));
+            }
+        }
+    };
+}
+
+/// A graphics mode with four tile-based background layers.
 #[derive(Debug)]
 #[non_exhaustive]
 pub struct Mode0 {
     pub layers: [TileLayer; 4],
-    force_blank: bool,
+    pub windows: Windows,
+    pub blending: Blending,
+    pub mosaic: Mosaic,
+    settings: ModeSettings,
 }
 impl Mode0 {
     pub fn new() -> Self {
@@ -21,84 +347,413 @@ impl Mode0 {
                 TileLayer::new(LayerId::Layer2),
                 TileLayer::new(LayerId::Layer3),
             ],
-            force_blank: false,
+            windows: Windows::new(),
+            blending: Blending::new(),
+            mosaic: Mosaic::new(),
+            settings: ModeSettings::new(),
         }
     }
 
-    /// Sets whether the screen is forced to a blank state.
-    pub fn set_force_blank(&mut self, force_blank: bool) {
-        self.force_blank = force_blank;
-    }
+    mode_settings_inactive!();
 
-    fn activate_raw(&mut self, lock: Option<RawMutexGuard<'static>>) -> ActiveMode0 {
+    fn activate_raw(
+        &mut self,
+        lock: Option<RawMutexGuard<'static>>,
+    ) -> Result<ActiveMode0<'_>, ActivateError> {
         let [layer0, layer1, layer2, layer3] = &mut self.layers;
-        let new_disp_cnt = DISPCNT
-            .read()
-            .with_mode(DispMode::Mode0)
-            .with_forced_blank(self.force_blank)
-            .with_display_bg0(layer0.enabled())
-            .with_display_bg1(layer1.enabled())
-            .with_display_bg2(layer2.enabled())
-            .with_display_bg3(layer3.enabled());
         let active_mode = ActiveMode0 {
             layers: [layer0.activate(), layer1.activate(), layer2.activate(), layer3.activate()],
-            force_blank: &mut self.force_blank,
+            windows: self.windows.activate(),
+            blending: self.blending.activate(),
+            mosaic: self.mosaic.activate(),
+            settings: &mut self.settings,
             _lock: lock,
         };
-        DISPCNT.write(new_disp_cnt);
-        active_mode
-    }
-
-    /// Activates this mode.
-    ///
-    /// This checks a global lock to avoid situations where two graphics modes are active at the
-    /// same time.
-    #[track_caller]
-    pub fn activate(&mut self) -> ActiveMode0 {
-        let lock = Some(
-            MAIN_GFX_LOCK
-                .try_lock()
-                .unwrap_or_else(|| graphics_in_use()),
-        );
-        self.activate_raw(lock)
+        edit_disp_cnt(|cnt| {
+            let [layer0, layer1, layer2, layer3] = &active_mode.layers;
+            let cnt = active_mode.windows.apply(cnt);
+            active_mode
+                .settings
+                .apply(cnt)
+                .with_mode(DispMode::Mode0)
+                .with_display_bg0(layer0.enabled())
+                .with_display_bg1(layer1.enabled())
+                .with_display_bg2(layer2.enabled())
+                .with_display_bg3(layer3.enabled())
+        });
+        Ok(active_mode)
     }
 
-    /// Activates this mode without locking the screen.
-    ///
-    /// This should not be used except in very special circumstances, such as in a panic handler
-    /// that may need to be called in a context where the graphics are already locked.
-    ///
-    /// There is no risk of memory unsafety while using this, but a great risk of very glitchy
-    /// graphics problems.
-    pub fn activate_no_lock(&mut self) -> ActiveMode0 {
-        self.activate_raw(None)
-    }
+    mode_activate!(ActiveMode0);
 }
 
+/// An active [`Mode0`].
 pub struct ActiveMode0<'a> {
     pub layers: [ActiveTileLayer<'a>; 4],
-    force_blank: &'a mut bool,
+    pub windows: ActiveWindows<'a>,
+    pub blending: ActiveBlending<'a>,
+    pub mosaic: ActiveMosaic<'a>,
+    settings: &'a mut ModeSettings,
     _lock: Option<RawMutexGuard<'static>>,
 }
 impl<'a> ActiveMode0<'a> {
-    /// Sets whether the screen is forced to a blank state.
-    pub fn set_force_blank(&mut self, force_blank: bool) {
-        let prev = *self.force_blank;
-        *self.force_blank = force_blank;
-        if prev != force_blank {
-            DISPCNT.write(DISPCNT.read().with_forced_blank(force_blank));
+    mode_settings_active!();
+}
+mode_drop!(ActiveMode0);
+display_mode!(Mode0, ActiveMode0);
+impl sealed::Sealed for Mode0 {}
+impl<'a> sealed::Sealed for ActiveMode0<'a> {
+    fn release_layer_vram(&mut self) {
+        for layer in &mut self.layers {
+            layer.release_vram();
+        }
+    }
+}
+
+/// A graphics mode with two tile-based background layers, and one affine layer that can be
+/// rotated and scaled.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Mode1 {
+    pub layers: [TileLayer; 2],
+    pub affine_layer: AffineTileLayer,
+    pub windows: Windows,
+    pub blending: Blending,
+    pub mosaic: Mosaic,
+    settings: ModeSettings,
+}
+impl Default for Mode1 {
+    fn default() -> Self {
+        Mode1::new()
+    }
+}
+impl Mode1 {
+    pub fn new() -> Self {
+        Mode1 {
+            layers: [TileLayer::new(LayerId::Layer0), TileLayer::new(LayerId::Layer1)],
+            affine_layer: AffineTileLayer::new(LayerId::Layer2),
+            windows: Windows::new(),
+            blending: Blending::new(),
+            mosaic: Mosaic::new(),
+            settings: ModeSettings::new(),
+        }
+    }
+
+    mode_settings_inactive!();
+
+    fn activate_raw(
+        &mut self,
+        lock: Option<RawMutexGuard<'static>>,
+    ) -> Result<ActiveMode1<'_>, ActivateError> {
+        let [layer0, layer1] = &mut self.layers;
+        let active_mode = ActiveMode1 {
+            layers: [layer0.activate(), layer1.activate()],
+            affine_layer: self.affine_layer.activate(),
+            windows: self.windows.activate(),
+            blending: self.blending.activate(),
+            mosaic: self.mosaic.activate(),
+            settings: &mut self.settings,
+            _lock: lock,
+        };
+        edit_disp_cnt(|cnt| {
+            let [layer0, layer1] = &active_mode.layers;
+            let cnt = active_mode.windows.apply(cnt);
+            active_mode
+                .settings
+                .apply(cnt)
+                .with_mode(DispMode::Mode1)
+                .with_display_bg0(layer0.enabled())
+                .with_display_bg1(layer1.enabled())
+                .with_display_bg2(active_mode.affine_layer.enabled())
+                .with_display_bg3(false)
+        });
+        Ok(active_mode)
+    }
+
+    mode_activate!(ActiveMode1);
+}
+
+/// An active [`Mode1`].
+pub struct ActiveMode1<'a> {
+    pub layers: [ActiveTileLayer<'a>; 2],
+    pub affine_layer: ActiveAffineTileLayer<'a>,
+    pub windows: ActiveWindows<'a>,
+    pub blending: ActiveBlending<'a>,
+    pub mosaic: ActiveMosaic<'a>,
+    settings: &'a mut ModeSettings,
+    _lock: Option<RawMutexGuard<'static>>,
+}
+impl<'a> ActiveMode1<'a> {
+    mode_settings_active!();
+}
+mode_drop!(ActiveMode1);
+display_mode!(Mode1, ActiveMode1);
+impl sealed::Sealed for Mode1 {}
+impl<'a> sealed::Sealed for ActiveMode1<'a> {
+    fn release_layer_vram(&mut self) {
+        for layer in &mut self.layers {
+            layer.release_vram();
+        }
+        self.affine_layer.release_vram();
+    }
+}
+
+/// A graphics mode with two affine layers that can be rotated and scaled.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Mode2 {
+    pub affine_layers: [AffineTileLayer; 2],
+    pub windows: Windows,
+    pub blending: Blending,
+    pub mosaic: Mosaic,
+    settings: ModeSettings,
+}
+impl Default for Mode2 {
+    fn default() -> Self {
+        Mode2::new()
+    }
+}
+impl Mode2 {
+    pub fn new() -> Self {
+        Mode2 {
+            affine_layers: [
+                AffineTileLayer::new(LayerId::Layer2),
+                AffineTileLayer::new(LayerId::Layer3),
+            ],
+            windows: Windows::new(),
+            blending: Blending::new(),
+            mosaic: Mosaic::new(),
+            settings: ModeSettings::new(),
+        }
+    }
+
+    mode_settings_inactive!();
+
+    fn activate_raw(
+        &mut self,
+        lock: Option<RawMutexGuard<'static>>,
+    ) -> Result<ActiveMode2<'_>, ActivateError> {
+        let [layer2, layer3] = &mut self.affine_layers;
+        let active_mode = ActiveMode2 {
+            affine_layers: [layer2.activate(), layer3.activate()],
+            windows: self.windows.activate(),
+            blending: self.blending.activate(),
+            mosaic: self.mosaic.activate(),
+            settings: &mut self.settings,
+            _lock: lock,
+        };
+        edit_disp_cnt(|cnt| {
+            let [layer2, layer3] = &active_mode.affine_layers;
+            let cnt = active_mode.windows.apply(cnt);
+            active_mode
+                .settings
+                .apply(cnt)
+                .with_mode(DispMode::Mode2)
+                .with_display_bg0(false)
+                .with_display_bg1(false)
+                .with_display_bg2(layer2.enabled())
+                .with_display_bg3(layer3.enabled())
+        });
+        Ok(active_mode)
+    }
+
+    mode_activate!(ActiveMode2);
+}
+
+/// An active [`Mode2`].
+pub struct ActiveMode2<'a> {
+    pub affine_layers: [ActiveAffineTileLayer<'a>; 2],
+    pub windows: ActiveWindows<'a>,
+    pub blending: ActiveBlending<'a>,
+    pub mosaic: ActiveMosaic<'a>,
+    settings: &'a mut ModeSettings,
+    _lock: Option<RawMutexGuard<'static>>,
+}
+impl<'a> ActiveMode2<'a> {
+    mode_settings_active!();
+}
+mode_drop!(ActiveMode2);
+display_mode!(Mode2, ActiveMode2);
+impl sealed::Sealed for Mode2 {}
+impl<'a> sealed::Sealed for ActiveMode2<'a> {
+    fn release_layer_vram(&mut self) {
+        for layer in &mut self.affine_layers {
+            layer.release_vram();
         }
     }
 }
-impl<'a> Drop for ActiveMode0<'a> {
-    fn drop(&mut self) {
-        // force blank when there's no active graphics mode
-        DISPCNT.write(DISPCNT.read().with_forced_blank(true));
+
+/// Prepares the background layer used by the bitmap modes, and returns the new display control.
+fn activate_bitmap(settings: &ModeSettings, mode: DispMode, cnt: DispCnt) -> DispCnt {
+    settings
+        .apply(cnt)
+        .with_mode(mode)
+        .with_active_frame(0)
+        .with_display_bg0(false)
+        .with_display_bg1(false)
+        .with_display_bg2(true)
+        .with_display_bg3(false)
+}
+
+macro_rules! bitmap_mode {
+    ($mode:ident, $active:ident, $doc:literal) => {
+        #[doc = $doc]
+        #[derive(Debug)]
+        #[non_exhaustive]
+        pub struct $mode {
+            pub windows: Windows,
+            pub blending: Blending,
+            pub mosaic: Mosaic,
+            settings: ModeSettings,
+        }
+        impl Default for $mode {
+            fn default() -> Self {
+                $mode::new()
+            }
+        }
+        impl $mode {
+            pub fn new() -> Self {
+                $mode {
+                    windows: Windows::new(),
+                    blending: Blending::new(),
+                    mosaic: Mosaic::new(),
+                    settings: ModeSettings::new(),
+                }
+            }
+
+            mode_settings_inactive!();
+
+            fn activate_raw(
+                &mut self,
+                lock: Option<RawMutexGuard<'static>>,
+            ) -> Result<$active<'_>, ActivateError> {
+                // the bitmap overlaps all background VRAM, so it must not be in use elsewhere.
+                let vram = vram_alloc::try_alloc_screen_blocks(vram_alloc::SCREEN_BLOCK_COUNT);
+                if vram.is_none() && lock.is_some() {
+                    return Err(ActivateError::VramInUse);
+                }
+
+                PAGE_FLIP_PENDING.write(false);
+                let active_mode = $active {
+                    windows: self.windows.activate(),
+                    blending: self.blending.activate(),
+                    mosaic: self.mosaic.activate(),
+                    settings: &mut self.settings,
+                    _vram: vram,
+                    _lock: lock,
+                };
+                edit_disp_cnt(|cnt| {
+                    let cnt = active_mode.windows.apply(cnt);
+                    activate_bitmap(active_mode.settings, DispMode::$mode, cnt)
+                });
+                Ok(active_mode)
+            }
+
+            mode_activate!($active);
+        }
+
+        #[doc = concat!("An active [`", stringify!($mode), "`].")]
+        pub struct $active<'a> {
+            pub windows: ActiveWindows<'a>,
+            pub blending: ActiveBlending<'a>,
+            pub mosaic: ActiveMosaic<'a>,
+            settings: &'a mut ModeSettings,
+            _vram: Option<ScreenBlocks>,
+            _lock: Option<RawMutexGuard<'static>>,
+        }
+        mode_drop!($active);
+        display_mode!($mode, $active);
+        impl sealed::Sealed for $mode {
+            fn uses_all_vram(&self) -> bool {
+                true
+            }
+        }
+        impl<'a> sealed::Sealed for $active<'a> {}
+    };
+}
+macro_rules! page_flip_ops {
+    ($bitmap:ident, $width:literal, $height:literal) => {
+        fn wait_flip(&self) {
+            while PAGE_FLIP_PENDING.read() {
+                crate::sys::wait_for_vblank();
+            }
+        }
+        fn page_base(page: usize) -> usize {
+            VRAM_BASE + page * 0xA000
+        }
+
+        /// Returns the page that is currently not displayed.
+        ///
+        /// If a flip is pending, this waits until the next VBlank for it to complete.
+        pub fn back_buffer(&mut self) -> $bitmap<'_> {
+            self.wait_flip();
+            let page = DISPCNT.read().active_frame() ^ 1;
+            $bitmap::new(Self::page_base(page), $width, $height)
+        }
+
+        /// Returns the page that is currently displayed.
+        ///
+        /// If a flip is pending, this waits until the next VBlank for it to complete.
+        pub fn front_buffer(&mut self) -> $bitmap<'_> {
+            self.wait_flip();
+            let page = DISPCNT.read().active_frame();
+            $bitmap::new(Self::page_base(page), $width, $height)
+        }
+
+        /// Swaps the displayed page with the back buffer during the next VBlank.
+        pub fn flip(&mut self) {
+            PAGE_FLIP_PENDING.write(true);
+        }
+
+        /// Returns whether a flip is still waiting for the next VBlank.
+        pub fn is_flip_pending(&self) -> bool {
+            PAGE_FLIP_PENDING.read()
+        }
+    };
+}
+
+bitmap_mode!(
+    Mode3,
+    ActiveMode3,
+    "A graphics mode with a single full-resolution 16bpp bitmap."
+);
+impl<'a> ActiveMode3<'a> {
+    mode_settings_active!();
+
+    /// Returns the framebuffer of this mode.
+    pub fn framebuffer(&mut self) -> Bitmap16<'_> {
+        Bitmap16::new(VRAM_BASE, 240, 160)
     }
 }
 
+bitmap_mode!(
+    Mode4,
+    ActiveMode4,
+    "A graphics mode with a double-buffered full-resolution paletted 8bpp bitmap."
+);
+impl<'a> ActiveMode4<'a> {
+    mode_settings_active!();
+    page_flip_ops!(Bitmap8, 240, 160);
+}
+
+bitmap_mode!(
+    Mode5,
+    ActiveMode5,
+    "A graphics mode with a double-buffered 160x128 16bpp bitmap."
+);
+impl<'a> ActiveMode5<'a> {
+    mode_settings_active!();
+    page_flip_ops!(Bitmap16, 160, 128);
+}
+
 #[inline(never)]
 #[track_caller]
 fn graphics_in_use() -> ! {
     crate::panic_handler::static_panic("A graphics mode is already activated!")
 }
+
+#[inline(never)]
+#[track_caller]
+fn bitmap_vram_in_use() -> ! {
+    crate::panic_handler::static_panic("Bitmap modes require all background VRAM to be free!")
+}
@@ -0,0 +1,242 @@
+//! Module for controlling the mosaic effect of the display.
+//!
+//! The mosaic effect enlarges pixels into blocks, giving the screen a pixelated look. The block
+//! size is shared by all layers, and separately by all objects, but the effect is only applied to
+//! layers and objects that enable it (for example, with [`TileLayer::set_mosaic_enabled`] or
+//! [`Object::set_mosaic_enabled`]).
+//!
+//! The block size can also be animated over a number of frames, which is done by the VBlank
+//! interrupt while the program continues running.
+//!
+//! [`TileLayer::set_mosaic_enabled`]: crate::display::TileLayer::set_mosaic_enabled
+//! [`Object::set_mosaic_enabled`]: crate::display::objects::Object::set_mosaic_enabled
+
+use crate::{
+    display::{shadow, transition::Transition},
+    mmio::{display::Mosaic as MosaicCnt, reg::*},
+    sync::Static,
+};
+
+static TRANSITION: Static<Option<Transition>> = Static::new(None);
+
+/// Updates the mosaic size if a transition is in progress.
+pub(crate) fn on_vblank() {
+    if let Some(mut transition) = TRANSITION.read() {
+        let (size, done) = transition.step();
+        MOSAIC.write(square_mosaic(size));
+        TRANSITION.write(if done { None } else { Some(transition) });
+    }
+}
+
+/// The largest supported mosaic block size.
+pub const MAX_MOSAIC_SIZE: u8 = 16;
+
+fn square_mosaic(size: u8) -> MosaicCnt {
+    let size = size as u32 - 1;
+    MosaicCnt::default()
+        .with_bg_mosaic_x(size)
+        .with_bg_mosaic_y(size)
+        .with_obj_mosaic_x(size)
+        .with_obj_mosaic_y(size)
+}
+
+/// The mosaic configuration of a display mode that is not currently active.
+///
+/// By default, the block size is 1x1, which leaves the image unchanged.
+#[derive(Debug)]
+pub struct Mosaic {
+    cnt: MosaicCnt,
+}
+impl Mosaic {
+    pub(crate) fn new() -> Self {
+        Mosaic { cnt: MosaicCnt::default() }
+    }
+
+    fn write_cnt(&self) {
+        TRANSITION.write(None);
+        shadow::write_mosaic(self.cnt);
+    }
+
+    pub(crate) fn activate(&mut self) -> ActiveMosaic<'_> {
+        self.write_cnt();
+        ActiveMosaic { mosaic: self }
+    }
+
+    /// Returns the width and height of the mosaic blocks used for background layers.
+    ///
+    /// If a transition is in progress, this returns the size the transition ends at.
+    pub fn bg_size(&self) -> (u8, u8) {
+        (self.cnt.bg_mosaic_x() as u8 + 1, self.cnt.bg_mosaic_y() as u8 + 1)
+    }
+
+    /// Sets the width and height of the mosaic blocks used for background layers.
+    ///
+    /// Both must be between 1 and 16 inclusive.
+    #[track_caller]
+    pub fn set_bg_size(&mut self, width: u8, height: u8) -> &mut Self {
+        check_size(width, height);
+        self.cnt = self
+            .cnt
+            .with_bg_mosaic_x(width as u32 - 1)
+            .with_bg_mosaic_y(height as u32 - 1);
+        self
+    }
+
+    /// Returns the width and height of the mosaic blocks used for objects.
+    ///
+    /// If a transition is in progress, this returns the size the transition ends at.
+    pub fn obj_size(&self) -> (u8, u8) {
+        (self.cnt.obj_mosaic_x() as u8 + 1, self.cnt.obj_mosaic_y() as u8 + 1)
+    }
+
+    /// Sets the width and height of the mosaic blocks used for objects.
+    ///
+    /// Both must be between 1 and 16 inclusive.
+    #[track_caller]
+    pub fn set_obj_size(&mut self, width: u8, height: u8) -> &mut Self {
+        check_size(width, height);
+        self.cnt = self
+            .cnt
+            .with_obj_mosaic_x(width as u32 - 1)
+            .with_obj_mosaic_y(height as u32 - 1);
+        self
+    }
+}
+
+/// The mosaic configuration of an active display mode.
+#[derive(Debug)]
+pub struct ActiveMosaic<'a> {
+    mosaic: &'a mut Mosaic,
+}
+impl<'a> ActiveMosaic<'a> {
+    /// Returns the width and height of the mosaic blocks used for background layers.
+    ///
+    /// If a transition is in progress, this returns the size the transition ends at.
+    pub fn bg_size(&self) -> (u8, u8) {
+        self.mosaic.bg_size()
+    }
+
+    /// Sets the width and height of the mosaic blocks used for background layers, stopping any
+    /// transition in progress.
+    ///
+    /// Both must be between 1 and 16 inclusive.
+    #[track_caller]
+    pub fn set_bg_size<'b>(&'b mut self, width: u8, height: u8) -> ActiveMosaicEditGuard<'b, 'a> {
+        self.mosaic.set_bg_size(width, height);
+        ActiveMosaicEditGuard::new(self)
+    }
+
+    /// Returns the width and height of the mosaic blocks used for objects.
+    ///
+    /// If a transition is in progress, this returns the size the transition ends at.
+    pub fn obj_size(&self) -> (u8, u8) {
+        self.mosaic.obj_size()
+    }
+
+    /// Sets the width and height of the mosaic blocks used for objects, stopping any transition
+    /// in progress.
+    ///
+    /// Both must be between 1 and 16 inclusive.
+    #[track_caller]
+    pub fn set_obj_size<'b>(&'b mut self, width: u8, height: u8) -> ActiveMosaicEditGuard<'b, 'a> {
+        self.mosaic.set_obj_size(width, height);
+        ActiveMosaicEditGuard::new(self)
+    }
+
+    /// Animates the mosaic blocks of both background layers and objects to a given square size
+    /// over a number of frames.
+    ///
+    /// The animation starts from the current background block width, or continues from where any
+    /// transition already in progress stopped.
+    #[track_caller]
+    pub fn transition_size(&mut self, size: u8, frames: u16) {
+        let from = TRANSITION
+            .read()
+            .map_or(self.mosaic.bg_size().0, |t| t.current());
+        self.mosaic.set_bg_size(size, size).set_obj_size(size, size);
+        TRANSITION.write(Some(Transition::new(from, size, frames)));
+    }
+
+    /// Pixelates the screen, growing the mosaic blocks to their largest size over a number of
+    /// frames.
+    pub fn pixelate_out(&mut self, frames: u16) {
+        self.transition_size(MAX_MOSAIC_SIZE, frames);
+    }
+
+    /// Removes the pixelation of the screen, shrinking the mosaic blocks from their largest size
+    /// over a number of frames.
+    ///
+    /// If no transition is in progress, this starts from the largest block size.
+    pub fn pixelate_in(&mut self, frames: u16) {
+        if TRANSITION.read().is_none() {
+            self.set_bg_size(MAX_MOSAIC_SIZE, MAX_MOSAIC_SIZE)
+                .set_obj_size(MAX_MOSAIC_SIZE, MAX_MOSAIC_SIZE);
+        }
+        self.transition_size(1, frames);
+    }
+
+    /// Returns whether a transition is currently in progress.
+    pub fn is_transitioning(&self) -> bool {
+        TRANSITION.read().is_some()
+    }
+
+    /// Waits until the current transition is complete.
+    pub fn wait_transition(&self) {
+        while self.is_transitioning() {
+            crate::sys::wait_for_vblank();
+        }
+    }
+}
+impl<'a> Drop for ActiveMosaic<'a> {
+    fn drop(&mut self) {
+        TRANSITION.write(None);
+    }
+}
+
+/// A temporary guard created to allow chaining operations on [`ActiveMosaic`], and only writing
+/// once to the memory mapped IO.
+#[derive(Debug)]
+pub struct ActiveMosaicEditGuard<'a, 'b: 'a> {
+    mosaic: &'a mut ActiveMosaic<'b>,
+}
+impl<'a, 'b: 'a> ActiveMosaicEditGuard<'a, 'b> {
+    fn new(mosaic: &'a mut ActiveMosaic<'b>) -> Self {
+        ActiveMosaicEditGuard { mosaic }
+    }
+
+    /// Sets the width and height of the mosaic blocks used for background layers.
+    ///
+    /// Both must be between 1 and 16 inclusive.
+    #[track_caller]
+    pub fn set_bg_size(self, width: u8, height: u8) -> Self {
+        self.mosaic.mosaic.set_bg_size(width, height);
+        self
+    }
+
+    /// Sets the width and height of the mosaic blocks used for objects.
+    ///
+    /// Both must be between 1 and 16 inclusive.
+    #[track_caller]
+    pub fn set_obj_size(self, width: u8, height: u8) -> Self {
+        self.mosaic.mosaic.set_obj_size(width, height);
+        self
+    }
+}
+impl<'a, 'b: 'a> Drop for ActiveMosaicEditGuard<'a, 'b> {
+    fn drop(&mut self) {
+        self.mosaic.mosaic.write_cnt();
+    }
+}
+
+#[track_caller]
+fn check_size(width: u8, height: u8) {
+    if width == 0 || height == 0 || width > MAX_MOSAIC_SIZE || height > MAX_MOSAIC_SIZE {
+        mosaic_size_out_of_range();
+    }
+}
+
+#[inline(never)]
+#[track_caller]
+fn mosaic_size_out_of_range() -> ! {
+    crate::panic_handler::static_panic("Mosaic sizes must be between 1 and 16!")
+}
@@ -0,0 +1,615 @@
+//! Module for rendering objects (also known as sprites).
+//!
+//! Objects are edited through handles allocated from an [`ObjectTable`]. Changes are made to a
+//! copy of the object attribute memory kept in RAM, and only become visible once
+//! [`ObjectTable::commit`] is called, at which point they are copied to the hardware during the
+//! next VBlank.
+//!
+//! Objects may be rotated and scaled by pointing them at an [`ObjectMatrix`], which is allocated
+//! from the same table. Up to 32 matrices may be used at once, and each may be shared between any
+//! number of objects.
+//!
+//! Objects are only displayed when enabled in the active display mode (for example, with
+//! [`ActiveMode0::set_objects_enabled`]).
+//!
+//! [`ActiveMode0::set_objects_enabled`]: crate::display::ActiveMode0::set_objects_enabled
+
+use crate::{
+    display::{vram::copy_volatile, AffineMatrix, CharAccess, GbaFrac16},
+    dma::DmaChannelId,
+    mmio::{
+        display::{ObjAttr0, ObjAttr1, ObjAttr2, ObjShape},
+        reg::{OAM_BASE, VRAM_OBJ_BASE, VRAM_OBJ_END},
+    },
+    sync::{Mutex, RawMutex, RawMutexGuard, Static},
+};
+use core::{ffi::c_void, marker::PhantomData};
+
+#[doc(inline)]
+pub use crate::mmio::display::ObjMode as ObjectMode;
+
+/// The number of objects supported by the hardware.
+pub const OBJECT_COUNT: usize = 128;
+
+/// The number of object affine matrices supported by the hardware.
+pub const MATRIX_COUNT: usize = 32;
+
+/// A single entry in the object attribute memory.
+#[derive(Copy, Clone, Debug, Default)]
+#[repr(C, align(4))]
+struct OamEntry {
+    attr0: ObjAttr0,
+    attr1: ObjAttr1,
+    attr2: ObjAttr2,
+    affine: i16,
+}
+impl OamEntry {
+    // an entry with rotation disabled and the disable flag set
+    const HIDDEN: OamEntry = unsafe { core::mem::transmute([0x200u16, 0, 0, 0]) };
+}
+
+struct ObjectTableState {
+    entries: [OamEntry; OBJECT_COUNT],
+    allocated: u128,
+    matrices_allocated: u32,
+}
+impl ObjectTableState {
+    fn set_matrix(&mut self, id: u8, matrix: &AffineMatrix) {
+        // the matrix parameters are interleaved with the object attributes
+        let base = id as usize * 4;
+        self.entries[base].affine = matrix.pa.to_raw();
+        self.entries[base + 1].affine = matrix.pb.to_raw();
+        self.entries[base + 2].affine = matrix.pc.to_raw();
+        self.entries[base + 3].affine = matrix.pd.to_raw();
+    }
+}
+
+static OBJECT_TABLE_LOCK: RawMutex = RawMutex::new();
+static OBJECT_STATE: Mutex<ObjectTableState> = Mutex::new(ObjectTableState {
+    entries: [OamEntry::HIDDEN; OBJECT_COUNT],
+    allocated: 0,
+    matrices_allocated: 0,
+});
+static COMMIT_PENDING: Static<bool> = Static::new(false);
+static COMMIT_DMA_CHANNEL: Static<Option<DmaChannelId>> = Static::new(None);
+
+/// Copies the object table into OAM if a commit is pending.
+///
+/// If the table is currently being edited, the commit is retried on the next VBlank.
+pub(crate) fn on_vblank() {
+    if !COMMIT_PENDING.read() {
+        return;
+    }
+    if let Some(state) = OBJECT_STATE.try_lock() {
+        let channel = COMMIT_DMA_CHANNEL.read().and_then(|x| x.try_create());
+        if let Some(mut channel) = channel {
+            unsafe {
+                channel.unsafe_transfer(
+                    state.entries.as_ptr() as *const c_void,
+                    OAM_BASE as *mut c_void,
+                    OBJECT_COUNT * 8,
+                );
+            }
+        } else {
+            unsafe {
+                copy_volatile(
+                    state.entries.as_ptr() as *const u32,
+                    OAM_BASE as *mut u32,
+                    OBJECT_COUNT * 2,
+                );
+            }
+        }
+        COMMIT_PENDING.write(false);
+    }
+}
+
+/// The size of an object, in pixels.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub enum ObjectSize {
+    Size8x8,
+    Size16x16,
+    Size32x32,
+    Size64x64,
+    Size16x8,
+    Size32x8,
+    Size32x16,
+    Size64x32,
+    Size8x16,
+    Size8x32,
+    Size16x32,
+    Size32x64,
+}
+impl ObjectSize {
+    fn shape_and_size(self) -> (ObjShape, u32) {
+        match self {
+            ObjectSize::Size8x8 => (ObjShape::Square, 0),
+            ObjectSize::Size16x16 => (ObjShape::Square, 1),
+            ObjectSize::Size32x32 => (ObjShape::Square, 2),
+            ObjectSize::Size64x64 => (ObjShape::Square, 3),
+            ObjectSize::Size16x8 => (ObjShape::Horizontal, 0),
+            ObjectSize::Size32x8 => (ObjShape::Horizontal, 1),
+            ObjectSize::Size32x16 => (ObjShape::Horizontal, 2),
+            ObjectSize::Size64x32 => (ObjShape::Horizontal, 3),
+            ObjectSize::Size8x16 => (ObjShape::Vertical, 0),
+            ObjectSize::Size8x32 => (ObjShape::Vertical, 1),
+            ObjectSize::Size16x32 => (ObjShape::Vertical, 2),
+            ObjectSize::Size32x64 => (ObjShape::Vertical, 3),
+        }
+    }
+
+    fn from_shape_and_size(shape: ObjShape, size: u32) -> Self {
+        match (shape, size) {
+            (ObjShape::Square, 0) => ObjectSize::Size8x8,
+            (ObjShape::Square, 1) => ObjectSize::Size16x16,
+            (ObjShape::Square, 2) => ObjectSize::Size32x32,
+            (ObjShape::Square, _) => ObjectSize::Size64x64,
+            (ObjShape::Horizontal, 0) => ObjectSize::Size16x8,
+            (ObjShape::Horizontal, 1) => ObjectSize::Size32x8,
+            (ObjShape::Horizontal, 2) => ObjectSize::Size32x16,
+            (ObjShape::Horizontal, _) => ObjectSize::Size64x32,
+            (ObjShape::Vertical, 0) => ObjectSize::Size8x16,
+            (ObjShape::Vertical, 1) => ObjectSize::Size8x32,
+            (ObjShape::Vertical, 2) => ObjectSize::Size16x32,
+            (ObjShape::Vertical, _) => ObjectSize::Size32x64,
+        }
+    }
+
+    /// Returns the width and height of this size in pixels.
+    pub fn dimensions(self) -> (u32, u32) {
+        match self {
+            ObjectSize::Size8x8 => (8, 8),
+            ObjectSize::Size16x16 => (16, 16),
+            ObjectSize::Size32x32 => (32, 32),
+            ObjectSize::Size64x64 => (64, 64),
+            ObjectSize::Size16x8 => (16, 8),
+            ObjectSize::Size32x8 => (32, 8),
+            ObjectSize::Size32x16 => (32, 16),
+            ObjectSize::Size64x32 => (64, 32),
+            ObjectSize::Size8x16 => (8, 16),
+            ObjectSize::Size8x32 => (8, 32),
+            ObjectSize::Size16x32 => (16, 32),
+            ObjectSize::Size32x64 => (32, 64),
+        }
+    }
+
+    /// Returns the number of 8x8 characters an object of this size uses.
+    pub fn char_count(self) -> usize {
+        let (width, height) = self.dimensions();
+        ((width / 8) * (height / 8)) as usize
+    }
+}
+
+/// The object attribute table.
+///
+/// Only one object table may exist at a time.
+#[derive(Debug)]
+pub struct ObjectTable {
+    _lock: RawMutexGuard<'static>,
+}
+impl Default for ObjectTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl ObjectTable {
+    /// Creates the object table, hiding all objects.
+    ///
+    /// This function panics if an object table already exists.
+    #[track_caller]
+    pub fn new() -> Self {
+        let lock = OBJECT_TABLE_LOCK
+            .try_lock()
+            .unwrap_or_else(|| object_table_in_use());
+        {
+            let mut state = OBJECT_STATE.lock();
+            state.entries = [OamEntry::HIDDEN; OBJECT_COUNT];
+            state.allocated = 0;
+            state.matrices_allocated = 0;
+        }
+        COMMIT_DMA_CHANNEL.write(None);
+        COMMIT_PENDING.write(true);
+        ObjectTable { _lock: lock }
+    }
+
+    /// Sets the DMA channel used to copy the object table into OAM.
+    ///
+    /// If no channel is set, or the channel is in use when the copy happens, the CPU is used
+    /// instead.
+    pub fn use_dma_channel(&mut self, id: DmaChannelId) {
+        COMMIT_DMA_CHANNEL.write(Some(id));
+    }
+
+    /// Returns a character access for object character data.
+    ///
+    /// Note that in the bitmap display modes, only characters 512 and later are usable.
+    pub fn char_access(&self) -> CharAccess {
+        CharAccess::new(VRAM_OBJ_BASE, 0, (VRAM_OBJ_END - VRAM_OBJ_BASE) / 32)
+    }
+
+    /// Allocates a new object, or returns `None` if all objects are in use.
+    ///
+    /// The object is initially hidden.
+    pub fn try_alloc(&self) -> Option<Object<'_>> {
+        let mut state = OBJECT_STATE.lock();
+        let free = !state.allocated;
+        if free == 0 {
+            return None;
+        }
+        let id = free.trailing_zeros() as u8;
+        state.allocated |= 1 << id;
+        state.entries[id as usize] = OamEntry::HIDDEN;
+        Some(Object {
+            id,
+            attr0: ObjAttr0::default(),
+            attr1: ObjAttr1::default(),
+            attr2: ObjAttr2::default(),
+            x: 0,
+            y: 0,
+            visible: false,
+            matrix: None,
+            double_size: false,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Allocates a new object.
+    ///
+    /// The object is initially hidden. This function panics if all objects are in use.
+    #[track_caller]
+    pub fn alloc(&self) -> Object<'_> {
+        self.try_alloc().unwrap_or_else(|| object_table_full())
+    }
+
+    /// Allocates a new affine matrix, or returns `None` if all matrices are in use.
+    ///
+    /// The matrix is initially set to [`AffineMatrix::IDENTITY`].
+    pub fn try_alloc_matrix(&self) -> Option<ObjectMatrix<'_>> {
+        let mut state = OBJECT_STATE.lock();
+        let free = !state.matrices_allocated;
+        if free == 0 {
+            return None;
+        }
+        let id = free.trailing_zeros() as u8;
+        state.matrices_allocated |= 1 << id;
+        state.set_matrix(id, &AffineMatrix::IDENTITY);
+        Some(ObjectMatrix { id, matrix: AffineMatrix::IDENTITY, _phantom: PhantomData })
+    }
+
+    /// Allocates a new affine matrix.
+    ///
+    /// The matrix is initially set to [`AffineMatrix::IDENTITY`]. This function panics if all
+    /// matrices are in use.
+    #[track_caller]
+    pub fn alloc_matrix(&self) -> ObjectMatrix<'_> {
+        self.try_alloc_matrix()
+            .unwrap_or_else(|| object_matrices_full())
+    }
+
+    /// Returns the number of objects that have been allocated.
+    pub fn allocated_count(&self) -> usize {
+        OBJECT_STATE.lock().allocated.count_ones() as usize
+    }
+
+    /// Copies the current state of the object table into OAM during the next VBlank.
+    ///
+    /// This should be called once all changes for a frame have been made. Changes made after this
+    /// function is called but before the next VBlank may or may not be included.
+    pub fn commit(&self) {
+        COMMIT_PENDING.write(true);
+    }
+
+    /// Returns whether a commit is still waiting for the next VBlank.
+    pub fn is_commit_pending(&self) -> bool {
+        COMMIT_PENDING.read()
+    }
+}
+impl Drop for ObjectTable {
+    fn drop(&mut self) {
+        // hide any leftover objects from the screen
+        OBJECT_STATE.lock().entries = [OamEntry::HIDDEN; OBJECT_COUNT];
+        COMMIT_PENDING.write(true);
+    }
+}
+
+/// A handle to an object allocated from an [`ObjectTable`].
+///
+/// The object is hidden and freed when this handle is dropped.
+#[derive(Debug)]
+pub struct Object<'a> {
+    id: u8,
+    attr0: ObjAttr0,
+    attr1: ObjAttr1,
+    attr2: ObjAttr2,
+    x: i16,
+    y: i16,
+    visible: bool,
+    matrix: Option<u8>,
+    double_size: bool,
+    _phantom: PhantomData<&'a ObjectTable>,
+}
+impl<'a> Object<'a> {
+    fn write_attrs(&self) {
+        let mut attr0 = self.attr0;
+        let mut attr1 = self.attr1;
+        if !self.visible {
+            attr0 = ObjAttr0::default().with_disabled(true);
+        } else if let Some(matrix) = self.matrix {
+            // the matrix ID overlaps the flip flags, which affine objects do not support
+            attr0 = attr0
+                .with_rotation_enabled(true)
+                .with_double_size(self.double_size);
+            attr1 = attr1
+                .with_h_flip(false)
+                .with_v_flip(false)
+                .with_rotation_id(matrix as usize);
+        }
+        let mut state = OBJECT_STATE.lock();
+        let entry = &mut state.entries[self.id as usize];
+        entry.attr0 = attr0;
+        entry.attr1 = attr1;
+        entry.attr2 = self.attr2;
+    }
+
+    /// Returns the index of this object in OAM.
+    ///
+    /// Objects with lower indexes are drawn above objects with higher indexes and the same
+    /// priority.
+    pub fn id(&self) -> usize {
+        self.id as usize
+    }
+
+    /// Whether this object is visible.
+    pub fn visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Sets whether this object is visible.
+    pub fn set_visible(&mut self, visible: bool) -> &mut Self {
+        self.visible = visible;
+        self.write_attrs();
+        self
+    }
+
+    /// Returns the position of the top-left corner of this object.
+    pub fn position(&self) -> (i16, i16) {
+        (self.x, self.y)
+    }
+
+    /// Sets the position of the top-left corner of this object.
+    ///
+    /// The hardware wraps coordinates, so objects positioned partially off the top or left of the
+    /// screen are displayed correctly.
+    pub fn set_position(&mut self, x: i16, y: i16) -> &mut Self {
+        self.x = x;
+        self.y = y;
+        self.attr0 = self.attr0.with_y_coordinate(y as u32 & 0xFF);
+        self.attr1 = self.attr1.with_x_coordinate(x as u32 & 0x1FF);
+        self.write_attrs();
+        self
+    }
+
+    /// The size of this object.
+    pub fn size(&self) -> ObjectSize {
+        ObjectSize::from_shape_and_size(self.attr0.obj_shape(), self.attr1.obj_size())
+    }
+
+    /// Sets the size of this object.
+    pub fn set_size(&mut self, size: ObjectSize) -> &mut Self {
+        let (shape, size) = size.shape_and_size();
+        self.attr0 = self.attr0.with_obj_shape(shape);
+        self.attr1 = self.attr1.with_obj_size(size);
+        self.write_attrs();
+        self
+    }
+
+    /// The ID of the first character used by this object.
+    pub fn tile(&self) -> u16 {
+        self.attr2.char()
+    }
+
+    /// Sets the ID of the first character used by this object.
+    ///
+    /// This must be a number between 0-1023, and must be even for 256-color objects.
+    pub fn set_tile(&mut self, tile: u16) -> &mut Self {
+        self.attr2 = self.attr2.with_char(tile);
+        self.write_attrs();
+        self
+    }
+
+    /// The ID of the palette used by this object.
+    pub fn palette(&self) -> u8 {
+        self.attr2.palette()
+    }
+
+    /// Sets the ID of the palette used by this object.
+    ///
+    /// This must be a number between 0-15, and is ignored for 256-color objects.
+    pub fn set_palette(&mut self, palette: u8) -> &mut Self {
+        self.attr2 = self.attr2.with_palette(palette);
+        self.write_attrs();
+        self
+    }
+
+    /// Returns the ID of the affine matrix used by this object, if any.
+    pub fn matrix(&self) -> Option<usize> {
+        self.matrix.map(|x| x as usize)
+    }
+
+    /// Sets the affine matrix used to rotate and scale this object.
+    ///
+    /// When a matrix is set, the horizontal and vertical flip settings are ignored. If the matrix
+    /// is dropped while this object still uses it, the object is drawn with the identity matrix
+    /// until another matrix is allocated in its place.
+    pub fn set_matrix(&mut self, matrix: Option<&ObjectMatrix>) -> &mut Self {
+        self.matrix = matrix.map(|x| x.id);
+        self.write_attrs();
+        self
+    }
+
+    /// Whether this object is drawn in double-size mode.
+    pub fn double_size(&self) -> bool {
+        self.double_size
+    }
+
+    /// Sets whether this object is drawn in double-size mode.
+    ///
+    /// This doubles the area the object is drawn in without changing its scale, preventing
+    /// rotated or enlarged objects from being clipped. The object's position still refers to the
+    /// top-left corner of this larger area. This has no effect on objects without a matrix.
+    pub fn set_double_size(&mut self, double_size: bool) -> &mut Self {
+        self.double_size = double_size;
+        self.write_attrs();
+        self
+    }
+
+    /// Whether this object is flipped horizontally.
+    pub fn h_flip(&self) -> bool {
+        self.attr1.h_flip()
+    }
+
+    /// Sets whether this object is flipped horizontally.
+    pub fn set_h_flip(&mut self, flip: bool) -> &mut Self {
+        self.attr1 = self.attr1.with_h_flip(flip);
+        self.write_attrs();
+        self
+    }
+
+    /// Whether this object is flipped vertically.
+    pub fn v_flip(&self) -> bool {
+        self.attr1.v_flip()
+    }
+
+    /// Sets whether this object is flipped vertically.
+    pub fn set_v_flip(&mut self, flip: bool) -> &mut Self {
+        self.attr1 = self.attr1.with_v_flip(flip);
+        self.write_attrs();
+        self
+    }
+
+    /// The priority of this object relative to the background layers.
+    ///
+    /// 0 is the highest, and 3 is the lowest.
+    pub fn priority(&self) -> u32 {
+        self.attr2.priority()
+    }
+
+    /// Sets the priority of this object relative to the background layers.
+    ///
+    /// 0 is the highest, and 3 is the lowest. This function panics if any other values are used.
+    pub fn set_priority(&mut self, priority: u32) -> &mut Self {
+        self.attr2 = self.attr2.with_priority(priority);
+        self.write_attrs();
+        self
+    }
+
+    /// The special effects this object is rendered with.
+    pub fn mode(&self) -> ObjectMode {
+        self.attr0.obj_mode()
+    }
+
+    /// Sets the special effects this object is rendered with.
+    pub fn set_mode(&mut self, mode: ObjectMode) -> &mut Self {
+        self.attr0 = self.attr0.with_obj_mode(mode);
+        self.write_attrs();
+        self
+    }
+
+    /// Whether the mosaic effect is enabled for this object.
+    pub fn mosaic_enabled(&self) -> bool {
+        self.attr0.mosaic_enabled()
+    }
+
+    /// Sets whether the mosaic effect is enabled for this object.
+    pub fn set_mosaic_enabled(&mut self, value: bool) -> &mut Self {
+        self.attr0 = self.attr0.with_mosaic_enabled(value);
+        self.write_attrs();
+        self
+    }
+
+    /// Whether to use 256-color palettes for this object.
+    pub fn enable_256_color(&self) -> bool {
+        self.attr0.use_256_color()
+    }
+
+    /// Sets whether to use 256-color palettes for this object.
+    pub fn set_enable_256_color(&mut self, value: bool) -> &mut Self {
+        self.attr0 = self.attr0.with_use_256_color(value);
+        self.write_attrs();
+        self
+    }
+}
+impl<'a> Drop for Object<'a> {
+    fn drop(&mut self) {
+        let mut state = OBJECT_STATE.lock();
+        let entry = &mut state.entries[self.id as usize];
+        entry.attr0 = OamEntry::HIDDEN.attr0;
+        entry.attr1 = OamEntry::HIDDEN.attr1;
+        entry.attr2 = OamEntry::HIDDEN.attr2;
+        state.allocated &= !(1 << self.id);
+    }
+}
+
+/// A handle to an affine matrix allocated from an [`ObjectTable`].
+///
+/// The matrix is freed when this handle is dropped.
+#[derive(Debug)]
+pub struct ObjectMatrix<'a> {
+    id: u8,
+    matrix: AffineMatrix,
+    _phantom: PhantomData<&'a ObjectTable>,
+}
+impl<'a> ObjectMatrix<'a> {
+    /// Returns the index of this matrix in OAM.
+    pub fn id(&self) -> usize {
+        self.id as usize
+    }
+
+    /// Returns the current value of this matrix.
+    pub fn matrix(&self) -> AffineMatrix {
+        self.matrix
+    }
+
+    /// Sets the value of this matrix.
+    pub fn set_matrix(&mut self, matrix: AffineMatrix) -> &mut Self {
+        self.matrix = matrix;
+        OBJECT_STATE.lock().set_matrix(self.id, &matrix);
+        self
+    }
+
+    /// Sets this matrix to rotate objects counter-clockwise and then scale them.
+    ///
+    /// See [`AffineMatrix::rotation_scale`] for details.
+    pub fn set_rotation_scale(
+        &mut self,
+        angle: u16,
+        scale_x: GbaFrac16,
+        scale_y: GbaFrac16,
+    ) -> &mut Self {
+        self.set_matrix(AffineMatrix::rotation_scale(angle, scale_x, scale_y))
+    }
+}
+impl<'a> Drop for ObjectMatrix<'a> {
+    fn drop(&mut self) {
+        let mut state = OBJECT_STATE.lock();
+        state.set_matrix(self.id, &AffineMatrix::IDENTITY);
+        state.matrices_allocated &= !(1 << self.id);
+    }
+}
+
+#[inline(never)]
+#[track_caller]
+fn object_table_in_use() -> ! {
+    crate::panic_handler::static_panic("An object table already exists!")
+}
+
+#[inline(never)]
+#[track_caller]
+fn object_table_full() -> ! {
+    crate::panic_handler::static_panic("All objects are already in use!")
+}
+
+#[inline(never)]
+#[track_caller]
+fn object_matrices_full() -> ! {
+    crate::panic_handler::static_panic("All object matrices are already in use!")
+}
@@ -0,0 +1,366 @@
+//! Module for managing the background and object palettes.
+//!
+//! Each palette is divided into 16 banks of 16 colors, which are used by 4bpp graphics. Banks are
+//! allocated as [`PaletteBank`] handles, so that different parts of a program (such as the
+//! terminal and game code) do not overwrite each other's colors. Graphics using 256 colors can
+//! allocate all banks of a palette at once with [`alloc_banks`].
+//!
+//! Changes are made to a copy of the palettes kept in RAM, and only become visible once
+//! [`commit`] is called, at which point they are copied to the hardware during the next VBlank.
+//! Color cycling and tinting are applied to this copy during VBlank as well, and do not require
+//! a commit. Only the banks that are currently allocated are copied, so palette RAM can still be
+//! written directly for colors that are not managed by this module.
+
+use crate::{
+    display::{transition::Transition, vram::copy_volatile, Color},
+    mmio::reg::{BG_PALETTE_RAM, OBJ_PALETTE_RAM},
+    sync::{Mutex, Static},
+};
+
+/// The number of banks in each palette.
+pub const BANK_COUNT: usize = 16;
+
+/// The number of colors in each palette bank.
+pub const BANK_SIZE: usize = 16;
+
+/// The maximum strength of the palette tint.
+pub const MAX_TINT: u8 = 32;
+
+/// Identifies one of the two palettes.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub enum PaletteKind {
+    /// The palette used by background layers.
+    Background,
+    /// The palette used by objects.
+    Object,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct ColorCycle {
+    start: u8,
+    len: u8,
+    period: u8,
+    timer: u8,
+}
+
+/// The colors of both palettes, aligned so they can be copied to palette RAM a word at a time.
+#[repr(C, align(4))]
+struct PaletteColors([[Color; 256]; 2]);
+
+struct PaletteState {
+    colors: PaletteColors,
+    allocated: [u16; 2],
+    cycles: [[Option<ColorCycle>; BANK_COUNT]; 2],
+    tint_color: Color,
+    tint_amount: u8,
+}
+impl PaletteState {
+    fn step_cycles(&mut self) -> bool {
+        let mut changed = false;
+        for kind in 0..2 {
+            for bank in 0..BANK_COUNT {
+                if let Some(cycle) = &mut self.cycles[kind][bank] {
+                    cycle.timer += 1;
+                    if cycle.timer >= cycle.period {
+                        cycle.timer = 0;
+                        let start = bank * BANK_SIZE + cycle.start as usize;
+                        let end = start + cycle.len as usize;
+                        self.colors.0[kind][start..end].rotate_right(1);
+                        changed = true;
+                    }
+                }
+            }
+        }
+        changed
+    }
+
+    fn write_hardware(&self) {
+        for kind in 0..2 {
+            let target = if kind == 0 { BG_PALETTE_RAM } else { OBJ_PALETTE_RAM };
+            for bank in 0..BANK_COUNT {
+                if self.allocated[kind] & (1 << bank) == 0 {
+                    continue;
+                }
+                let base = bank * BANK_SIZE;
+                let colors = &self.colors.0[kind][base..base + BANK_SIZE];
+                if self.tint_amount == 0 {
+                    let src = colors.as_ptr() as *const u32;
+                    let dst = target.index(base).as_ptr() as *mut u32;
+                    unsafe { copy_volatile(src, dst, BANK_SIZE / 2) }
+                } else {
+                    for (i, color) in colors.iter().enumerate() {
+                        let color = color.lerp(self.tint_color, self.tint_amount);
+                        target.index(base + i).write(color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+static PALETTE_STATE: Mutex<PaletteState> = Mutex::new(PaletteState {
+    colors: PaletteColors([[Color::BLACK; 256]; 2]),
+    allocated: [0; 2],
+    cycles: [[None; BANK_COUNT]; 2],
+    tint_color: Color::BLACK,
+    tint_amount: 0,
+});
+static COMMIT_PENDING: Static<bool> = Static::new(false);
+static TINT_FADE: Static<Option<Transition>> = Static::new(None);
+
+/// Copies the palettes into palette RAM if they have changed.
+///
+/// If the palettes are currently being edited, this is retried on the next VBlank.
+pub(crate) fn on_vblank() {
+    if let Some(mut state) = PALETTE_STATE.try_lock() {
+        let mut changed = COMMIT_PENDING.replace(false);
+        changed |= state.step_cycles();
+        if let Some(mut fade) = TINT_FADE.read() {
+            let (amount, done) = fade.step();
+            state.tint_amount = amount;
+            TINT_FADE.write(if done { None } else { Some(fade) });
+            changed = true;
+        }
+        if changed {
+            state.write_hardware();
+        }
+    }
+}
+
+/// Allocates a palette bank, or returns `None` if every bank is in use.
+pub fn try_alloc_bank(kind: PaletteKind) -> Option<PaletteBank> {
+    try_alloc_banks::<1>(kind).map(|[bank]| bank)
+}
+
+/// Allocates a palette bank.
+///
+/// This function panics if every bank is in use.
+#[track_caller]
+pub fn alloc_bank(kind: PaletteKind) -> PaletteBank {
+    try_alloc_bank(kind).unwrap_or_else(|| palette_banks_full())
+}
+
+/// Allocates a number of palette banks at once, or returns `None` if not enough banks are free.
+///
+/// The banks are not necessarily contiguous, unless all 16 banks are allocated.
+pub fn try_alloc_banks<const N: usize>(kind: PaletteKind) -> Option<[PaletteBank; N]> {
+    let mut state = PALETTE_STATE.lock();
+    let allocated = &mut state.allocated[kind as usize];
+    if allocated.count_zeros() < N as u32 {
+        return None;
+    }
+    Some(core::array::from_fn(|_| {
+        let id = allocated.trailing_ones() as u8;
+        *allocated |= 1 << id;
+        PaletteBank { kind, id }
+    }))
+}
+
+/// Allocates a number of palette banks at once.
+///
+/// This function panics if not enough banks are free.
+#[track_caller]
+pub fn alloc_banks<const N: usize>(kind: PaletteKind) -> [PaletteBank; N] {
+    try_alloc_banks(kind).unwrap_or_else(|| palette_banks_full())
+}
+
+/// Copies the current state of the palettes into palette RAM during the next VBlank.
+///
+/// The VBlank interrupt must be enabled for this to happen.
+pub fn commit() {
+    COMMIT_PENDING.write(true);
+}
+
+/// Returns whether a commit is still waiting for the next VBlank.
+pub fn is_commit_pending() -> bool {
+    COMMIT_PENDING.read()
+}
+
+/// Returns the color both palettes are tinted toward, and the strength of the tint.
+///
+/// If a fade is in progress, this returns the strength the fade ends at.
+pub fn tint() -> (Color, u8) {
+    let state = PALETTE_STATE.lock();
+    let amount = TINT_FADE
+        .read()
+        .map_or(state.tint_amount, |fade| fade.target());
+    (state.tint_color, amount)
+}
+
+/// Tints the allocated banks of both palettes toward a color, stopping any fade in progress.
+///
+/// The strength is in units of 1/32nd, and must be between 0 and 32 inclusive. At 32, every
+/// color is replaced by the tint color. The tint is applied during the next VBlank.
+#[track_caller]
+pub fn set_tint(color: Color, amount: u8) {
+    check_tint(amount);
+    let mut state = PALETTE_STATE.lock();
+    TINT_FADE.write(None);
+    state.tint_color = color;
+    state.tint_amount = amount;
+    COMMIT_PENDING.write(true);
+}
+
+/// Fades the tint of both palettes to a given strength over a number of frames.
+///
+/// If the color is the same as the current tint color, the fade starts from the current
+/// strength. Otherwise, it starts from no tint at all.
+#[track_caller]
+pub fn fade_tint(color: Color, amount: u8, frames: u16) {
+    check_tint(amount);
+    let mut state = PALETTE_STATE.lock();
+    let from = if state.tint_color == color {
+        TINT_FADE
+            .read()
+            .map_or(state.tint_amount, |fade| fade.current())
+    } else {
+        0
+    };
+    state.tint_color = color;
+    TINT_FADE.write(Some(Transition::new(from, amount, frames)));
+}
+
+/// Returns whether a tint fade is currently in progress.
+pub fn is_fading() -> bool {
+    TINT_FADE.read().is_some()
+}
+
+/// Waits until the current tint fade is complete.
+pub fn wait_fade() {
+    while is_fading() {
+        crate::sys::wait_for_vblank();
+    }
+}
+
+/// An allocated bank of 16 colors in one of the palettes.
+///
+/// The bank is freed when this handle is dropped.
+#[derive(Debug)]
+pub struct PaletteBank {
+    kind: PaletteKind,
+    id: u8,
+}
+impl PaletteBank {
+    fn base(&self) -> usize {
+        self.id as usize * BANK_SIZE
+    }
+
+    /// Returns the palette this bank is part of.
+    pub fn kind(&self) -> PaletteKind {
+        self.kind
+    }
+
+    /// Returns the index of this bank, as used by tiles and objects.
+    pub fn id(&self) -> usize {
+        self.id as usize
+    }
+
+    /// Returns a color in this bank.
+    #[track_caller]
+    pub fn color(&self, index: usize) -> Color {
+        check_index(index, 1);
+        PALETTE_STATE.lock().colors.0[self.kind as usize][self.base() + index]
+    }
+
+    /// Sets a color in this bank.
+    ///
+    /// Color 0 is transparent, except in the first background bank, where it is the backdrop
+    /// color.
+    #[track_caller]
+    pub fn set_color(&mut self, index: usize, color: Color) -> &mut Self {
+        self.set_colors(index, &[color])
+    }
+
+    /// Sets a range of colors in this bank, starting at a given index.
+    #[track_caller]
+    pub fn set_colors(&mut self, start: usize, colors: &[Color]) -> &mut Self {
+        check_index(start, colors.len());
+        let base = self.base() + start;
+        let mut state = PALETTE_STATE.lock();
+        state.colors.0[self.kind as usize][base..base + colors.len()].copy_from_slice(colors);
+        self
+    }
+
+    /// Cycles a range of colors in this bank, moving each color to the next index every
+    /// `period` frames. The last color in the range wraps around to the start.
+    ///
+    /// The colors are cycled during VBlank, and do not need to be committed. This function
+    /// panics if the range contains fewer than two colors.
+    #[track_caller]
+    pub fn set_cycle(&mut self, start: usize, len: usize, period: u8) -> &mut Self {
+        check_index(start, len);
+        if len < 2 {
+            palette_cycle_too_short();
+        }
+        let cycle = ColorCycle { start: start as u8, len: len as u8, period, timer: 0 };
+        PALETTE_STATE.lock().cycles[self.kind as usize][self.id as usize] = Some(cycle);
+        self
+    }
+
+    /// Stops cycling the colors of this bank.
+    pub fn clear_cycle(&mut self) -> &mut Self {
+        PALETTE_STATE.lock().cycles[self.kind as usize][self.id as usize] = None;
+        self
+    }
+
+    /// Writes this bank to palette RAM immediately, without waiting for a commit.
+    ///
+    /// This ignores the palette tint, and is meant for code that may run with interrupts
+    /// disabled.
+    pub(crate) fn write_immediate(&self) {
+        let state = PALETTE_STATE.lock();
+        let colors = &state.colors.0[self.kind as usize][self.base()..self.base() + BANK_SIZE];
+        let target = match self.kind {
+            PaletteKind::Background => BG_PALETTE_RAM,
+            PaletteKind::Object => OBJ_PALETTE_RAM,
+        };
+        for (i, color) in colors.iter().enumerate() {
+            target.index(self.base() + i).write(*color);
+        }
+    }
+}
+impl Drop for PaletteBank {
+    fn drop(&mut self) {
+        let mut state = PALETTE_STATE.lock();
+        state.cycles[self.kind as usize][self.id as usize] = None;
+        state.allocated[self.kind as usize] &= !(1 << self.id);
+    }
+}
+
+#[track_caller]
+fn check_index(start: usize, len: usize) {
+    if start + len > BANK_SIZE {
+        palette_index_out_of_range();
+    }
+}
+
+#[track_caller]
+fn check_tint(amount: u8) {
+    if amount > MAX_TINT {
+        tint_out_of_range();
+    }
+}
+
+#[inline(never)]
+#[track_caller]
+fn palette_banks_full() -> ! {
+    crate::panic_handler::static_panic("Not enough palette banks are free!")
+}
+
+#[inline(never)]
+#[track_caller]
+fn palette_index_out_of_range() -> ! {
+    crate::panic_handler::static_panic("Palette bank colors must be between 0 and 15!")
+}
+
+#[inline(never)]
+#[track_caller]
+fn palette_cycle_too_short() -> ! {
+    crate::panic_handler::static_panic("Color cycles must contain at least two colors!")
+}
+
+#[inline(never)]
+#[track_caller]
+fn tint_out_of_range() -> ! {
+    crate::panic_handler::static_panic("Palette tint must be between 0 and 32!")
+}
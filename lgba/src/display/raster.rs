@@ -0,0 +1,267 @@
+//! Module for raster effects that change registers partway through a frame.
+//!
+//! A [`RasterScheduler`] runs a list of [`RasterAction`]s at chosen scanlines of every frame,
+//! using the [`VCounter`] interrupt. After each scanline is reached, the interrupt is moved to the
+//! next scanline in the list, and the list is started again from the top during every VBlank.
+//! This allows, for example, a status bar with its own scroll position to share the screen with
+//! a scrolling playfield.
+//!
+//! Actions scheduled for scanline 0 run during VBlank, before the frame is drawn. They can be
+//! used to restore the settings that later actions change.
+//!
+//! Unlike a [`ScanlineEffect`], which can change a single register on every line, the scheduler
+//! can change any number of registers, but only on a few lines of the screen.
+//!
+//! # Example
+//!
+//! ```rust
+//! use core::pin::pin;
+//! use lgba::display::raster::*;
+//!
+//! let mut raster = pin!(RasterScheduler::new());
+//! raster.as_mut().start();
+//! # let scroll = 0;
+//! raster.set_schedule(&[
+//!     (0, RasterAction::SetLayerOffset { layer: 0, x: scroll, y: 0 }),
+//!     (128, RasterAction::SetLayerOffset { layer: 0, x: 0, y: 0 }),
+//! ]);
+//! ```
+//!
+//! [`VCounter`]: crate::irq::Interrupt::VCounter
+//! [`ScanlineEffect`]: crate::display::scanline::ScanlineEffect
+
+use crate::{
+    display::Color,
+    irq,
+    irq::{Interrupt, InterruptHandler},
+    mmio::reg::{BG_HOFS, BG_PALETTE_RAM, BG_VOFS, DISPCNT, DISPSTAT, VCOUNT},
+    sync::{Mutex, Static},
+};
+use core::pin::Pin;
+
+/// The maximum number of actions that can be scheduled in a single frame.
+pub const MAX_RASTER_ACTIONS: usize = 16;
+
+/// A change to the display made by a [`RasterScheduler`].
+#[derive(Copy, Clone, Debug)]
+pub enum RasterAction {
+    /// Sets the offset of a tile layer.
+    SetLayerOffset { layer: u8, x: i16, y: i16 },
+    /// Sets which background layers and objects are displayed.
+    ///
+    /// Bits 0 to 3 enable the background layers, and bit 4 enables objects.
+    SetLayersEnabled(u8),
+    /// Sets the backdrop color, which is shown where nothing else is drawn.
+    SetBackdrop(Color),
+    /// Calls a function. This runs inside an interrupt handler.
+    Call(fn()),
+}
+impl RasterAction {
+    fn run(&self) {
+        match *self {
+            RasterAction::SetLayerOffset { layer, x, y } => {
+                BG_HOFS.index(layer as usize).write(x);
+                BG_VOFS.index(layer as usize).write(y);
+            }
+            RasterAction::SetLayersEnabled(layers) => {
+                let disp_cnt = DISPCNT.read();
+                DISPCNT.write(
+                    disp_cnt
+                        .with_display_bg0(layers & 1 != 0)
+                        .with_display_bg1(layers & 2 != 0)
+                        .with_display_bg2(layers & 4 != 0)
+                        .with_display_bg3(layers & 8 != 0)
+                        .with_display_obj(layers & 16 != 0),
+                );
+            }
+            RasterAction::SetBackdrop(color) => BG_PALETTE_RAM.index(0).write(color),
+            RasterAction::Call(func) => func(),
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Schedule {
+    entries: [(u8, RasterAction); MAX_RASTER_ACTIONS],
+    len: u8,
+}
+impl Schedule {
+    const EMPTY: Schedule =
+        Schedule { entries: [(0, RasterAction::SetLayersEnabled(0)); MAX_RASTER_ACTIONS], len: 0 };
+}
+
+struct RasterState {
+    active: Schedule,
+    pending: Option<Schedule>,
+    next: u8,
+}
+
+static RASTER_STATE: Mutex<RasterState> =
+    Mutex::new(RasterState { active: Schedule::EMPTY, pending: None, next: 0 });
+static RASTER_ACTIVE: Static<bool> = Static::new(false);
+
+/// Runs the actions up to the current scanline, and moves the interrupt to the next one.
+fn run_actions(state: &mut RasterState, line: usize) {
+    let schedule = &state.active;
+    while (state.next as usize) < schedule.len as usize {
+        let (target, action) = &schedule.entries[state.next as usize];
+        if *target as usize > line {
+            break;
+        }
+        action.run();
+        state.next += 1;
+    }
+
+    // the VCounter interrupt is moved to the start of VBlank when nothing is left to run.
+    let target = if (state.next as usize) < schedule.len as usize {
+        schedule.entries[state.next as usize].0 as u32
+    } else {
+        160
+    };
+    DISPSTAT.write(DISPSTAT.read().with_vcount_scanline(target));
+}
+
+fn on_vblank() {
+    // the lock is only held by the main thread while interrupts are disabled.
+    if let Some(mut state) = RASTER_STATE.try_lock() {
+        if let Some(pending) = state.pending.take() {
+            state.active = pending;
+        }
+        state.next = 0;
+        run_actions(&mut state, 0);
+    }
+}
+
+fn on_vcounter() {
+    let line = VCOUNT.read() as usize;
+    if line < 160 {
+        if let Some(mut state) = RASTER_STATE.try_lock() {
+            run_actions(&mut state, line);
+        }
+    }
+}
+
+/// Runs actions at chosen scanlines of every frame.
+///
+/// The scheduler must be pinned and started before it does anything. Only one scheduler may be
+/// started at a time, and it is stopped when it is dropped.
+pub struct RasterScheduler {
+    vblank: InterruptHandler<fn()>,
+    vcounter: InterruptHandler<fn()>,
+}
+impl Default for RasterScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl RasterScheduler {
+    /// Creates a new raster scheduler with an empty schedule.
+    pub fn new() -> Self {
+        RasterScheduler {
+            vblank: InterruptHandler::new(on_vblank),
+            vcounter: InterruptHandler::new(on_vcounter),
+        }
+    }
+
+    /// Returns whether this scheduler has been started.
+    pub fn is_started(&self) -> bool {
+        self.vblank.is_registered()
+    }
+
+    /// Starts running the schedule, beginning with the next frame.
+    ///
+    /// This enables the [`VCounter`] interrupt, and panics if another scheduler is running.
+    ///
+    /// [`VCounter`]: Interrupt::VCounter
+    #[track_caller]
+    pub fn start(self: Pin<&mut Self>) {
+        if self.is_started() {
+            return;
+        }
+        if RASTER_ACTIVE.replace(true) {
+            raster_scheduler_in_use();
+        }
+        irq::suppress(|| {
+            let mut state = RASTER_STATE.lock();
+            state.active = Schedule::EMPTY;
+            state.next = 0;
+            DISPSTAT.write(DISPSTAT.read().with_vcount_scanline(160));
+        });
+
+        let this = unsafe { self.get_unchecked_mut() };
+        unsafe { Pin::new_unchecked(&mut this.vblank) }.register(Interrupt::VBlank);
+        unsafe { Pin::new_unchecked(&mut this.vcounter) }.register(Interrupt::VCounter);
+        irq::enable(Interrupt::VCounter);
+    }
+
+    /// Stops running the schedule, and disables the [`VCounter`] interrupt.
+    ///
+    /// [`VCounter`]: Interrupt::VCounter
+    pub fn stop(self: Pin<&mut Self>) {
+        if !self.is_started() {
+            return;
+        }
+        irq::disable(Interrupt::VCounter);
+
+        let this = unsafe { self.get_unchecked_mut() };
+        unsafe { Pin::new_unchecked(&mut this.vblank) }.deregister();
+        unsafe { Pin::new_unchecked(&mut this.vcounter) }.deregister();
+        RASTER_ACTIVE.write(false);
+    }
+
+    /// Sets the actions to run in each frame, starting from the next VBlank.
+    ///
+    /// Each entry is a scanline between 0 and 159 and an action to run at the start of it. The
+    /// entries must be sorted by scanline, and actions on the same scanline run in the order
+    /// they are listed. This function panics if there are more than [`MAX_RASTER_ACTIONS`]
+    /// entries, or if they are not sorted.
+    #[track_caller]
+    pub fn set_schedule(&self, entries: &[(usize, RasterAction)]) {
+        if entries.len() > MAX_RASTER_ACTIONS {
+            raster_schedule_too_long();
+        }
+
+        let mut schedule = Schedule::EMPTY;
+        let mut last = 0;
+        for (i, &(line, action)) in entries.iter().enumerate() {
+            if line >= 160 || line < last {
+                raster_schedule_invalid();
+            }
+            if let RasterAction::SetLayerOffset { layer, .. } = action {
+                if layer >= 4 {
+                    raster_schedule_invalid();
+                }
+            }
+            schedule.entries[i] = (line as u8, action);
+            last = line;
+        }
+        schedule.len = entries.len() as u8;
+
+        irq::suppress(|| RASTER_STATE.lock().pending = Some(schedule));
+    }
+}
+impl Drop for RasterScheduler {
+    fn drop(&mut self) {
+        unsafe { Pin::new_unchecked(self) }.stop();
+    }
+}
+
+#[inline(never)]
+#[track_caller]
+fn raster_scheduler_in_use() -> ! {
+    crate::panic_handler::static_panic("Another raster scheduler is already running.")
+}
+
+#[inline(never)]
+#[track_caller]
+fn raster_schedule_too_long() -> ! {
+    crate::panic_handler::static_panic("Too many actions in raster schedule.")
+}
+
+#[inline(never)]
+#[track_caller]
+fn raster_schedule_invalid() -> ! {
+    crate::panic_handler::static_panic(
+        "Raster schedule entries must be sorted scanlines between 0 and 159, with valid layers.",
+    )
+}
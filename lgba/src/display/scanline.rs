@@ -0,0 +1,241 @@
+//! Module for raster effects that change a register on every scanline.
+//!
+//! A [`ScanlineEffect`] streams a table of values into a register using HBlank DMA, so that
+//! every line of the screen is drawn with a different value. This allows effects such as wavy
+//! water (by changing the horizontal offset of a layer), or circular windows (by changing the
+//! horizontal span of a window).
+//!
+//! The transfer is restarted from the start of the table during every VBlank, so the VBlank
+//! interrupt must be enabled for the effect to work.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lgba::{display::scanline::*, dma::DmaChannelId};
+//!
+//! let mut table = [0i16; SCANLINE_TABLE_LEN];
+//! let channel = DmaChannelId::Dma0.create();
+//! let mut effect = ScanlineEffect::new(channel, ScanlineTarget::bg_h_offset(0), &mut table);
+//! for (line, offset) in effect.table_mut().iter_mut().enumerate() {
+//!     *offset = (line % 8) as i16;
+//! }
+//! ```
+
+use crate::{
+    display::{
+        window::{rect_window_index, WindowId, WindowSpan},
+        Color, GbaFrac32,
+    },
+    dma::{DmaChannel, DmaChannelId},
+    mmio::{
+        reg::*,
+        sys::{DmaAddrCnt, DmaCnt, DmaStartTiming},
+    },
+    sync::Static,
+};
+use core::{ffi::c_void, marker::PhantomData, mem};
+
+/// The number of visible scanlines.
+pub const SCANLINE_COUNT: usize = 160;
+
+/// The length of the table of a scanline effect: one entry for each visible scanline, and one for
+/// the lines in VBlank.
+pub const SCANLINE_TABLE_LEN: usize = SCANLINE_COUNT + 1;
+
+#[derive(Copy, Clone)]
+#[repr(align(4))]
+struct ActiveEffect {
+    table: *const c_void,
+    target: *mut c_void,
+    is_u32: bool,
+}
+
+static ACTIVE_EFFECTS: [Static<Option<ActiveEffect>>; 4] = [const { Static::new(None) }; 4];
+
+/// Restarts the DMA transfers of all active scanline effects.
+pub(crate) fn on_vblank() {
+    for (i, effect) in ACTIVE_EFFECTS.iter().enumerate() {
+        if let Some(effect) = effect.read() {
+            unsafe { arm_effect(i, &effect) }
+        }
+    }
+}
+
+unsafe fn arm_effect(channel: usize, effect: &ActiveEffect) {
+    DMA_CNT_H.index(channel).write(DmaCnt::default());
+
+    // The first line is drawn before the first HBlank, so its value is written directly. The
+    // transfer after the last visible line then writes the final entry, which is used in VBlank.
+    let unit = if effect.is_u32 { 4 } else { 2 };
+    if effect.is_u32 {
+        (effect.target as *mut u32).write_volatile((effect.table as *const u32).read_volatile());
+    } else {
+        (effect.target as *mut u16).write_volatile((effect.table as *const u16).read_volatile());
+    }
+
+    DMA_SAD.index(channel).write(effect.table.byte_add(unit));
+    DMA_DAD.index(channel).write(effect.target);
+    DMA_CNT_L.index(channel).write(1);
+    DMA_CNT_H.index(channel).write(
+        DmaCnt::default()
+            .with_dst_ctl(DmaAddrCnt::Fixed)
+            .with_repeat(true)
+            .with_transfer_u32(effect.is_u32)
+            .with_start_timing(DmaStartTiming::HBlank)
+            .with_enabled(true),
+    );
+}
+
+/// A register that can be changed on every scanline by a [`ScanlineEffect`].
+#[derive(Copy, Clone, Debug)]
+pub struct ScanlineTarget<T: Copy> {
+    target: *mut T,
+    _phantom: PhantomData<T>,
+}
+impl<T: Copy> ScanlineTarget<T> {
+    /// Creates a target from the address of an arbitrary register.
+    ///
+    /// `T` must either have a size and alignment of two bytes, or a size and alignment of four
+    /// bytes.
+    ///
+    /// # Safety
+    ///
+    /// The address must be a writable register or memory location for which writing arbitrary
+    /// values of type `T` is safe.
+    pub const unsafe fn new(target: *mut T) -> Self {
+        ScanlineTarget { target, _phantom: PhantomData }
+    }
+}
+impl ScanlineTarget<i16> {
+    /// Targets the horizontal offset of a background layer.
+    #[track_caller]
+    pub fn bg_h_offset(layer: usize) -> Self {
+        unsafe { ScanlineTarget::new(BG_HOFS.index(layer).as_ptr()) }
+    }
+
+    /// Targets the vertical offset of a background layer.
+    #[track_caller]
+    pub fn bg_v_offset(layer: usize) -> Self {
+        unsafe { ScanlineTarget::new(BG_VOFS.index(layer).as_ptr()) }
+    }
+}
+impl ScanlineTarget<GbaFrac32> {
+    /// Targets the horizontal reference point of an affine background layer (2 or 3).
+    #[track_caller]
+    pub fn bg_reference_x(layer: usize) -> Self {
+        unsafe { ScanlineTarget::new(BG_X.index(affine_layer_index(layer)).as_ptr()) }
+    }
+
+    /// Targets the vertical reference point of an affine background layer (2 or 3).
+    #[track_caller]
+    pub fn bg_reference_y(layer: usize) -> Self {
+        unsafe { ScanlineTarget::new(BG_Y.index(affine_layer_index(layer)).as_ptr()) }
+    }
+}
+impl ScanlineTarget<WindowSpan> {
+    /// Targets the horizontal span of a rectangular window.
+    ///
+    /// This function panics if called with anything but [`WindowId::Win0`] or
+    /// [`WindowId::Win1`].
+    #[track_caller]
+    pub fn window_h(id: WindowId) -> Self {
+        unsafe { ScanlineTarget::new(WIN_H.index(rect_window_index(id)).as_ptr() as *mut _) }
+    }
+
+    /// Targets the vertical span of a rectangular window.
+    ///
+    /// This function panics if called with anything but [`WindowId::Win0`] or
+    /// [`WindowId::Win1`].
+    #[track_caller]
+    pub fn window_v(id: WindowId) -> Self {
+        unsafe { ScanlineTarget::new(WIN_V.index(rect_window_index(id)).as_ptr() as *mut _) }
+    }
+}
+impl ScanlineTarget<Color> {
+    /// Targets the backdrop color, which is useful for drawing gradients.
+    pub fn backdrop_color() -> Self {
+        unsafe { ScanlineTarget::new(BG_PALETTE_RAM.as_ptr()) }
+    }
+}
+
+/// A raster effect that writes a different value to a register on every scanline.
+///
+/// The effect runs for as long as this value is alive, and owns the DMA channel used for the
+/// transfer.
+#[derive(Debug)]
+pub struct ScanlineEffect<'a, T: Copy> {
+    channel: DmaChannel,
+    table: &'a mut [T; SCANLINE_TABLE_LEN],
+}
+impl<'a, T: Copy> ScanlineEffect<'a, T> {
+    /// Creates a new scanline effect, which starts at the next VBlank.
+    ///
+    /// Each entry of the table is the value of the target register while the corresponding
+    /// scanline is drawn. The last entry is the value of the register after the final scanline,
+    /// until the first entry is written again at the start of the next VBlank.
+    #[track_caller]
+    pub fn new(
+        channel: DmaChannel,
+        target: ScanlineTarget<T>,
+        table: &'a mut [T; SCANLINE_TABLE_LEN],
+    ) -> Self {
+        let is_u32 = if mem::size_of::<T>() == 2 && mem::align_of::<T>() == 2 {
+            false
+        } else if mem::size_of::<T>() == 4 && mem::align_of::<T>() == 4 {
+            true
+        } else {
+            scanline_value_invalid_size()
+        };
+        ACTIVE_EFFECTS[channel.id() as usize].write(Some(ActiveEffect {
+            table: table.as_ptr() as *const c_void,
+            target: target.target as *mut c_void,
+            is_u32,
+        }));
+        ScanlineEffect { channel, table }
+    }
+
+    /// Returns the ID of the DMA channel used by this effect.
+    pub fn channel_id(&self) -> DmaChannelId {
+        self.channel.id()
+    }
+
+    /// Returns the table of values used by this effect.
+    pub fn table(&self) -> &[T; SCANLINE_TABLE_LEN] {
+        self.table
+    }
+
+    /// Returns a mutable reference to the table of values used by this effect.
+    ///
+    /// Changes take effect immediately, so values changed while the screen is being drawn may be
+    /// only partially displayed in the current frame.
+    pub fn table_mut(&mut self) -> &mut [T; SCANLINE_TABLE_LEN] {
+        self.table
+    }
+}
+impl<'a, T: Copy> Drop for ScanlineEffect<'a, T> {
+    fn drop(&mut self) {
+        let channel = self.channel.id() as usize;
+        ACTIVE_EFFECTS[channel].write(None);
+        unsafe { DMA_CNT_H.index(channel).write(DmaCnt::default()) }
+    }
+}
+
+#[track_caller]
+fn affine_layer_index(layer: usize) -> usize {
+    match layer {
+        2 | 3 => layer - 2,
+        _ => not_affine_layer(),
+    }
+}
+
+#[inline(never)]
+#[track_caller]
+fn scanline_value_invalid_size() -> ! {
+    crate::panic_handler::static_panic("Scanline effect values must be 2 or 4 bytes in size!")
+}
+
+#[inline(never)]
+#[track_caller]
+fn not_affine_layer() -> ! {
+    crate::panic_handler::static_panic("Only layers 2 and 3 can be affine layers!")
+}
@@ -0,0 +1,350 @@
+//! Shadow copies of the display registers, used by the deferred commit mode.
+//!
+//! This covers the display control register, the background layer registers, and the window,
+//! color effect and mosaic registers. Brightness fades and mosaic transitions are still applied
+//! during each VBlank without waiting for a commit.
+
+use crate::{
+    display::{AffineMatrix, GbaFrac32},
+    mmio::{
+        display::{BgCnt, BldCnt, DispCnt, Mosaic, WinBound, WinCnt},
+        reg::*,
+    },
+    sync::{Mutex, Static},
+};
+
+const DIRTY_DISP_CNT: u32 = 1 << 0;
+const DIRTY_BG_CNT: u32 = 1 << 1;
+const DIRTY_BG_HOFS: u32 = 1 << 5;
+const DIRTY_BG_VOFS: u32 = 1 << 9;
+const DIRTY_BG_MATRIX: u32 = 1 << 13;
+const DIRTY_BG_REFERENCE: u32 = 1 << 15;
+const DIRTY_WIN_BOUNDS: u32 = 1 << 17;
+const DIRTY_WIN_IN: u32 = 1 << 19;
+const DIRTY_WIN_OUT: u32 = 1 << 20;
+const DIRTY_MOSAIC: u32 = 1 << 21;
+const DIRTY_BLD_CNT: u32 = 1 << 22;
+const DIRTY_BLD_ALPHA: u32 = 1 << 23;
+const DIRTY_BLD_Y: u32 = 1 << 24;
+
+struct RegisterShadow {
+    dirty: u32,
+    disp_cnt: DispCnt,
+    bg_cnt: [BgCnt; 4],
+    bg_hofs: [i16; 4],
+    bg_vofs: [i16; 4],
+    bg_matrix: [AffineMatrix; 2],
+    bg_reference: [(GbaFrac32, GbaFrac32); 2],
+    win_bounds: [(WinBound, WinBound); 2],
+    win_in: WinCnt,
+    win_out: WinCnt,
+    mosaic: Mosaic,
+    bld_cnt: BldCnt,
+    bld_alpha: [u8; 2],
+    bld_y: u16,
+}
+impl RegisterShadow {
+    fn flush(&mut self) {
+        if self.dirty & DIRTY_DISP_CNT != 0 {
+            // the displayed page is flipped separately, so it must not be overwritten here.
+            let active_frame = DISPCNT.read().active_frame();
+            DISPCNT.write(self.disp_cnt.with_active_frame(active_frame));
+        }
+        for i in 0..4 {
+            if self.dirty & (DIRTY_BG_CNT << i) != 0 {
+                BG_CNT.index(i).write(self.bg_cnt[i]);
+            }
+            if self.dirty & (DIRTY_BG_HOFS << i) != 0 {
+                BG_HOFS.index(i).write(self.bg_hofs[i]);
+            }
+            if self.dirty & (DIRTY_BG_VOFS << i) != 0 {
+                BG_VOFS.index(i).write(self.bg_vofs[i]);
+            }
+        }
+        for i in 0..2 {
+            if self.dirty & (DIRTY_BG_MATRIX << i) != 0 {
+                BG_PA.index(i).write(self.bg_matrix[i].pa);
+                BG_PB.index(i).write(self.bg_matrix[i].pb);
+                BG_PC.index(i).write(self.bg_matrix[i].pc);
+                BG_PD.index(i).write(self.bg_matrix[i].pd);
+            }
+            if self.dirty & (DIRTY_BG_REFERENCE << i) != 0 {
+                BG_X.index(i).write(self.bg_reference[i].0);
+                BG_Y.index(i).write(self.bg_reference[i].1);
+            }
+            if self.dirty & (DIRTY_WIN_BOUNDS << i) != 0 {
+                WIN_H.index(i).write(self.win_bounds[i].0);
+                WIN_V.index(i).write(self.win_bounds[i].1);
+            }
+        }
+        if self.dirty & DIRTY_WIN_IN != 0 {
+            WININ.write(self.win_in);
+        }
+        if self.dirty & DIRTY_WIN_OUT != 0 {
+            WINOUT.write(self.win_out);
+        }
+        if self.dirty & DIRTY_MOSAIC != 0 {
+            MOSAIC.write(self.mosaic);
+        }
+        if self.dirty & DIRTY_BLD_CNT != 0 {
+            BLDCNT.write(self.bld_cnt);
+        }
+        if self.dirty & DIRTY_BLD_ALPHA != 0 {
+            BLDALPHA.write(self.bld_alpha);
+        }
+        if self.dirty & DIRTY_BLD_Y != 0 {
+            BLDY.write(self.bld_y);
+        }
+        self.dirty = 0;
+    }
+}
+
+static SHADOW: Mutex<Option<RegisterShadow>> = Mutex::new(None);
+static IS_DEFERRED: Static<bool> = Static::new(false);
+static COMMIT_PENDING: Static<bool> = Static::new(false);
+
+/// Writes the recorded register changes if a commit is pending.
+///
+/// If the shadow registers are currently being edited, this is retried on the next VBlank.
+pub(crate) fn on_vblank() {
+    if COMMIT_PENDING.read() {
+        if let Some(mut shadow) = SHADOW.try_lock() {
+            if let Some(shadow) = &mut *shadow {
+                shadow.flush();
+            }
+            COMMIT_PENDING.write(false);
+        }
+    }
+}
+
+/// Returns whether register writes are currently recorded rather than written immediately.
+pub(crate) fn is_deferred() -> bool {
+    IS_DEFERRED.read()
+}
+
+/// Sets whether register writes are recorded until the next [`commit`].
+///
+/// Leaving the deferred mode writes any changes that have not been committed yet.
+pub(crate) fn set_deferred(deferred: bool) {
+    if deferred == IS_DEFERRED.read() {
+        return;
+    }
+    // the shadow may be locked if we are panicking, in which case the changes are dropped.
+    if let Some(mut shadow) = SHADOW.try_lock() {
+        if deferred {
+            *shadow = Some(RegisterShadow {
+                dirty: 0,
+                disp_cnt: DISPCNT.read(),
+                bg_cnt: [BgCnt::default(); 4],
+                bg_hofs: [0; 4],
+                bg_vofs: [0; 4],
+                bg_matrix: [AffineMatrix::IDENTITY; 2],
+                bg_reference: [(GbaFrac32::ZERO, GbaFrac32::ZERO); 2],
+                win_bounds: [(WinBound::default(), WinBound::default()); 2],
+                win_in: WinCnt::default(),
+                win_out: WinCnt::default(),
+                mosaic: Mosaic::default(),
+                bld_cnt: BldCnt::default(),
+                bld_alpha: [0; 2],
+                bld_y: 0,
+            });
+        } else if let Some(mut shadow) = shadow.take() {
+            shadow.flush();
+        }
+    }
+    COMMIT_PENDING.write(false);
+    IS_DEFERRED.write(deferred);
+}
+
+fn edit_shadow(dirty: u32, func: impl FnOnce(&mut RegisterShadow)) {
+    if let Some(shadow) = &mut *SHADOW.lock() {
+        func(shadow);
+        shadow.dirty |= dirty;
+    }
+}
+
+/// Modifies the display control register, or its shadow copy in the deferred mode.
+pub(crate) fn edit_disp_cnt(func: impl FnOnce(DispCnt) -> DispCnt) {
+    if is_deferred() {
+        edit_shadow(DIRTY_DISP_CNT, |shadow| shadow.disp_cnt = func(shadow.disp_cnt));
+    } else {
+        crate::irq::suppress(|| DISPCNT.write(func(DISPCNT.read())));
+    }
+}
+
+/// Writes the control register of a background layer.
+pub(crate) fn write_bg_cnt(layer: usize, cnt: BgCnt) {
+    if is_deferred() {
+        edit_shadow(DIRTY_BG_CNT << layer, |shadow| shadow.bg_cnt[layer] = cnt);
+    } else {
+        BG_CNT.index(layer).write(cnt);
+    }
+}
+
+/// Changes the tile map base of a background layer from the VBlank interrupt.
+///
+/// The register is written immediately even in the deferred mode, as the tile map must change
+/// together with the tiles copied during the same VBlank. The shadow copy is updated as well, so
+/// that a later commit does not restore the old tile map. Returns `false` without writing anything
+/// if the shadow is currently being edited.
+pub(crate) fn try_write_bg_tile_base_from_vblank(layer: usize, base: usize) -> bool {
+    if let Some(mut shadow) = SHADOW.try_lock() {
+        if let Some(shadow) = &mut *shadow {
+            shadow.bg_cnt[layer] = shadow.bg_cnt[layer].with_tile_map_base(base);
+        }
+        BG_CNT
+            .index(layer)
+            .write(BG_CNT.index(layer).read().with_tile_map_base(base));
+        true
+    } else {
+        false
+    }
+}
+
+/// Writes the scroll registers of a background layer from the VBlank interrupt.
+///
+/// The registers are written immediately even in the deferred mode, as they must change together
+/// with the tiles written during the same VBlank. The shadow copy is updated as well, so that a
+/// later commit does not restore the old position. Returns `false` without writing anything if
+/// the shadow is currently being edited.
+pub(crate) fn try_write_bg_offset_from_vblank(layer: usize, x: i16, y: i16) -> bool {
+    if let Some(mut shadow) = SHADOW.try_lock() {
+        if let Some(shadow) = &mut *shadow {
+            shadow.bg_hofs[layer] = x;
+            shadow.bg_vofs[layer] = y;
+        }
+        BG_HOFS.index(layer).write(x);
+        BG_VOFS.index(layer).write(y);
+        true
+    } else {
+        false
+    }
+}
+
+/// Writes the horizontal scroll register of a background layer.
+pub(crate) fn write_bg_hofs(layer: usize, value: i16) {
+    if is_deferred() {
+        edit_shadow(DIRTY_BG_HOFS << layer, |shadow| shadow.bg_hofs[layer] = value);
+    } else {
+        BG_HOFS.index(layer).write(value);
+    }
+}
+
+/// Writes the vertical scroll register of a background layer.
+pub(crate) fn write_bg_vofs(layer: usize, value: i16) {
+    if is_deferred() {
+        edit_shadow(DIRTY_BG_VOFS << layer, |shadow| shadow.bg_vofs[layer] = value);
+    } else {
+        BG_VOFS.index(layer).write(value);
+    }
+}
+
+/// Writes the transformation matrix of an affine background layer.
+pub(crate) fn write_bg_matrix(affine_id: usize, matrix: AffineMatrix) {
+    if is_deferred() {
+        edit_shadow(DIRTY_BG_MATRIX << affine_id, |shadow| shadow.bg_matrix[affine_id] = matrix);
+    } else {
+        BG_PA.index(affine_id).write(matrix.pa);
+        BG_PB.index(affine_id).write(matrix.pb);
+        BG_PC.index(affine_id).write(matrix.pc);
+        BG_PD.index(affine_id).write(matrix.pd);
+    }
+}
+
+/// Writes the reference point of an affine background layer.
+pub(crate) fn write_bg_reference(affine_id: usize, x: GbaFrac32, y: GbaFrac32) {
+    if is_deferred() {
+        edit_shadow(DIRTY_BG_REFERENCE << affine_id, |shadow| {
+            shadow.bg_reference[affine_id] = (x, y)
+        });
+    } else {
+        BG_X.index(affine_id).write(x);
+        BG_Y.index(affine_id).write(y);
+    }
+}
+
+/// Writes the bounds of a rectangular window.
+pub(crate) fn write_win_bounds(window: usize, h: WinBound, v: WinBound) {
+    if is_deferred() {
+        edit_shadow(DIRTY_WIN_BOUNDS << window, |shadow| shadow.win_bounds[window] = (h, v));
+    } else {
+        WIN_H.index(window).write(h);
+        WIN_V.index(window).write(v);
+    }
+}
+
+/// Writes the targets displayed inside the rectangular windows.
+pub(crate) fn write_win_in(cnt: WinCnt) {
+    if is_deferred() {
+        edit_shadow(DIRTY_WIN_IN, |shadow| shadow.win_in = cnt);
+    } else {
+        WININ.write(cnt);
+    }
+}
+
+/// Writes the targets displayed outside the windows and inside the object window.
+pub(crate) fn write_win_out(cnt: WinCnt) {
+    if is_deferred() {
+        edit_shadow(DIRTY_WIN_OUT, |shadow| shadow.win_out = cnt);
+    } else {
+        WINOUT.write(cnt);
+    }
+}
+
+/// Writes the mosaic size register.
+pub(crate) fn write_mosaic(cnt: Mosaic) {
+    if is_deferred() {
+        edit_shadow(DIRTY_MOSAIC, |shadow| shadow.mosaic = cnt);
+    } else {
+        MOSAIC.write(cnt);
+    }
+}
+
+/// Writes the color special effect control register.
+pub(crate) fn write_bld_cnt(cnt: BldCnt) {
+    if is_deferred() {
+        edit_shadow(DIRTY_BLD_CNT, |shadow| shadow.bld_cnt = cnt);
+    } else {
+        BLDCNT.write(cnt);
+    }
+}
+
+/// Writes the alpha blending coefficients.
+pub(crate) fn write_bld_alpha(alpha: [u8; 2]) {
+    if is_deferred() {
+        edit_shadow(DIRTY_BLD_ALPHA, |shadow| shadow.bld_alpha = alpha);
+    } else {
+        BLDALPHA.write(alpha);
+    }
+}
+
+/// Writes the brightness coefficient.
+pub(crate) fn write_bld_y(value: u16) {
+    if is_deferred() {
+        edit_shadow(DIRTY_BLD_Y, |shadow| shadow.bld_y = value);
+    } else {
+        BLDY.write(value);
+    }
+}
+
+/// Writes all display changes recorded in the deferred commit mode during the next VBlank.
+///
+/// This does nothing unless the active display mode uses deferred commits. The VBlank interrupt
+/// must be enabled for the changes to be written.
+pub fn commit() {
+    if is_deferred() {
+        COMMIT_PENDING.write(true);
+    }
+}
+
+/// Returns whether a commit is still waiting for the next VBlank.
+pub fn is_commit_pending() -> bool {
+    COMMIT_PENDING.read()
+}
+
+/// Waits until the current commit has been written.
+pub fn wait_commit() {
+    while is_commit_pending() {
+        crate::sys::wait_for_vblank();
+    }
+}
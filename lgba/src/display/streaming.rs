@@ -0,0 +1,337 @@
+//! Module for scrolling tile maps that are larger than the hardware supports.
+//!
+//! A [`StreamingMap`] displays part of a [`WorldMap`] of any size on a regular tile layer. The
+//! layer uses a single 32x32 tile map that wraps around as the camera moves, and only the rows
+//! and columns of tiles that come into view are written to it.
+//!
+//! New rows and columns are buffered when the camera moves, and are copied into VRAM together
+//! with the new scroll position during the next VBlank, so the VBlank interrupt must be enabled
+//! for the map to scroll.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lgba::{display::streaming::*, dma::DmaChannelId};
+//!
+//! # fn example(mode: &mut lgba::display::ActiveMode0, level: &'static [u8]) {
+//! let world = WorldMap::from_bytes(level, 256, 32);
+//! let mut map = StreamingMap::new(&mut mode.layers[0], world, DmaChannelId::Dma3.create());
+//! for x in 0..1024 {
+//!     map.set_offset(x, 0);
+//!     lgba::sys::wait_for_vblank();
+//! }
+//! # }
+//! ```
+
+use crate::{
+    display::{
+        shadow,
+        vram::{copy_volatile, MapAccess},
+        vram_alloc,
+        vram_alloc::ScreenBlocks,
+        ActiveTileLayer, TileLayerSize, VramTile,
+    },
+    dma::DmaChannel,
+    mmio::reg::VRAM_BASE,
+    sync::Mutex,
+};
+use core::ffi::c_void;
+
+/// The width and height of the hardware tile map used by a streaming map, in tiles.
+const MAP_SIZE: i32 = 32;
+
+/// The number of tile columns that may be visible at once.
+const VISIBLE_COLUMNS: i32 = 31;
+
+/// The number of tile rows that may be visible at once.
+const VISIBLE_ROWS: i32 = 21;
+
+struct StreamState {
+    channel: DmaChannel,
+    map_base: usize,
+    row: Option<u8>,
+    row_tiles: [VramTile; MAP_SIZE as usize],
+    column: Option<u8>,
+    column_tiles: [VramTile; MAP_SIZE as usize],
+    scroll: Option<(i16, i16)>,
+}
+impl StreamState {
+    fn row_ptr(&self, row: u8) -> *mut VramTile {
+        (self.map_base as *mut VramTile).wrapping_add(row as usize * MAP_SIZE as usize)
+    }
+
+    /// Copies the pending row and column into VRAM.
+    fn upload_edges(&mut self) {
+        if let Some(row) = self.row.take() {
+            unsafe {
+                self.channel.unsafe_transfer(
+                    self.row_tiles.as_ptr() as *const c_void,
+                    self.row_ptr(row) as *mut c_void,
+                    MAP_SIZE as usize * 2,
+                );
+            }
+        }
+        if let Some(column) = self.column.take() {
+            // columns are not contiguous in VRAM, so they cannot be copied with a single DMA.
+            for (row, tile) in self.column_tiles.iter().enumerate() {
+                unsafe {
+                    let target = self.row_ptr(row as u8).wrapping_add(column as usize);
+                    copy_volatile(tile, target, 1);
+                }
+            }
+        }
+    }
+}
+
+static STREAMS: [Mutex<Option<StreamState>>; 4] = [const { Mutex::new(None) }; 4];
+
+/// Uploads the pending edges of all streaming maps, and updates their scroll position.
+///
+/// If a streaming map is currently being edited, this is retried on the next VBlank.
+pub(crate) fn on_vblank() {
+    for (layer, stream) in STREAMS.iter().enumerate() {
+        if let Some(mut stream) = stream.try_lock() {
+            if let Some(stream) = &mut *stream {
+                stream.upload_edges();
+                if let Some((x, y)) = stream.scroll {
+                    if shadow::try_write_bg_offset_from_vblank(layer, x, y) {
+                        stream.scroll = None;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A tile map of any size, usually stored in ROM.
+///
+/// Tiles are stored in rows, from the top-left corner of the map.
+#[derive(Copy, Clone, Debug)]
+pub struct WorldMap {
+    tiles: &'static [VramTile],
+    width: usize,
+    height: usize,
+}
+impl WorldMap {
+    /// Creates a new world map from a list of tiles.
+    ///
+    /// This function panics if the number of tiles does not match the width and height.
+    #[track_caller]
+    pub fn new(tiles: &'static [VramTile], width: usize, height: usize) -> Self {
+        if tiles.len() != width * height {
+            world_map_size_incorrect();
+        }
+        WorldMap { tiles, width, height }
+    }
+
+    /// Creates a new world map from raw tile data, such as a file from `lgba_data`.
+    ///
+    /// Each tile is stored as a little-endian 16-bit value in the format of [`VramTile`]. This
+    /// function panics if the data is not aligned to 2 bytes, or if its length does not match
+    /// the width and height.
+    #[track_caller]
+    pub fn from_bytes(data: &'static [u8], width: usize, height: usize) -> Self {
+        if !(data.as_ptr() as usize).is_multiple_of(2) || !data.len().is_multiple_of(2) {
+            world_map_not_aligned();
+        }
+        let tiles = unsafe {
+            core::slice::from_raw_parts(data.as_ptr() as *const VramTile, data.len() / 2)
+        };
+        WorldMap::new(tiles, width, height)
+    }
+
+    /// Returns the width of this map in tiles.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of this map in tiles.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the tile at a given coordinate, or a blank tile if it is outside the map.
+    pub fn tile(&self, x: i32, y: i32) -> VramTile {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            VramTile::default()
+        } else {
+            self.tiles[y as usize * self.width + x as usize]
+        }
+    }
+}
+
+/// A tile layer that displays part of a [`WorldMap`], writing new tiles into VRAM as the camera
+/// moves.
+///
+/// The layer is reconfigured to use a 256x256 tile map in screen blocks allocated by the
+/// streaming map. Its character base and other settings are left unchanged. The streaming map
+/// keeps the layer borrowed for as long as it is alive, and disables it when dropped, as its
+/// screen block is freed.
+pub struct StreamingMap<'a, 'b> {
+    layer: &'a mut ActiveTileLayer<'b>,
+    world: WorldMap,
+    map: MapAccess,
+    camera: (i32, i32),
+    _blocks: ScreenBlocks,
+}
+impl<'a, 'b> StreamingMap<'a, 'b> {
+    /// Creates a new streaming map on a tile layer, with the camera at the top-left corner of
+    /// the world.
+    ///
+    /// The DMA channel is used to upload rows of tiles during VBlank. This function panics if
+    /// there is no free screen block, or if another streaming map already uses this layer.
+    #[track_caller]
+    pub fn new(layer: &'a mut ActiveTileLayer<'b>, world: WorldMap, channel: DmaChannel) -> Self {
+        let mut slot = STREAMS[layer.id() as usize].lock();
+        if slot.is_some() {
+            streaming_map_in_use();
+        }
+
+        let blocks = vram_alloc::alloc_screen_blocks(1);
+        let map = blocks.map_access(0);
+        layer
+            .set_tile_map_size(TileLayerSize::Map256x256)
+            .set_tile_base(blocks.tile_base());
+        *slot = Some(StreamState {
+            channel,
+            map_base: VRAM_BASE + 2048 * blocks.tile_base(),
+            row: None,
+            row_tiles: [VramTile::default(); MAP_SIZE as usize],
+            column: None,
+            column_tiles: [VramTile::default(); MAP_SIZE as usize],
+            scroll: None,
+        });
+        drop(slot);
+
+        let mut streaming = StreamingMap { layer, world, map, camera: (0, 0), _blocks: blocks };
+        streaming.redraw();
+        streaming
+    }
+
+    /// Returns the world map displayed by this streaming map.
+    pub fn world(&self) -> &WorldMap {
+        &self.world
+    }
+
+    /// Returns the world coordinate, in pixels, displayed at the top-left corner of the screen.
+    pub fn offset(&self) -> (i32, i32) {
+        self.camera
+    }
+
+    /// Moves the camera so the given world coordinate, in pixels, is displayed at the top-left
+    /// corner of the screen.
+    ///
+    /// The change is displayed starting from the next VBlank. Moving the camera by more than one
+    /// tile in either direction at once writes the new tiles into VRAM immediately, which may
+    /// briefly show them in the wrong place during the current frame.
+    pub fn set_offset(&mut self, x: i32, y: i32) {
+        let (old_x, old_y) = tile_position(self.camera);
+        let (new_x, new_y) = tile_position((x, y));
+        self.camera = (x, y);
+
+        let mut slot = STREAMS[self.layer.id() as usize].lock();
+        let state = slot.as_mut().unwrap();
+
+        // edges still waiting for a VBlank are not visible yet, so they can be written now.
+        if new_x != old_x || new_y != old_y {
+            state.upload_edges();
+        }
+
+        // queue a single row and column for the next VBlank, and write everything else directly.
+        let rows = new_lines(old_y, new_y, VISIBLE_ROWS);
+        let columns = new_lines(old_x, new_x, VISIBLE_COLUMNS);
+        let queue_edges = rows.len() <= 1 && columns.len() <= 1;
+        for row in rows {
+            let hw_row = row.rem_euclid(MAP_SIZE) as u8;
+            if queue_edges {
+                self.fill_row(&mut state.row_tiles, row, new_x);
+                state.row = Some(hw_row);
+            } else {
+                let mut tiles = [VramTile::default(); MAP_SIZE as usize];
+                self.fill_row(&mut tiles, row, new_x);
+                unsafe { copy_volatile(tiles.as_ptr(), state.row_ptr(hw_row), tiles.len()) }
+            }
+        }
+        for column in columns {
+            let hw_column = column.rem_euclid(MAP_SIZE) as u8;
+            self.fill_column(&mut state.column_tiles, column, new_y);
+            state.column = Some(hw_column);
+            if !queue_edges {
+                state.upload_edges();
+            }
+        }
+
+        state.scroll = Some((x as i16, y as i16));
+        self.layer.set_offset_untracked(-x as i16, -y as i16);
+    }
+
+    /// Rewrites every visible tile of the map immediately.
+    ///
+    /// This is useful after changing tiles of a world map stored in RAM.
+    pub fn redraw(&mut self) {
+        let (tile_x, tile_y) = tile_position(self.camera);
+        let mut tiles = [VramTile::default(); MAP_SIZE as usize];
+        for row in tile_y..tile_y + MAP_SIZE {
+            self.fill_row(&mut tiles, row, tile_x);
+            self.map
+                .set_tiles(0, row.rem_euclid(MAP_SIZE) as usize, &tiles);
+        }
+
+        let mut slot = STREAMS[self.layer.id() as usize].lock();
+        let state = slot.as_mut().unwrap();
+        state.row = None;
+        state.column = None;
+        state.scroll = Some((self.camera.0 as i16, self.camera.1 as i16));
+    }
+
+    /// Fills a buffer with a row of the world map, ordered by hardware tile map column.
+    fn fill_row(&self, tiles: &mut [VramTile; MAP_SIZE as usize], row: i32, start_x: i32) {
+        for x in start_x..start_x + MAP_SIZE {
+            tiles[x.rem_euclid(MAP_SIZE) as usize] = self.world.tile(x, row);
+        }
+    }
+
+    /// Fills a buffer with a column of the world map, ordered by hardware tile map row.
+    fn fill_column(&self, tiles: &mut [VramTile; MAP_SIZE as usize], column: i32, start_y: i32) {
+        for y in start_y..start_y + MAP_SIZE {
+            tiles[y.rem_euclid(MAP_SIZE) as usize] = self.world.tile(column, y);
+        }
+    }
+}
+impl<'a, 'b> Drop for StreamingMap<'a, 'b> {
+    fn drop(&mut self) {
+        *STREAMS[self.layer.id() as usize].lock() = None;
+        self.layer.set_enabled(false);
+    }
+}
+
+fn tile_position((x, y): (i32, i32)) -> (i32, i32) {
+    (x.div_euclid(8), y.div_euclid(8))
+}
+
+/// Returns the lines that become visible when the first visible line changes.
+fn new_lines(old: i32, new: i32, visible: i32) -> core::ops::Range<i32> {
+    if new > old {
+        core::cmp::max(old + visible, new)..new + visible
+    } else {
+        new..core::cmp::min(old, new + visible)
+    }
+}
+
+#[inline(never)]
+#[track_caller]
+fn world_map_size_incorrect() -> ! {
+    crate::panic_handler::static_panic("World map data does not match its width and height!")
+}
+
+#[inline(never)]
+#[track_caller]
+fn world_map_not_aligned() -> ! {
+    crate::panic_handler::static_panic("World map data must be aligned to 2 bytes!")
+}
+
+#[inline(never)]
+#[track_caller]
+fn streaming_map_in_use() -> ! {
+    crate::panic_handler::static_panic("Layer is already used by a streaming map!")
+}
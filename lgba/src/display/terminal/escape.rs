@@ -0,0 +1,126 @@
+//! A parser for the subset of ANSI escape sequences understood by the terminal.
+
+const MAX_PARAMS: usize = 4;
+const MAX_PARAM_VALUE: u16 = 9999;
+
+#[derive(Copy, Clone, Debug)]
+enum ParserState {
+    Ground,
+    Escape,
+    Csi,
+    /// A sequence that is either malformed or uses private parameters. Its characters are
+    /// consumed until it ends, and it is then ignored.
+    IgnoredCsi,
+}
+
+/// A complete control sequence, of the form `ESC [ params command`.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct CsiSequence {
+    params: [u16; MAX_PARAMS],
+    param_count: u8,
+    pub command: char,
+}
+impl CsiSequence {
+    /// Returns the parameters of this sequence. Omitted parameters are returned as 0.
+    pub fn params(&self) -> &[u16] {
+        &self.params[..self.param_count as usize]
+    }
+
+    /// Returns a parameter of this sequence, or a default value if it was omitted or is 0.
+    pub fn param_or(&self, id: usize, default: u16) -> u16 {
+        match self.params().get(id) {
+            Some(&value) if value != 0 => value,
+            _ => default,
+        }
+    }
+}
+
+/// The result of passing a character through the escape sequence parser.
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum EscapeAction {
+    /// The character is not part of an escape sequence and should be printed.
+    Print(char),
+    /// The character was consumed as part of an escape sequence.
+    Consumed,
+    /// The character completed a control sequence.
+    Csi(CsiSequence),
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct EscapeParser {
+    state: ParserState,
+    current: CsiSequence,
+}
+impl EscapeParser {
+    pub const fn new() -> Self {
+        EscapeParser {
+            state: ParserState::Ground,
+            current: CsiSequence { params: [0; MAX_PARAMS], param_count: 0, command: '\0' },
+        }
+    }
+
+    pub fn push(&mut self, ch: char) -> EscapeAction {
+        match self.state {
+            ParserState::Ground if ch == '\x1b' => {
+                self.state = ParserState::Escape;
+                EscapeAction::Consumed
+            }
+            ParserState::Ground => EscapeAction::Print(ch),
+            ParserState::Escape => {
+                // only control sequences are supported, other escapes are dropped.
+                self.state = if ch == '[' {
+                    ParserState::Csi
+                } else {
+                    ParserState::Ground
+                };
+                self.current = EscapeParser::new().current;
+                EscapeAction::Consumed
+            }
+            ParserState::Csi | ParserState::IgnoredCsi => match ch {
+                '0'..='9' if matches!(self.state, ParserState::Csi) => {
+                    if self.current.param_count == 0 {
+                        self.current.param_count = 1;
+                    }
+                    let param = &mut self.current.params[self.current.param_count as usize - 1];
+                    let value = *param as u32 * 10 + (ch as u32 - '0' as u32);
+                    *param = core::cmp::min(value, MAX_PARAM_VALUE as u32) as u16;
+                    EscapeAction::Consumed
+                }
+                ';' if matches!(self.state, ParserState::Csi) => {
+                    if self.current.param_count == 0 {
+                        self.current.param_count = 1;
+                    }
+                    if self.current.param_count as usize == MAX_PARAMS {
+                        self.state = ParserState::IgnoredCsi;
+                    } else {
+                        self.current.param_count += 1;
+                    }
+                    EscapeAction::Consumed
+                }
+                '\x40'..='\x7e' => {
+                    let is_valid = matches!(self.state, ParserState::Csi);
+                    self.state = ParserState::Ground;
+                    if is_valid {
+                        self.current.command = ch;
+                        EscapeAction::Csi(self.current)
+                    } else {
+                        EscapeAction::Consumed
+                    }
+                }
+                '\x20'..='\x3f' => {
+                    self.state = ParserState::IgnoredCsi;
+                    EscapeAction::Consumed
+                }
+                '\x1b' => {
+                    self.state = ParserState::Escape;
+                    EscapeAction::Consumed
+                }
+                _ => {
+                    // any other character aborts the sequence, and is printed normally.
+                    self.state = ParserState::Ground;
+                    EscapeAction::Print(ch)
+                }
+            },
+        }
+    }
+}
@@ -1,28 +1,66 @@
 use crate::{
     display::{
-        modes::{ActiveMode0, Mode0},
+        modes::{sealed, ActiveMode0, Mode0},
+        palette,
+        palette::{PaletteBank, PaletteKind},
         vram::MapAccess,
-        VramTile,
+        vram_alloc,
+        vram_alloc::{CharRange, ScreenBlocks},
+        ActiveDisplayMode, Color, VramTile,
     },
     dma::DmaChannelId,
-    mmio::reg::BG_PALETTE_RAM,
+    input,
+    input::InputState,
+    mmio::reg::{BG_PALETTE_RAM, VCOUNT},
     sync::{Mutex, MutexGuard, Static},
+    sys::Button,
 };
-use core::{fmt, fmt::Arguments, marker::PhantomData};
+use core::{cmp::min, fmt, fmt::Arguments, marker::PhantomData};
+use escape::{CsiSequence, EscapeAction, EscapeParser};
+use scrollback::{Scrollback, ScrollbackLine, SCREEN_LINES};
+
+/// Returns the colors of the palette bank used to render a plane of the font.
+fn plane_colors(plane: usize, (background, foreground): (Color, Color)) -> [Color; 16] {
+    let mask = 1 << (3 - plane);
+    let mut colors = [Color::BLACK; 16];
+    for (j, color) in colors.iter_mut().enumerate() {
+        *color = if (j & mask) != 0 { foreground } else { background };
+    }
+    colors
+}
 
-fn update_palette(id: usize, (background, foreground): (u16, u16)) {
-    let mut start = id * 0x40;
+fn update_palette(banks: &mut Option<[PaletteBank; 16]>, id: usize, colors: (Color, Color)) {
     for i in 0..4 {
-        let mask = 1 << (3 - i);
-        for j in start..start + 0x10 {
-            let color = if (j & mask) != 0 { foreground } else { background };
-            BG_PALETTE_RAM.index(j).write(color);
+        let colors = plane_colors(i, colors);
+
+        // the palette is written immediately, as the terminal may be used with interrupts off.
+        let bank = id * 4 + i;
+        if let Some(banks) = banks {
+            banks[bank].set_colors(0, &colors).write_immediate();
+        } else {
+            for (j, color) in colors.iter().enumerate() {
+                BG_PALETTE_RAM.index(bank * 16 + j).write(*color);
+            }
         }
-        start += 0x10;
     }
 }
 
+/// Allocates the VRAM used by a terminal with a given number of layers, or returns `None` if not
+/// enough is free.
+fn alloc_terminal_vram<T: TerminalFont>(layers: usize) -> Option<(CharRange, ScreenBlocks)> {
+    // the tiles used by the font start from character 0, so it must start at a character base.
+    let char_count = T::get_font_data().len() / 8;
+    let chars = (0..4)
+        .filter_map(|base| vram_alloc::try_alloc_chars_at_base(base, char_count))
+        .find(|chars| chars.first_char() == 0)?;
+    let blocks = vram_alloc::try_alloc_screen_blocks(layers)?;
+    Some((chars, blocks))
+}
+
 /// Represents a font that can be rendered in a terminal.
+///
+/// Custom fonts can be generated from BDF or `.hex` files with the `terminal_font!` macro, which
+/// requires the `terminal_font` feature.
 pub trait TerminalFont {
     /// Returns the glyph that represents a character.
     ///
@@ -37,19 +75,26 @@ pub trait TerminalFont {
     fn get_font_data() -> &'static [u32];
 }
 
+mod escape;
 mod gen_font_ascii;
 mod gen_font_basic;
 mod gen_font_full;
+mod scrollback;
+pub mod widgets;
+mod window;
 
 pub use gen_font_ascii::*;
 pub use gen_font_basic::*;
 pub use gen_font_full::*;
+pub use scrollback::TerminalScrollback;
+pub use window::{TerminalWindow, TerminalWindowWrite};
 
 /// A terminal display mode that makes it easy to display text.
 pub struct Terminal {
     mode: Mode0,
     dma_channel: Option<DmaChannelId>,
-    terminal_colors: [Static<(u16, u16)>; 4],
+    terminal_colors: [Static<(Color, Color)>; 4],
+    scrollback: Option<&'static mut [ScrollbackLine]>,
 }
 impl Terminal {
     pub fn new() -> Self {
@@ -57,11 +102,12 @@ impl Terminal {
             mode: Mode0::new(),
             dma_channel: None,
             terminal_colors: [
-                Static::new((0, !0)),
-                Static::new((0, !0)),
-                Static::new((0, !0)),
-                Static::new((0, !0)),
+                Static::new((Color::BLACK, Color::WHITE)),
+                Static::new((Color::BLACK, Color::WHITE)),
+                Static::new((Color::BLACK, Color::WHITE)),
+                Static::new((Color::BLACK, Color::WHITE)),
             ],
+            scrollback: None,
         }
     }
 
@@ -73,38 +119,62 @@ impl Terminal {
         self.dma_channel = Some(id);
     }
 
-    pub fn set_color(&mut self, id: usize, background: u16, foreground: u16) {
+    pub fn set_color(&mut self, id: usize, background: Color, foreground: Color) {
         if id >= 4 {
             terminal_color_out_of_range();
         }
         self.terminal_colors[id].write((background, foreground));
-        update_palette(id, self.terminal_colors[id].read());
     }
 
     pub fn set_force_blank(&mut self, force_blank: bool) {
         self.mode.set_force_blank(force_blank);
     }
 
+    /// Keeps the lines that scroll off the top of the terminal in the given storage, so that they
+    /// can be viewed later with [`ActiveTerminalAccess::view_scrollback`].
+    ///
+    /// The storage can only be used by a single terminal. The history is cleared whenever the
+    /// terminal is activated.
+    #[track_caller]
+    pub fn set_scrollback<const N: usize>(&mut self, storage: &'static TerminalScrollback<N>) {
+        // the storage can only be claimed once, so this is its only reference.
+        self.scrollback = Some(unsafe { scrollback::init_lines(storage.claim(), N) });
+    }
+
     fn active_raw<T: TerminalFont>(&mut self, no_lock: bool) -> ActiveTerminal<T> {
+        // allocate VRAM for the font and the tile maps of each layer
+        let vram = if no_lock {
+            // VRAM may already be in use if we are panicking, so overwrite it if needed.
+            alloc_terminal_vram::<T>(4)
+        } else {
+            Some(alloc_terminal_vram::<T>(4).unwrap_or_else(|| terminal_vram_full()))
+        };
+        let tile_base = vram.as_ref().map_or(28, |(_, blocks)| blocks.tile_base());
+        let char_base = vram.as_ref().map_or(0, |(chars, _)| chars.char_base());
+
         // configure all layers
         self.mode.layers[0]
             .set_enabled(true)
-            .set_tile_base(28)
+            .set_char_base(char_base)
+            .set_tile_base(tile_base)
             .set_h_offset(4)
             .set_v_offset(4);
         self.mode.layers[1]
             .set_enabled(true)
-            .set_tile_base(29)
+            .set_char_base(char_base)
+            .set_tile_base(tile_base + 1)
             .set_h_offset(8)
             .set_v_offset(4);
         self.mode.layers[2]
             .set_enabled(true)
-            .set_tile_base(30)
+            .set_char_base(char_base)
+            .set_tile_base(tile_base + 2)
             .set_h_offset(4)
             .set_v_offset(4);
         self.mode.layers[3]
             .set_enabled(true)
-            .set_tile_base(31)
+            .set_char_base(char_base)
+            .set_tile_base(tile_base + 3)
             .set_h_offset(8)
             .set_v_offset(4);
 
@@ -122,8 +192,14 @@ impl Terminal {
         }
 
         // upload palette
+        let mut palette = if no_lock {
+            // the palette may be locked if we are panicking, so overwrite it if needed.
+            palette::try_alloc_banks(PaletteKind::Background)
+        } else {
+            Some(palette::alloc_banks(PaletteKind::Background))
+        };
         for i in 0..4 {
-            update_palette(i, self.terminal_colors[i].read());
+            update_palette(&mut palette, i, self.terminal_colors[i].read());
         }
 
         // create an appropriate active terminal object
@@ -145,8 +221,12 @@ impl Terminal {
                 cursor_hw: false,
                 color: 0,
                 line_advance: 0,
+                escape: EscapeParser::new(),
+                scrollback: self.scrollback.as_deref_mut().map(Scrollback::new),
                 mode: active_mode,
                 terminal_colors: &self.terminal_colors,
+                palette,
+                _vram: vram,
                 map,
                 space_ch: [
                     ActiveTerminalAccess::<T>::tile_for_ch('\u{F508}', 0),
@@ -206,7 +286,7 @@ impl<'a, T: TerminalFont> ActiveTerminal<'a, T> {
 
     /// Sets the color for this terminal.
     #[track_caller]
-    pub fn set_color(&self, id: usize, background: u16, foreground: u16) {
+    pub fn set_color(&self, id: usize, background: Color, foreground: Color) {
         self.lock().set_color(id, background, foreground)
     }
     pub fn clear(&self) {
@@ -231,17 +311,31 @@ impl<'a, T: TerminalFont> ActiveTerminal<'a, T> {
     pub fn set_char_half(&self, x: usize, y: usize, ch: char, color: usize) {
         self.lock().set_char_half(x, y, ch, color);
     }
+
+    /// Shows a viewer for the lines that have scrolled off the top of the terminal.
+    ///
+    /// See [`ActiveTerminalAccess::view_scrollback`] for details.
+    pub fn view_scrollback(&self) {
+        self.lock().view_scrollback();
+    }
 }
 
+impl<'a, T: TerminalFont> sealed::Sealed for ActiveTerminal<'a, T> {}
+impl<'a, T: TerminalFont> ActiveDisplayMode for ActiveTerminal<'a, T> {}
+
 struct ActiveTerminalState<'a> {
     cursor_x: u8,
     cursor_y: u8,
     cursor_hw: bool,
     color: u8,
     line_advance: u8,
+    escape: EscapeParser,
+    scrollback: Option<Scrollback<'a>>,
 
     mode: ActiveMode0<'a>,
-    terminal_colors: &'a [Static<(u16, u16)>; 4],
+    terminal_colors: &'a [Static<(Color, Color)>; 4],
+    palette: Option<[PaletteBank; 16]>,
+    _vram: Option<(CharRange, ScreenBlocks)>,
     map: [MapAccess; 4],
 
     space_ch: [VramTile; 4],
@@ -256,20 +350,23 @@ impl<'a> ActiveTerminalState<'a> {
     }
     #[track_caller]
     fn check_coordinate(x: usize, y: usize) {
-        if x >= 58 && y >= 19 {
+        if x >= 58 || y >= 19 {
             terminal_coord_out_of_range();
         }
     }
 
-    fn set_color(&self, id: usize, background: u16, foreground: u16) {
+    fn set_color(&mut self, id: usize, background: Color, foreground: Color) {
         self.terminal_colors[id].write((background, foreground));
-        update_palette(id, self.terminal_colors[id].read());
+        update_palette(&mut self.palette, id, self.terminal_colors[id].read());
     }
     fn clear(&mut self) {
         self.cursor_x = 0;
         self.cursor_y = 0;
         self.color = 0;
         self.line_advance = 0;
+        for i in 0..4 {
+            self.mode.layers[i].set_v_offset(4);
+        }
 
         let tile = self.space_ch[0];
         if let Some(channel) = self.dma_channel {
@@ -341,6 +438,13 @@ impl<'a> ActiveTerminalState<'a> {
         }
     }
     fn advance_screen(&mut self) {
+        let y = self.apply_advance(0);
+        if let Some(scrollback) = &mut self.scrollback {
+            let line = scrollback.push();
+            for i in 0..4 {
+                self.map[i].read_tiles(0, y, &mut line.maps[i]);
+            }
+        }
         self.clear_line(0);
 
         self.line_advance += 1;
@@ -352,6 +456,33 @@ impl<'a> ActiveTerminalState<'a> {
             self.mode.layers[i].set_v_offset(4 - self.line_advance as i16 * 8);
         }
     }
+
+    /// Copies the lines on the screen after the scrollback history.
+    fn save_screen(&mut self) {
+        let advance = self.line_advance as usize;
+        if let Some(scrollback) = &mut self.scrollback {
+            let start = scrollback.history_len();
+            for row in 0..SCREEN_LINES {
+                let line = scrollback.line(start + row);
+                for i in 0..4 {
+                    self.map[i].read_tiles(0, (row + advance) % 32, &mut line.maps[i]);
+                }
+            }
+        }
+    }
+    /// Shows the lines of the scrollback starting from a given line.
+    fn show_scrollback(&mut self, top: usize) {
+        let advance = self.line_advance as usize;
+        if let Some(scrollback) = &mut self.scrollback {
+            for row in 0..SCREEN_LINES {
+                let line = scrollback.line(top + row);
+                for i in 0..4 {
+                    self.map[i].set_tiles(0, (row + advance) % 32, &line.maps[i]);
+                }
+            }
+        }
+    }
+
     fn advance_cursor(&mut self) {
         self.cursor_x = 0;
         if self.cursor_y == 18 {
@@ -373,7 +504,7 @@ impl<'a, 'b: 'a, T: TerminalFont> ActiveTerminalAccess<'a, 'b, T> {
     }
 
     #[track_caller]
-    pub fn set_color(&self, id: usize, background: u16, foreground: u16) {
+    pub fn set_color(&mut self, id: usize, background: Color, foreground: Color) {
         if id >= 4 {
             terminal_color_out_of_range();
         }
@@ -447,6 +578,9 @@ impl<'a, 'b: 'a, T: TerminalFont> ActiveTerminalAccess<'a, 'b, T> {
         }
         self.term.color = color as u8;
     }
+    pub fn active_color(&self) -> usize {
+        self.term.color as usize
+    }
 
     pub fn set_half_width(&mut self, half_width: bool) {
         self.term.set_half_width(half_width);
@@ -456,6 +590,125 @@ impl<'a, 'b: 'a, T: TerminalFont> ActiveTerminalAccess<'a, 'b, T> {
         self.term.advance_cursor();
     }
 
+    /// Returns the number of lines that have scrolled off the top of the terminal and are kept in
+    /// its scrollback.
+    pub fn scrollback_lines(&self) -> usize {
+        self.term.scrollback.as_ref().map_or(0, |x| x.history_len())
+    }
+
+    /// Shows a viewer for the lines that have scrolled off the top of the terminal.
+    ///
+    /// Up and Down scroll by a single line, and L and R by a full screen. The viewer is closed,
+    /// and the screen restored, when B is pressed. This returns immediately if the terminal has
+    /// no scrollback.
+    ///
+    /// This waits for VBlank without using interrupts, so it can be used while they are disabled,
+    /// and reads the keypad directly, ignoring any input recording that is being replayed.
+    pub fn view_scrollback(&mut self) {
+        if self.term.scrollback.is_none() {
+            return;
+        }
+        self.term.save_screen();
+
+        let bottom = self.scrollback_lines();
+        let mut top = bottom;
+        let mut keys = InputState::with_keys(input::keypad_keys());
+        loop {
+            wait_for_vblank_polling();
+            keys.update_with(input::keypad_keys());
+
+            if keys.is_just_pressed(Button::B) {
+                break;
+            }
+            let mut new_top = top;
+            if keys.is_repeated(Button::Up) {
+                new_top = new_top.saturating_sub(1);
+            }
+            if keys.is_repeated(Button::Down) {
+                new_top = min(new_top + 1, bottom);
+            }
+            if keys.is_repeated(Button::L) {
+                new_top = new_top.saturating_sub(SCREEN_LINES);
+            }
+            if keys.is_repeated(Button::R) {
+                new_top = min(new_top + SCREEN_LINES, bottom);
+            }
+            if new_top != top {
+                top = new_top;
+                self.term.show_scrollback(top);
+            }
+        }
+
+        self.term.show_scrollback(bottom);
+    }
+
+    /// Clears the half-width columns from `start` to `end` (exclusive) of a line.
+    fn clear_span(&mut self, y: usize, start: usize, end: usize) {
+        for x in start..end {
+            self.set_char_half(x, y, ' ', 0);
+        }
+    }
+
+    /// Applies a control sequence written to the terminal. Unsupported sequences are ignored.
+    fn apply_csi(&mut self, seq: &CsiSequence) {
+        const MAX_X: usize = 57;
+        const MAX_Y: usize = 18;
+
+        let (x, y) = self.cursor();
+        let count = seq.param_or(0, 1) as usize;
+        match seq.command {
+            'm' if seq.params().is_empty() => self.term.color = 0,
+            'm' => {
+                for &param in seq.params() {
+                    match param {
+                        0 | 39 | 49 => self.term.color = 0,
+                        30..=33 => self.term.color = (param - 30) as u8,
+                        40..=43 => self.term.color = (param - 40) as u8,
+                        _ => {}
+                    }
+                }
+            }
+            'H' | 'f' => {
+                let y = seq.param_or(0, 1) as usize - 1;
+                let x = seq.param_or(1, 1) as usize - 1;
+                self.set_cursor(min(x, MAX_X), min(y, MAX_Y));
+            }
+            'A' => self.set_cursor(x, y.saturating_sub(count)),
+            'B' => self.set_cursor(x, min(y + count, MAX_Y)),
+            'C' => self.set_cursor(min(x + count, MAX_X), y),
+            'D' => self.set_cursor(x.saturating_sub(count), y),
+            'G' => self.set_cursor(min(count - 1, MAX_X), y),
+            'J' => match seq.param_or(0, 0) {
+                0 => {
+                    self.clear_span(y, x, MAX_X + 1);
+                    for y in y + 1..=MAX_Y {
+                        self.clear_line(y);
+                    }
+                }
+                1 => {
+                    for y in 0..y {
+                        self.clear_line(y);
+                    }
+                    self.clear_span(y, 0, x + 1);
+                }
+                2 | 3 => {
+                    let color = self.term.color;
+                    self.clear();
+                    self.term.color = color;
+                    self.set_cursor(x, y);
+                }
+                _ => {}
+            },
+            'K' => match seq.param_or(0, 0) {
+                0 => self.clear_span(y, x, MAX_X + 1),
+                1 => self.clear_span(y, 0, x + 1),
+                2 => self.clear_line(y),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
     pub fn write_char(&mut self, ch: char) {
         let ch = self.process_hw_conditional(ch);
 
@@ -498,6 +751,22 @@ impl<'a, 'b: 'a, T: TerminalFont> ActiveTerminalAccess<'a, 'b, T> {
     }
 }
 
+/// A buffered writer for an [`ActiveTerminal`], which wraps lines between words.
+///
+/// The writer understands a subset of the ANSI escape sequences, which allows formatted output
+/// to change colors and move the cursor:
+///
+/// * `ESC[30m` to `ESC[33m` (or `ESC[40m` to `ESC[43m`) select one of the four terminal colors,
+///   and `ESC[0m` selects color 0.
+/// * `ESC[{row};{column}H` moves the cursor, and `ESC[{n}A`, `ESC[{n}B`, `ESC[{n}C` and
+///   `ESC[{n}D` move it up, down, right and left respectively. `ESC[{column}G` moves the cursor
+///   within the current line. Rows and columns start at 1, and columns are counted in
+///   half-width characters.
+/// * `ESC[J`, `ESC[1J` and `ESC[2J` clear the screen from the cursor to the end, from the start
+///   to the cursor, and entirely. `ESC[K`, `ESC[1K` and `ESC[2K` clear the current line in the
+///   same way. Neither moves the cursor.
+///
+/// Other sequences are ignored.
 pub struct ActiveTerminalWrite<'a, 'b: 'a, 'c: 'a + 'b, T: TerminalFont> {
     access: &'a mut ActiveTerminalAccess<'b, 'c, T>,
     buffer: [u16; 60],
@@ -544,6 +813,17 @@ impl<'a, 'b: 'a, 'c: 'a + 'b, T: TerminalFont> ActiveTerminalWrite<'a, 'b, 'c, T
         }
     }
     pub fn write_char(&mut self, ch: char) {
+        match self.access.term.escape.push(ch) {
+            EscapeAction::Print(ch) => self.write_plain_char(ch),
+            EscapeAction::Consumed => {}
+            EscapeAction::Csi(seq) => {
+                self.flush_buffers();
+                self.access.apply_csi(&seq);
+                self.synthetic_newline = false;
+            }
+        }
+    }
+    fn write_plain_char(&mut self, ch: char) {
         match ch {
             ' ' => {
                 self.flush_buffers();
@@ -559,7 +839,7 @@ impl<'a, 'b: 'a, 'c: 'a + 'b, T: TerminalFont> ActiveTerminalWrite<'a, 'b, 'c, T
                     self.access.write_char(' ');
                     self.access.term.cursor_hw = false;
                 }
-                while self.access.term.cursor_x % 8 == 0 {
+                while !self.access.term.cursor_x.is_multiple_of(8) {
                     self.access.write_char(' ');
                 }
             }
@@ -603,6 +883,12 @@ impl<'a, 'b: 'a, 'c: 'a + 'b, T: TerminalFont> Drop for ActiveTerminalWrite<'a,
     }
 }
 
+/// Waits for the start of the next VBlank by polling the current scanline.
+fn wait_for_vblank_polling() {
+    while VCOUNT.read() >= 160 {}
+    while VCOUNT.read() < 160 {}
+}
+
 #[inline(never)]
 #[track_caller]
 fn terminal_coord_out_of_range() -> ! {
@@ -629,3 +915,9 @@ fn terminal_lock_failure() -> ! {
 fn terminal_invalid_dma_channel() -> ! {
     crate::panic_handler::static_panic("DMA channel cannot be used for terminal rendering!")
 }
+
+#[inline(never)]
+#[track_caller]
+fn terminal_vram_full() -> ! {
+    crate::panic_handler::static_panic("Not enough background VRAM is free for the terminal!")
+}
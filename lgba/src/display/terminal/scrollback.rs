@@ -0,0 +1,114 @@
+use crate::{display::VramTile, sync::Static};
+use core::{cell::UnsafeCell, mem::MaybeUninit};
+
+/// The number of lines shown on the screen at once.
+pub(crate) const SCREEN_LINES: usize = 19;
+
+/// The number of tiles of each layer stored for a line.
+const LINE_TILES: usize = 30;
+
+/// The tiles of every terminal layer for a single line.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct ScrollbackLine {
+    pub(crate) maps: [[VramTile; LINE_TILES]; 4],
+}
+
+/// Storage for the lines that scroll off the top of a terminal.
+///
+/// This is meant to be placed in a static, and passed to [`Terminal::set_scrollback`]. Each line
+/// takes 240 bytes, so the static should usually be placed in EWRAM with `#[lgba::ewram]`. The
+/// last 19 lines are reserved for the viewer, which uses them to save the contents of the screen.
+///
+/// [`Terminal::set_scrollback`]: crate::display::Terminal::set_scrollback
+pub struct TerminalScrollback<const N: usize> {
+    is_used: Static<bool>,
+    lines: UnsafeCell<MaybeUninit<[ScrollbackLine; N]>>,
+}
+impl<const N: usize> Default for TerminalScrollback<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<const N: usize> TerminalScrollback<N> {
+    /// Creates new scrollback storage.
+    pub const fn new() -> Self {
+        TerminalScrollback {
+            is_used: Static::new(false),
+            lines: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Claims the storage for a terminal, returning a pointer to its `N` lines.
+    #[track_caller]
+    pub(crate) fn claim(&'static self) -> *mut ScrollbackLine {
+        if N <= SCREEN_LINES {
+            scrollback_too_small();
+        }
+        if self.is_used.replace(true) {
+            scrollback_in_use();
+        }
+        self.lines.get() as *mut ScrollbackLine
+    }
+}
+unsafe impl<const N: usize> Send for TerminalScrollback<N> {}
+unsafe impl<const N: usize> Sync for TerminalScrollback<N> {}
+
+/// Initializes a number of scrollback lines in uninitialized memory.
+pub(crate) unsafe fn init_lines(
+    ptr: *mut ScrollbackLine,
+    count: usize,
+) -> &'static mut [ScrollbackLine] {
+    // tiles are plain integers, so the zeroed memory is a valid (if empty) line.
+    core::ptr::write_bytes(ptr, 0, count);
+    core::slice::from_raw_parts_mut(ptr, count)
+}
+
+/// A ring buffer of the lines that scrolled off the top of a terminal.
+///
+/// The buffer is followed by [`SCREEN_LINES`] lines that can store the contents of the screen,
+/// which lets the history and the screen be viewed as one list of lines.
+pub(crate) struct Scrollback<'a> {
+    lines: &'a mut [ScrollbackLine],
+    head: usize,
+    len: usize,
+}
+impl<'a> Scrollback<'a> {
+    pub fn new(lines: &'a mut [ScrollbackLine]) -> Self {
+        Scrollback { lines, head: 0, len: 0 }
+    }
+
+    /// Returns the number of lines in the history.
+    pub fn history_len(&self) -> usize {
+        self.len
+    }
+
+    /// Adds a new line to the history, removing the oldest line if it is full.
+    pub fn push(&mut self) -> &mut ScrollbackLine {
+        let id = self.head;
+        self.head = (self.head + 1) % self.lines.len();
+        self.len = core::cmp::min(self.len + 1, self.lines.len() - SCREEN_LINES);
+        &mut self.lines[id]
+    }
+
+    /// Returns a line of the history or the saved screen.
+    ///
+    /// Line 0 is the oldest line in the history, and the saved screen starts at
+    /// [`Scrollback::history_len`].
+    pub fn line(&mut self, id: usize) -> &mut ScrollbackLine {
+        let total = self.lines.len();
+        &mut self.lines[(self.head + total - self.len + id) % total]
+    }
+}
+
+#[inline(never)]
+#[track_caller]
+fn scrollback_too_small() -> ! {
+    crate::panic_handler::static_panic("Terminal scrollback must store more than 19 lines!")
+}
+
+#[inline(never)]
+#[track_caller]
+fn scrollback_in_use() -> ! {
+    crate::panic_handler::static_panic("Terminal scrollback is already in use!")
+}
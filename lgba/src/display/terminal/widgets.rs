@@ -0,0 +1,578 @@
+//! Interactive widgets drawn on an [`ActiveTerminal`], such as menus and dialogs.
+//!
+//! Each widget is drawn at a fixed position of the terminal. Widgets that take input have an
+//! `update` method that reads the keypad once and should be called every frame after the widget
+//! is first drawn, and a `run` method that draws the widget and waits for it to finish.
+//!
+//! Coordinates and widths are given in half-width columns, as with
+//! [`ActiveTerminalAccess::set_cursor`]. Widgets must end before the last two columns of the
+//! terminal, as writing there moves the cursor onto the next line.
+//!
+//! [`ActiveTerminal`]: crate::display::ActiveTerminal
+
+use crate::{
+    display::{ActiveTerminalAccess, TerminalFont},
+    input::InputState,
+    sys::Button,
+};
+use core::{fmt, fmt::Write};
+
+const MAX_X: usize = 56;
+const MAX_Y: usize = 19;
+
+/// The result of updating an interactive widget.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum WidgetResult<T> {
+    /// The widget is still waiting for input.
+    Pending,
+    /// The widget was accepted with A.
+    Accepted(T),
+    /// The widget was cancelled with B.
+    Cancelled,
+}
+
+/// A buffer used to format a line of text without allocating.
+struct LineBuffer {
+    data: [u8; 64],
+    len: usize,
+}
+impl LineBuffer {
+    fn new() -> Self {
+        LineBuffer { data: [0; 64], len: 0 }
+    }
+    fn as_str(&self) -> &str {
+        // only whole strings or characters are ever written to the buffer.
+        unsafe { core::str::from_utf8_unchecked(&self.data[..self.len]) }
+    }
+}
+impl Write for LineBuffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for ch in s.chars() {
+            let len = ch.len_utf8();
+            if self.len + len > self.data.len() {
+                break;
+            }
+            ch.encode_utf8(&mut self.data[self.len..]);
+            self.len += len;
+        }
+        Ok(())
+    }
+}
+
+#[track_caller]
+fn check_region(x: usize, y: usize, width: usize, height: usize) {
+    if x + width > MAX_X || y + height > MAX_Y {
+        widget_out_of_range();
+    }
+}
+
+/// Draws text in a line of the terminal, padding it with spaces or cutting it to a width.
+fn draw_text<T: TerminalFont>(
+    term: &mut ActiveTerminalAccess<'_, '_, T>,
+    (x, y): (usize, usize),
+    width: usize,
+    text: &str,
+    color: usize,
+) {
+    let old_color = term.active_color();
+    term.set_active_color(color);
+    term.set_cursor(x, y);
+    for ch in text.chars() {
+        if term.cursor().0 + 2 > x + width {
+            break;
+        }
+        term.write_char(ch);
+    }
+    while term.cursor().0 + 2 <= x + width {
+        term.write_char(' ');
+    }
+    term.set_active_color(old_color);
+}
+
+/// Draws a formatted line of text.
+fn draw_fmt<T: TerminalFont>(
+    term: &mut ActiveTerminalAccess<'_, '_, T>,
+    pos: (usize, usize),
+    width: usize,
+    args: fmt::Arguments,
+    color: usize,
+) {
+    let mut buffer = LineBuffer::new();
+    let _ = buffer.write_fmt(args);
+    draw_text(term, pos, width, buffer.as_str(), color);
+}
+
+/// A list of items, one of which can be selected with the keypad.
+///
+/// Up and Down move the cursor, A selects the current item, and B cancels the menu if it is
+/// cancellable. If there are more items than fit in the menu, it scrolls to keep the cursor
+/// visible.
+pub struct Menu<'a> {
+    items: &'a [&'a str],
+    x: u8,
+    y: u8,
+    width: u8,
+    height: u8,
+    selected: usize,
+    top: usize,
+    highlight_color: u8,
+    is_cancellable: bool,
+    keys: InputState,
+}
+impl<'a> Menu<'a> {
+    /// Creates a new menu in the top left corner of the terminal, filling the screen.
+    pub fn new(items: &'a [&'a str]) -> Self {
+        Menu {
+            items,
+            x: 0,
+            y: 0,
+            width: MAX_X as u8,
+            height: MAX_Y as u8,
+            selected: 0,
+            top: 0,
+            highlight_color: 0,
+            is_cancellable: true,
+            keys: InputState::new(),
+        }
+    }
+
+    /// Sets the position of the top left corner of the menu.
+    pub fn set_position(&mut self, x: usize, y: usize) -> &mut Self {
+        self.x = x as u8;
+        self.y = y as u8;
+        self
+    }
+
+    /// Sets the width of the menu in half-width columns, and the number of items shown at once.
+    pub fn set_size(&mut self, width: usize, height: usize) -> &mut Self {
+        self.width = width as u8;
+        self.height = height as u8;
+        self.scroll_to_selected();
+        self
+    }
+
+    /// Sets the terminal color used to draw the selected item.
+    #[track_caller]
+    pub fn set_highlight_color(&mut self, color: usize) -> &mut Self {
+        if color >= 4 {
+            widget_color_out_of_range();
+        }
+        self.highlight_color = color as u8;
+        self
+    }
+
+    /// Sets whether the menu can be cancelled with B. Menus are cancellable by default.
+    pub fn set_cancellable(&mut self, cancellable: bool) -> &mut Self {
+        self.is_cancellable = cancellable;
+        self
+    }
+
+    /// Moves the cursor to an item.
+    pub fn set_selected(&mut self, selected: usize) -> &mut Self {
+        self.selected = selected.min(self.items.len().saturating_sub(1));
+        self.scroll_to_selected();
+        self
+    }
+
+    /// Returns the index of the item under the cursor.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    fn scroll_to_selected(&mut self) {
+        let height = (self.height as usize).max(1);
+        if self.selected < self.top {
+            self.top = self.selected;
+        } else if self.selected >= self.top + height {
+            self.top = self.selected + 1 - height;
+        }
+    }
+
+    /// Draws the menu.
+    #[track_caller]
+    pub fn draw<T: TerminalFont>(&self, term: &mut ActiveTerminalAccess<'_, '_, T>) {
+        let (x, y, width, height) =
+            (self.x as usize, self.y as usize, self.width as usize, self.height as usize);
+        check_region(x, y, width, height);
+        if width < 6 {
+            widget_out_of_range();
+        }
+
+        for row in 0..height {
+            let index = self.top + row;
+            let text = self.items.get(index).copied().unwrap_or("");
+            let (cursor, color) = if index == self.selected && index < self.items.len() {
+                (">", self.highlight_color as usize)
+            } else {
+                (" ", 0)
+            };
+            let indicator = if row == 0 && self.top > 0 {
+                "^"
+            } else if row == height - 1 && self.top + height < self.items.len() {
+                "v"
+            } else {
+                " "
+            };
+
+            draw_text(term, (x, y + row), 2, cursor, 0);
+            draw_text(term, (x + 2, y + row), width - 4, text, color);
+            draw_text(term, (x + width - 2, y + row), 2, indicator, 0);
+        }
+    }
+
+    /// Reads the keypad and updates the menu.
+    #[track_caller]
+    pub fn update<T: TerminalFont>(
+        &mut self,
+        term: &mut ActiveTerminalAccess<'_, '_, T>,
+    ) -> WidgetResult<usize> {
+        self.keys.update();
+        if self.is_cancellable && self.keys.is_just_pressed(Button::B) {
+            return WidgetResult::Cancelled;
+        }
+        if self.items.is_empty() {
+            return WidgetResult::Pending;
+        }
+
+        if self.keys.is_just_pressed(Button::A) {
+            return WidgetResult::Accepted(self.selected);
+        }
+
+        let last = self.items.len() - 1;
+        let new_selected = if self.keys.is_repeated(Button::Up) {
+            if self.selected == 0 {
+                last
+            } else {
+                self.selected - 1
+            }
+        } else if self.keys.is_repeated(Button::Down) {
+            if self.selected == last {
+                0
+            } else {
+                self.selected + 1
+            }
+        } else {
+            self.selected
+        };
+        if new_selected != self.selected {
+            self.selected = new_selected;
+            self.scroll_to_selected();
+            self.draw(term);
+        }
+        WidgetResult::Pending
+    }
+
+    /// Draws the menu and waits until an item is selected, returning its index.
+    ///
+    /// Returns `None` if the menu was cancelled.
+    #[track_caller]
+    pub fn run<T: TerminalFont>(
+        &mut self,
+        term: &mut ActiveTerminalAccess<'_, '_, T>,
+    ) -> Option<usize> {
+        self.draw(term);
+        run_widget(|| self.update(term))
+    }
+}
+
+/// A message with a choice between "Yes" and "No".
+///
+/// Left and Right move the cursor, and A confirms the choice. B cancels the dialog, which is
+/// treated as choosing "No" by [`ConfirmDialog::run`].
+pub struct ConfirmDialog<'a> {
+    message: &'a str,
+    x: u8,
+    y: u8,
+    width: u8,
+    choice: bool,
+    keys: InputState,
+}
+impl<'a> ConfirmDialog<'a> {
+    /// Creates a new dialog in the top left corner of the terminal.
+    ///
+    /// The message may contain newlines. The choices are shown on the line after the message,
+    /// with "No" initially selected.
+    pub fn new(message: &'a str) -> Self {
+        ConfirmDialog {
+            message,
+            x: 0,
+            y: 0,
+            width: MAX_X as u8,
+            choice: false,
+            keys: InputState::new(),
+        }
+    }
+
+    /// Sets the position of the top left corner of the dialog.
+    pub fn set_position(&mut self, x: usize, y: usize) -> &mut Self {
+        self.x = x as u8;
+        self.y = y as u8;
+        self
+    }
+
+    /// Sets the width of the dialog in half-width columns.
+    pub fn set_width(&mut self, width: usize) -> &mut Self {
+        self.width = width as u8;
+        self
+    }
+
+    /// Sets the initially selected choice.
+    pub fn set_default(&mut self, choice: bool) -> &mut Self {
+        self.choice = choice;
+        self
+    }
+
+    /// Returns the number of lines the dialog takes up.
+    pub fn height(&self) -> usize {
+        self.message.lines().count() + 1
+    }
+
+    /// Draws the dialog.
+    #[track_caller]
+    pub fn draw<T: TerminalFont>(&self, term: &mut ActiveTerminalAccess<'_, '_, T>) {
+        let (x, y, width) = (self.x as usize, self.y as usize, self.width as usize);
+        check_region(x, y, width, self.height());
+
+        for (i, line) in self.message.lines().enumerate() {
+            draw_text(term, (x, y + i), width, line, 0);
+        }
+        let (yes, no) = if self.choice { (">", " ") } else { (" ", ">") };
+        draw_fmt(term, (x, y + self.height() - 1), width, format_args!("  {yes}Yes  {no}No"), 0);
+    }
+
+    /// Reads the keypad and updates the dialog.
+    #[track_caller]
+    pub fn update<T: TerminalFont>(
+        &mut self,
+        term: &mut ActiveTerminalAccess<'_, '_, T>,
+    ) -> WidgetResult<bool> {
+        self.keys.update();
+        if self.keys.is_just_pressed(Button::A) {
+            return WidgetResult::Accepted(self.choice);
+        }
+        if self.keys.is_just_pressed(Button::B) {
+            return WidgetResult::Cancelled;
+        }
+        if self.keys.is_just_pressed(Button::Left) || self.keys.is_just_pressed(Button::Right) {
+            self.choice = !self.choice;
+            self.draw(term);
+        }
+        WidgetResult::Pending
+    }
+
+    /// Draws the dialog and waits for a choice, returning `true` if "Yes" was chosen.
+    #[track_caller]
+    pub fn run<T: TerminalFont>(&mut self, term: &mut ActiveTerminalAccess<'_, '_, T>) -> bool {
+        self.draw(term);
+        run_widget(|| self.update(term)).unwrap_or(false)
+    }
+}
+
+/// A number that can be changed with the keypad, within a range.
+///
+/// Up and Down change the number by one step, and L and R by ten steps. A accepts the number,
+/// and B cancels the spinner.
+pub struct NumberSpinner<'a> {
+    label: &'a str,
+    x: u8,
+    y: u8,
+    width: u8,
+    value: i32,
+    min: i32,
+    max: i32,
+    step: i32,
+    keys: InputState,
+}
+impl<'a> NumberSpinner<'a> {
+    /// Creates a new spinner in the top left corner of the terminal, with a given range.
+    pub fn new(label: &'a str, min: i32, max: i32) -> Self {
+        NumberSpinner {
+            label,
+            x: 0,
+            y: 0,
+            width: MAX_X as u8,
+            value: min,
+            min,
+            max: max.max(min),
+            step: 1,
+            keys: InputState::new(),
+        }
+    }
+
+    /// Sets the position of the spinner.
+    pub fn set_position(&mut self, x: usize, y: usize) -> &mut Self {
+        self.x = x as u8;
+        self.y = y as u8;
+        self
+    }
+
+    /// Sets the width of the spinner in half-width columns.
+    pub fn set_width(&mut self, width: usize) -> &mut Self {
+        self.width = width as u8;
+        self
+    }
+
+    /// Sets the amount the number changes by when Up or Down is pressed. The default is 1.
+    pub fn set_step(&mut self, step: i32) -> &mut Self {
+        self.step = step.max(1);
+        self
+    }
+
+    /// Sets the current number, clamping it to the range of the spinner.
+    pub fn set_value(&mut self, value: i32) -> &mut Self {
+        self.value = value.clamp(self.min, self.max);
+        self
+    }
+
+    /// Returns the current number.
+    pub fn value(&self) -> i32 {
+        self.value
+    }
+
+    /// Draws the spinner.
+    #[track_caller]
+    pub fn draw<T: TerminalFont>(&self, term: &mut ActiveTerminalAccess<'_, '_, T>) {
+        let (x, y, width) = (self.x as usize, self.y as usize, self.width as usize);
+        check_region(x, y, width, 1);
+
+        let left = if self.value > self.min { "<" } else { " " };
+        let right = if self.value < self.max { ">" } else { " " };
+        let label = self.label;
+        let value = self.value;
+        draw_fmt(term, (x, y), width, format_args!("{label} {left}{value}{right}"), 0);
+    }
+
+    /// Reads the keypad and updates the spinner.
+    #[track_caller]
+    pub fn update<T: TerminalFont>(
+        &mut self,
+        term: &mut ActiveTerminalAccess<'_, '_, T>,
+    ) -> WidgetResult<i32> {
+        self.keys.update();
+        if self.keys.is_just_pressed(Button::A) {
+            return WidgetResult::Accepted(self.value);
+        }
+        if self.keys.is_just_pressed(Button::B) {
+            return WidgetResult::Cancelled;
+        }
+
+        let mut delta = 0;
+        if self.keys.is_repeated(Button::Up) {
+            delta += self.step;
+        }
+        if self.keys.is_repeated(Button::Down) {
+            delta -= self.step;
+        }
+        if self.keys.is_repeated(Button::R) {
+            delta += self.step * 10;
+        }
+        if self.keys.is_repeated(Button::L) {
+            delta -= self.step * 10;
+        }
+        let new_value = self.value.saturating_add(delta).clamp(self.min, self.max);
+        if new_value != self.value {
+            self.value = new_value;
+            self.draw(term);
+        }
+        WidgetResult::Pending
+    }
+
+    /// Draws the spinner and waits until a number is accepted, returning it.
+    ///
+    /// Returns `None` if the spinner was cancelled.
+    #[track_caller]
+    pub fn run<T: TerminalFont>(
+        &mut self,
+        term: &mut ActiveTerminalAccess<'_, '_, T>,
+    ) -> Option<i32> {
+        self.draw(term);
+        run_widget(|| self.update(term))
+    }
+}
+
+/// A bar showing the progress of a task, with a percentage.
+pub struct ProgressBar {
+    x: u8,
+    y: u8,
+    width: u8,
+    progress: u32,
+    total: u32,
+}
+impl Default for ProgressBar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl ProgressBar {
+    /// Creates a new progress bar in the top left corner of the terminal.
+    pub fn new() -> Self {
+        ProgressBar { x: 0, y: 0, width: MAX_X as u8, progress: 0, total: 1 }
+    }
+
+    /// Sets the position of the progress bar.
+    pub fn set_position(&mut self, x: usize, y: usize) -> &mut Self {
+        self.x = x as u8;
+        self.y = y as u8;
+        self
+    }
+
+    /// Sets the width of the progress bar in half-width columns, including the percentage.
+    pub fn set_width(&mut self, width: usize) -> &mut Self {
+        self.width = width as u8;
+        self
+    }
+
+    /// Sets the progress, out of a total amount of work.
+    pub fn set_progress(&mut self, progress: u32, total: u32) -> &mut Self {
+        self.total = total.max(1);
+        self.progress = progress.min(self.total);
+        self
+    }
+
+    /// Draws the progress bar.
+    #[track_caller]
+    pub fn draw<T: TerminalFont>(&self, term: &mut ActiveTerminalAccess<'_, '_, T>) {
+        let (x, y, width) = (self.x as usize, self.y as usize, self.width as usize);
+        check_region(x, y, width, 1);
+        if width < 16 {
+            widget_out_of_range();
+        }
+
+        // leave space for the brackets and the percentage
+        let cells = width / 2 - 6;
+        let filled = (self.progress as u64 * cells as u64 / self.total as u64) as usize;
+        let percent = self.progress as u64 * 100 / self.total as u64;
+
+        let mut buffer = LineBuffer::new();
+        let _ = buffer.write_str("[");
+        for i in 0..cells {
+            let _ = buffer.write_str(if i < filled { "#" } else { "-" });
+        }
+        let _ = write!(buffer, "]{percent:>3}%");
+        draw_text(term, (x, y), width, buffer.as_str(), 0);
+    }
+}
+
+/// Calls a widget's update function once per frame until it finishes.
+fn run_widget<T>(mut update: impl FnMut() -> WidgetResult<T>) -> Option<T> {
+    loop {
+        crate::sys::wait_for_vblank();
+        match update() {
+            WidgetResult::Pending => {}
+            WidgetResult::Accepted(value) => return Some(value),
+            WidgetResult::Cancelled => return None,
+        }
+    }
+}
+
+#[inline(never)]
+#[track_caller]
+fn widget_out_of_range() -> ! {
+    crate::panic_handler::static_panic("Widget does not fit in the terminal.")
+}
+
+#[inline(never)]
+#[track_caller]
+fn widget_color_out_of_range() -> ! {
+    crate::panic_handler::static_panic("Terminal color must be between 0 and 3 inclusive.")
+}
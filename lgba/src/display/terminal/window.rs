@@ -0,0 +1,404 @@
+use super::{
+    escape::{CsiSequence, EscapeAction, EscapeParser},
+    plane_colors, terminal_color_out_of_range, terminal_vram_full, TerminalFont,
+};
+use crate::display::{
+    palette,
+    palette::{PaletteBank, PaletteKind},
+    vram::MapAccess,
+    ActiveTileLayer, Color, TileLayerSize, VramTile,
+};
+use core::{fmt, fmt::Arguments, marker::PhantomData};
+
+/// A terminal that renders into a rectangular region of a pair of tile layers.
+///
+/// Unlike [`Terminal`], this does not take over the display mode, so the remaining layers and the
+/// rest of the screen stay available for other graphics. Characters are always rendered in
+/// full-width cells, so the window is `width` characters wide.
+///
+/// The text layer must be drawn above the background layer, which fills the region with the
+/// background color. Each color used by the window takes up four background palette banks, which
+/// are only allocated once the color is first used. If not enough banks are free, characters in
+/// that color are drawn in the default color instead.
+///
+/// The window configures the character and tile map bases of both layers. Its VRAM is owned by the
+/// text layer and shared with the background layer, so it stays allocated until the text layer is
+/// reconfigured, and both layers should be reconfigured together after the window is dropped.
+///
+/// [`Terminal`]: crate::display::Terminal
+pub struct TerminalWindow<'a, T: TerminalFont> {
+    x: u8,
+    y: u8,
+    width: u8,
+    height: u8,
+    cursor_x: u8,
+    cursor_y: u8,
+    color: u8,
+    escape: EscapeParser,
+
+    text: MapAccess,
+    background: MapAccess,
+    colors: [(Color, Color); 4],
+    banks: [Option<[PaletteBank; 4]>; 4],
+    blank_ch: VramTile,
+    _phantom: PhantomData<(&'a mut (), T)>,
+}
+impl<'a, T: TerminalFont> TerminalWindow<'a, T> {
+    /// Creates a new terminal window covering the given region of the screen, in tiles.
+    ///
+    /// The region must fit inside the 30x20 tiles visible on the screen.
+    #[track_caller]
+    pub fn new(
+        text: &'a mut ActiveTileLayer<'_>,
+        background: &'a mut ActiveTileLayer<'_>,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Self {
+        if width == 0 || height == 0 || x + width > 30 || y + height > 20 {
+            window_region_out_of_range();
+        }
+
+        // allocate VRAM and configure the layers
+        let (chars, blocks) =
+            super::alloc_terminal_vram::<T>(2).unwrap_or_else(|| terminal_vram_full());
+        let (char_base, tile_base) = (chars.char_base(), blocks.tile_base());
+        chars.char_access().write_char_4bpp(0, T::get_font_data());
+        text.set_tile_map_size(TileLayerSize::Map256x256)
+            .set_char_range(chars)
+            .set_screen_blocks(blocks)
+            .set_offset(0, 0)
+            .set_enabled(true);
+        background
+            .set_char_base(char_base)
+            .set_tile_base(tile_base + 1)
+            .set_tile_map_size(TileLayerSize::Map256x256)
+            .set_offset(0, 0)
+            .set_enabled(true);
+
+        let (_, blank_tile, _) = T::get_font_glyph('\u{F508}');
+        let mut window = TerminalWindow {
+            x: x as u8,
+            y: y as u8,
+            width: width as u8,
+            height: height as u8,
+            cursor_x: 0,
+            cursor_y: 0,
+            color: 0,
+            escape: EscapeParser::new(),
+            text: text.map_access(0),
+            background: background.map_access(0),
+            colors: [(Color::BLACK, Color::WHITE); 4],
+            banks: [None, None, None, None],
+            blank_ch: VramTile::default().with_char(blank_tile),
+            _phantom: PhantomData,
+        };
+
+        // clear the parts of the layers outside the window, and then the window itself
+        for y in 0..32 {
+            for x in 0..32 {
+                window.text.set_tile(x, y, window.blank_ch);
+                window.background.set_tile(x, y, window.blank_ch);
+            }
+        }
+        window.ensure_color(0);
+        window.clear();
+
+        window
+    }
+
+    fn write_palette(&mut self, id: usize) {
+        if let Some(banks) = &mut self.banks[id] {
+            for (i, bank) in banks.iter_mut().enumerate() {
+                // color 0 is left alone, as it is the backdrop color in the first bank.
+                let colors = plane_colors(i, self.colors[id]);
+                bank.set_colors(1, &colors[1..]).write_immediate();
+            }
+        }
+    }
+    /// Allocates the palette banks of a color if needed, and returns the color to draw with.
+    ///
+    /// This is the default color if there are not enough free palette banks.
+    #[track_caller]
+    fn ensure_color(&mut self, id: usize) -> usize {
+        if id >= 4 {
+            terminal_color_out_of_range();
+        }
+        if self.banks[id].is_none() {
+            self.banks[id] = palette::try_alloc_banks(PaletteKind::Background);
+            self.write_palette(id);
+        }
+        if self.banks[id].is_some() {
+            id
+        } else {
+            0
+        }
+    }
+
+    fn tile_for_ch(&self, ch: char, color: usize) -> VramTile {
+        let (plane, tile, _) = T::get_font_glyph(ch);
+        let pal = match &self.banks[color] {
+            Some(banks) => banks[plane as usize].id(),
+            None => 0,
+        };
+        VramTile::default().with_char(tile).with_palette(pal as u8)
+    }
+    fn write_cell(&self, x: usize, y: usize, ch: char, color: usize) {
+        let (x, y) = (self.x as usize + x, self.y as usize + y);
+        self.text.set_tile(x, y, self.tile_for_ch(ch, color));
+        self.background
+            .set_tile(x, y, self.tile_for_ch('\u{F501}', color));
+    }
+    #[track_caller]
+    fn check_coordinate(&self, x: usize, y: usize) {
+        if x >= self.width as usize || y >= self.height as usize {
+            window_coord_out_of_range();
+        }
+    }
+
+    /// Returns the size of this window, in characters.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width as usize, self.height as usize)
+    }
+
+    /// Sets one of the four colors of this window.
+    #[track_caller]
+    pub fn set_color(&mut self, id: usize, background: Color, foreground: Color) {
+        self.ensure_color(id);
+        self.colors[id] = (background, foreground);
+        self.write_palette(id);
+    }
+
+    /// Sets the color used for characters written to this window.
+    #[track_caller]
+    pub fn set_active_color(&mut self, color: usize) {
+        self.color = self.ensure_color(color) as u8;
+    }
+
+    /// Clears the window, and moves the cursor back to the top left corner.
+    pub fn clear(&mut self) {
+        self.cursor_x = 0;
+        self.cursor_y = 0;
+        self.color = 0;
+        for y in 0..self.height as usize {
+            self.clear_line(y);
+        }
+    }
+
+    /// Clears a line of the window.
+    #[track_caller]
+    pub fn clear_line(&mut self, y: usize) {
+        self.check_coordinate(0, y);
+        let bg_ch = self.tile_for_ch('\u{F501}', 0);
+        for x in 0..self.width as usize {
+            let (x, y) = (self.x as usize + x, self.y as usize + y);
+            self.text.set_tile(x, y, self.blank_ch);
+            self.background.set_tile(x, y, bg_ch);
+        }
+    }
+
+    /// Sets the character at a given position in the window.
+    #[track_caller]
+    pub fn set_char(&mut self, x: usize, y: usize, ch: char, color: usize) {
+        self.check_coordinate(x, y);
+        let color = self.ensure_color(color);
+        self.write_cell(x, y, ch, color);
+    }
+
+    #[track_caller]
+    pub fn set_cursor(&mut self, x: usize, y: usize) {
+        self.check_coordinate(x, y);
+        self.cursor_x = x as u8;
+        self.cursor_y = y as u8;
+    }
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.cursor_x as usize, self.cursor_y as usize)
+    }
+
+    /// Moves every line of the window up by one, clearing the bottom line.
+    fn scroll(&mut self) {
+        let mut buffer = [VramTile::default(); 30];
+        let row = &mut buffer[..self.width as usize];
+        for y in self.y as usize..(self.y + self.height - 1) as usize {
+            for map in [&self.text, &self.background] {
+                map.read_tiles(self.x as usize, y + 1, row);
+                map.set_tiles(self.x as usize, y, row);
+            }
+        }
+        self.clear_line(self.height as usize - 1);
+    }
+
+    pub fn new_line(&mut self) {
+        self.cursor_x = 0;
+        if self.cursor_y + 1 == self.height {
+            self.scroll();
+        } else {
+            self.cursor_y += 1;
+        }
+    }
+
+    /// Writes a character at the cursor without any special handling, and advances the cursor.
+    pub fn write_char(&mut self, ch: char) {
+        self.write_cell(self.cursor_x as usize, self.cursor_y as usize, ch, self.color as usize);
+        self.cursor_x += 1;
+        if self.cursor_x == self.width {
+            self.new_line();
+        }
+    }
+
+    /// Applies a control sequence written to the window. Unsupported sequences are ignored.
+    fn apply_csi(&mut self, seq: &CsiSequence) {
+        if seq.command == 'm' {
+            if seq.params().is_empty() {
+                self.color = 0;
+            }
+            for &param in seq.params() {
+                match param {
+                    0 | 39 | 49 => self.color = 0,
+                    30..=33 => self.set_active_color((param - 30) as usize),
+                    40..=43 => self.set_active_color((param - 40) as usize),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    pub fn write<'x>(&'x mut self) -> TerminalWindowWrite<'x, 'a, T>
+    where 'a: 'x {
+        TerminalWindowWrite {
+            window: self,
+            buffer: ['\0'; 30],
+            buffer_idx: 0,
+            passthrough_mode: false,
+            synthetic_newline: false,
+        }
+    }
+    pub fn write_str(&mut self, str: &str) {
+        self.write().write_str(str);
+    }
+}
+
+/// A buffered writer for a [`TerminalWindow`], which wraps lines between words.
+///
+/// Like [`ActiveTerminalWrite`], this understands the `ESC[30m` to `ESC[33m` sequences (or
+/// `ESC[40m` to `ESC[43m`) to select one of the colors, and `ESC[0m` to select color 0. Other
+/// escape sequences are ignored.
+///
+/// [`ActiveTerminalWrite`]: crate::display::ActiveTerminalWrite
+pub struct TerminalWindowWrite<'a, 'b: 'a, T: TerminalFont> {
+    window: &'a mut TerminalWindow<'b, T>,
+    buffer: [char; 30],
+    buffer_idx: usize,
+    passthrough_mode: bool,
+    synthetic_newline: bool,
+}
+impl<'a, 'b: 'a, T: TerminalFont> TerminalWindowWrite<'a, 'b, T> {
+    fn dump_buffers(&mut self) {
+        for i in 0..self.buffer_idx {
+            self.window.write_char(self.buffer[i]);
+        }
+    }
+    fn flush_buffers(&mut self) {
+        if self.passthrough_mode {
+            self.passthrough_mode = false;
+        } else if self.buffer_idx != 0 {
+            let remaining = (self.window.width - self.window.cursor_x) as usize;
+            if self.buffer_idx > remaining {
+                self.window.new_line();
+            }
+            self.dump_buffers();
+
+            // a word that ends exactly at the end of a line wraps the cursor by itself.
+            self.synthetic_newline = self.window.cursor_x == 0;
+        }
+        self.buffer_idx = 0;
+    }
+    fn push_char(&mut self, ch: char) {
+        if self.passthrough_mode {
+            self.window.write_char(ch);
+        } else if self.buffer_idx == self.window.width as usize {
+            self.passthrough_mode = true;
+            self.dump_buffers();
+            self.window.write_char(ch);
+        } else {
+            self.buffer[self.buffer_idx] = ch;
+            self.buffer_idx += 1;
+        }
+    }
+    pub fn write_char(&mut self, ch: char) {
+        match self.window.escape.push(ch) {
+            EscapeAction::Print(ch) => self.write_plain_char(ch),
+            EscapeAction::Consumed => {}
+            EscapeAction::Csi(seq) => {
+                self.flush_buffers();
+                self.window.apply_csi(&seq);
+            }
+        }
+    }
+    fn write_plain_char(&mut self, ch: char) {
+        match ch {
+            ' ' => {
+                self.flush_buffers();
+                if self.window.cursor_x != 0 || !self.synthetic_newline {
+                    self.window.write_char(' ');
+                }
+                self.synthetic_newline = false;
+            }
+            '\t' => {
+                self.flush_buffers();
+                self.window.write_char(' ');
+                while !self.window.cursor_x.is_multiple_of(4) {
+                    self.window.write_char(' ');
+                }
+                self.synthetic_newline = false;
+            }
+            '\n' => {
+                self.flush_buffers();
+                self.window.new_line();
+                self.synthetic_newline = false;
+            }
+            _ => self.push_char(ch),
+        }
+    }
+    pub fn write_str(&mut self, s: &str) {
+        for char in s.chars() {
+            self.write_char(char);
+        }
+    }
+    pub fn write_fmt(&mut self, args: Arguments) {
+        fmt::Write::write_fmt(self, args).unwrap();
+    }
+    pub fn new_line(&mut self) {
+        self.write_char('\n');
+    }
+}
+impl<'a, 'b: 'a, T: TerminalFont> fmt::Write for TerminalWindowWrite<'a, 'b, T> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_str(s);
+        Ok(())
+    }
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.write_char(c);
+        Ok(())
+    }
+}
+impl<'a, 'b: 'a, T: TerminalFont> Drop for TerminalWindowWrite<'a, 'b, T> {
+    fn drop(&mut self) {
+        self.flush_buffers();
+    }
+}
+
+#[inline(never)]
+#[track_caller]
+fn window_region_out_of_range() -> ! {
+    crate::panic_handler::static_panic(
+        "Terminal windows must be non-empty and fit within the 30x20 tiles of the screen.",
+    )
+}
+
+#[inline(never)]
+#[track_caller]
+fn window_coord_out_of_range() -> ! {
+    crate::panic_handler::static_panic("Terminal window coordinates are out of range.")
+}
@@ -0,0 +1,38 @@
+/// A value that is animated linearly over a number of frames from the VBlank interrupt.
+#[derive(Copy, Clone, Debug)]
+#[repr(align(4))]
+pub(crate) struct Transition {
+    from: u8,
+    to: u8,
+    frame: u16,
+    frames: u16,
+}
+impl Transition {
+    pub fn new(from: u8, to: u8, frames: u16) -> Self {
+        Transition { from, to, frame: 0, frames }
+    }
+
+    /// Returns the value for the current frame.
+    pub fn current(&self) -> u8 {
+        if self.frame >= self.frames {
+            self.to
+        } else {
+            let delta = (self.to as i32 - self.from as i32) * self.frame as i32;
+            (self.from as i32 + delta / self.frames as i32) as u8
+        }
+    }
+
+    /// Returns the final value of this transition.
+    pub fn target(&self) -> u8 {
+        self.to
+    }
+
+    /// Advances the transition by one frame, and returns the new value and whether the
+    /// transition is complete.
+    pub fn step(&mut self) -> (u8, bool) {
+        if self.frame < self.frames {
+            self.frame += 1;
+        }
+        (self.current(), self.frame >= self.frames)
+    }
+}
@@ -12,7 +12,7 @@ mod private {
     }
 }
 
-unsafe fn copy_volatile<T>(mut src: *const T, mut dst: *mut T, len: usize) {
+pub(crate) unsafe fn copy_volatile<T>(mut src: *const T, mut dst: *mut T, len: usize) {
     for _ in 0..len {
         core::ptr::write_volatile(dst, core::ptr::read_volatile(src));
         src = src.offset(1);
@@ -79,7 +79,7 @@ impl CharAccess {
             || id < self.lower_bound
             || id >= self.upper_bound
             || end_id < self.lower_bound
-            || end_id >= self.upper_bound
+            || end_id > self.upper_bound
         {
             invalid_glyph_id(self.lower_bound, self.upper_bound)
         }
@@ -217,6 +217,18 @@ impl MapAccess {
         }
     }
 
+    /// Reads the data in the tile map starting at a given coordinate.
+    ///
+    /// The tiles are read horizontally, and will roll over to the start of the next row if they
+    /// reach the end of a row.
+    #[track_caller]
+    pub fn read_tiles(&self, x: usize, y: usize, tile: &mut [VramTile]) {
+        self.check_bounds(x, y, tile.len());
+        unsafe {
+            copy_volatile(self.base_index(x, y), tile.as_mut_ptr(), tile.len());
+        }
+    }
+
     /// Sets the data in the tile map starting at a given coordinate to a single tile.
     ///
     /// The list of tiles is laid out horizontally, and will roll over to the start of the next
@@ -253,6 +265,98 @@ impl MapAccess {
     }
 }
 
+/// A helper type used to write data into the tile maps of affine layers.
+///
+/// Each tile in an affine tile map is a single byte containing the ID of the character to render.
+#[derive(Copy, Clone, Debug)]
+pub struct AffineMapAccess {
+    base: usize,
+    map_length: usize,
+    map_shift: usize,
+    map_area: usize,
+}
+impl AffineMapAccess {
+    pub(crate) fn new(base: usize, shift: usize) -> Self {
+        let scale = 1 << shift;
+        AffineMapAccess { base, map_length: scale, map_shift: shift, map_area: scale * scale }
+    }
+
+    #[track_caller]
+    fn index(&self, x: usize, y: usize) -> usize {
+        if x >= self.map_length || y >= self.map_length {
+            invalid_tile_map_coordinate(self.map_length);
+        }
+        x + (y << self.map_shift)
+    }
+    #[track_caller]
+    fn check_bounds(&self, x: usize, y: usize, count: usize) {
+        let start_idx = self.index(x, y);
+        let end_idx = start_idx + count;
+
+        if end_idx > self.map_area {
+            invalid_tile_map_coordinate(self.map_length);
+        }
+    }
+
+    unsafe fn write_byte(&self, idx: usize, tile: u8) {
+        // VRAM does not support 8-bit writes, so we must write the entire halfword.
+        let ptr = (self.base as *mut u16).add(idx / 2);
+        let prev = core::ptr::read_volatile(ptr);
+        let new = if idx.is_multiple_of(2) {
+            (prev & 0xFF00) | tile as u16
+        } else {
+            (prev & 0x00FF) | ((tile as u16) << 8)
+        };
+        core::ptr::write_volatile(ptr, new);
+    }
+
+    /// Sets a coordinate to a given tile.
+    #[track_caller]
+    pub fn set_tile(&self, x: usize, y: usize, tile: u8) {
+        let idx = self.index(x, y);
+        unsafe { self.write_byte(idx, tile) }
+    }
+
+    /// Sets the data in the tile map starting at a given coordinate.
+    ///
+    /// The list of tiles is laid out horizontally, and will roll over to the start of the next
+    /// row if it reaches the end of a row.
+    #[track_caller]
+    pub fn set_tiles(&self, x: usize, y: usize, tiles: &[u8]) {
+        self.check_bounds(x, y, tiles.len());
+        let mut idx = self.index(x, y);
+        let mut tiles = tiles;
+        unsafe {
+            if !idx.is_multiple_of(2) && !tiles.is_empty() {
+                self.write_byte(idx, tiles[0]);
+                tiles = &tiles[1..];
+                idx += 1;
+            }
+            let mut ptr = (self.base as *mut u16).add(idx / 2);
+            for pair in tiles.chunks_exact(2) {
+                core::ptr::write_volatile(ptr, u16::from_le_bytes([pair[0], pair[1]]));
+                ptr = ptr.add(1);
+            }
+            if !tiles.len().is_multiple_of(2) {
+                self.write_byte(idx + tiles.len() - 1, tiles[tiles.len() - 1]);
+            }
+        }
+    }
+
+    /// Sets the data in the tile map starting at a given coordinate to a single tile.
+    ///
+    /// The list of tiles is laid out horizontally, and will roll over to the start of the next
+    /// row if it reaches the end of a row.
+    #[track_caller]
+    pub fn fill_tiles(&self, x: usize, y: usize, tile: u8, count: usize) {
+        self.check_bounds(x, y, count);
+        let idx = self.index(x, y);
+        for i in idx..idx + count {
+            unsafe { self.write_byte(i, tile) }
+        }
+    }
+}
+
 #[inline(never)]
 #[track_caller]
 fn invalid_tile_map_coordinate(scale_max: usize) {
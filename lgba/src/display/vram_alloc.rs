@@ -0,0 +1,247 @@
+//! Module for allocating background VRAM between layers.
+//!
+//! Background VRAM is 64 KiB in size, and is shared between character data and tile maps. It is
+//! divided into 32 screen blocks of 2 KiB, each of which holds one 32x32 tile map or 64 4bpp
+//! characters. Character data is addressed relative to one of four character bases, which start
+//! every 8 screen blocks.
+//!
+//! Memory is handed out as [`CharRange`] and [`ScreenBlocks`] handles, which free their memory
+//! when dropped. The handles can be given to layers (for example, with
+//! [`TileLayer::set_char_range`] and [`TileLayer::set_screen_blocks`]), which then free them once
+//! they are reconfigured or dropped, so the memory cannot be reused while it is displayed.
+//!
+//! Characters are allocated from the start of VRAM, and screen blocks from the end, so that large
+//! allocations of either kind are less likely to fail.
+//!
+//! These allocations are only meaningful in the tiled display modes, as the bitmap modes use the
+//! same memory for their frame buffers.
+//!
+//! [`TileLayer::set_char_range`]: crate::display::TileLayer::set_char_range
+//! [`TileLayer::set_screen_blocks`]: crate::display::TileLayer::set_screen_blocks
+
+use crate::{
+    display::vram::{CharAccess, MapAccess},
+    mmio::reg::VRAM_BASE,
+    sync::Mutex,
+};
+
+/// The number of screen blocks in background VRAM.
+pub const SCREEN_BLOCK_COUNT: usize = 32;
+
+/// The number of 4bpp characters that fit in a single screen block.
+pub const CHARS_PER_SCREEN_BLOCK: usize = 64;
+
+/// The largest number of 4bpp characters that can be addressed from a single character base.
+pub const MAX_CHARS: usize = 1024;
+
+const BLOCKS_PER_CHAR_BASE: usize = 8;
+
+static VRAM_ALLOCATED: Mutex<u32> = Mutex::new(0);
+
+fn block_mask(start: usize, len: usize) -> u32 {
+    (((1u64 << len) - 1) << start) as u32
+}
+
+fn try_alloc_blocks(
+    len: usize,
+    candidates: impl Iterator<Item = usize>,
+    valid: impl Fn(usize) -> bool,
+) -> Option<usize> {
+    let mut allocated = VRAM_ALLOCATED.try_lock()?;
+    for start in candidates {
+        let mask = block_mask(start, len);
+        if valid(start) && *allocated & mask == 0 {
+            *allocated |= mask;
+            return Some(start);
+        }
+    }
+    None
+}
+
+fn free_blocks(start: u8, len: u8) {
+    *VRAM_ALLOCATED.lock() &= !block_mask(start as usize, len as usize);
+}
+
+/// Allocates a range of characters, or returns `None` if no large enough range is free.
+///
+/// The count is in 4bpp characters, and is rounded up to a multiple of 64. 8bpp characters take
+/// up the space of two 4bpp characters each. The count must be between 1 and 1024 inclusive.
+#[track_caller]
+pub fn try_alloc_chars(count: usize) -> Option<CharRange> {
+    let len = char_range_blocks(count);
+    let start = try_alloc_blocks(len, 0..=SCREEN_BLOCK_COUNT - len, |start| {
+        start % BLOCKS_PER_CHAR_BASE + len <= MAX_CHARS / CHARS_PER_SCREEN_BLOCK
+    })?;
+    Some(CharRange { start: start as u8, len: len as u8 })
+}
+
+/// Allocates a range of characters.
+///
+/// This function panics if no large enough range is free.
+#[track_caller]
+pub fn alloc_chars(count: usize) -> CharRange {
+    try_alloc_chars(count).unwrap_or_else(|| vram_full())
+}
+
+/// Allocates a range of characters that can be addressed from a given character base, or returns
+/// `None` if no large enough range is free.
+///
+/// This allows a layer to use characters from multiple ranges. The character base must be
+/// between 0 and 3 inclusive.
+#[track_caller]
+pub fn try_alloc_chars_at_base(char_base: usize, count: usize) -> Option<CharRange> {
+    if char_base >= 4 {
+        char_base_out_of_range();
+    }
+    let len = char_range_blocks(count);
+    let base_block = char_base * BLOCKS_PER_CHAR_BASE;
+    let last_block =
+        core::cmp::min(base_block + MAX_CHARS / CHARS_PER_SCREEN_BLOCK, SCREEN_BLOCK_COUNT);
+    if base_block + len > last_block {
+        return None;
+    }
+    let start = try_alloc_blocks(len, base_block..=last_block - len, |_| true)?;
+    Some(CharRange { start: start as u8, len: len as u8 })
+}
+
+/// Allocates a range of characters that can be addressed from a given character base.
+///
+/// This function panics if no large enough range is free.
+#[track_caller]
+pub fn alloc_chars_at_base(char_base: usize, count: usize) -> CharRange {
+    try_alloc_chars_at_base(char_base, count).unwrap_or_else(|| vram_full())
+}
+
+/// Allocates a number of contiguous screen blocks, or returns `None` if not enough contiguous
+/// blocks are free.
+///
+/// Tile layers require 1, 2 or 4 screen blocks depending on their size, and affine layers
+/// require between 1 and 8. The count must be between 1 and 32 inclusive.
+#[track_caller]
+pub fn try_alloc_screen_blocks(count: usize) -> Option<ScreenBlocks> {
+    if count == 0 || count > SCREEN_BLOCK_COUNT {
+        screen_block_count_out_of_range();
+    }
+    let start = try_alloc_blocks(count, (0..=SCREEN_BLOCK_COUNT - count).rev(), |_| true)?;
+    Some(ScreenBlocks { start: start as u8, len: count as u8 })
+}
+
+/// Allocates a number of contiguous screen blocks.
+///
+/// This function panics if not enough contiguous blocks are free.
+#[track_caller]
+pub fn alloc_screen_blocks(count: usize) -> ScreenBlocks {
+    try_alloc_screen_blocks(count).unwrap_or_else(|| vram_full())
+}
+
+/// An allocated range of background characters.
+///
+/// The range is freed when this handle is dropped.
+#[derive(Debug)]
+pub struct CharRange {
+    start: u8,
+    len: u8,
+}
+impl CharRange {
+    /// Returns the character base a layer must use to access this range.
+    pub fn char_base(&self) -> usize {
+        self.start as usize / BLOCKS_PER_CHAR_BASE
+    }
+
+    /// Returns the ID of the first character in this range, relative to its character base.
+    ///
+    /// This is the value tile maps use to refer to the first character of the range.
+    pub fn first_char(&self) -> usize {
+        (self.start as usize % BLOCKS_PER_CHAR_BASE) * CHARS_PER_SCREEN_BLOCK
+    }
+
+    /// Returns the number of 4bpp characters in this range.
+    pub fn char_count(&self) -> usize {
+        self.len as usize * CHARS_PER_SCREEN_BLOCK
+    }
+
+    /// Returns a character access that can only write into this range.
+    ///
+    /// Character IDs are relative to the character base, as with the character access of a layer.
+    pub fn char_access(&self) -> CharAccess {
+        let base = VRAM_BASE + 16 * 1024 * self.char_base();
+        CharAccess::new(base, self.first_char(), self.first_char() + self.char_count())
+    }
+}
+impl Drop for CharRange {
+    fn drop(&mut self) {
+        free_blocks(self.start, self.len);
+    }
+}
+
+/// An allocated range of contiguous screen blocks, used to store tile maps.
+///
+/// The blocks are freed when this handle is dropped.
+#[derive(Debug)]
+pub struct ScreenBlocks {
+    start: u8,
+    len: u8,
+}
+impl ScreenBlocks {
+    /// Returns the index of the first block, as used by the tile base of a layer.
+    pub fn tile_base(&self) -> usize {
+        self.start as usize
+    }
+
+    /// Returns the number of screen blocks in this range.
+    pub fn count(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Returns a tile map access for one of the 32x32 tile maps in this range.
+    #[track_caller]
+    pub fn map_access(&self, screen: usize) -> MapAccess {
+        if screen >= self.count() {
+            screen_block_out_of_range();
+        }
+        MapAccess::new(VRAM_BASE + 2048 * (self.tile_base() + screen), 5)
+    }
+}
+impl Drop for ScreenBlocks {
+    fn drop(&mut self) {
+        free_blocks(self.start, self.len);
+    }
+}
+
+#[track_caller]
+fn char_range_blocks(count: usize) -> usize {
+    if count == 0 || count > MAX_CHARS {
+        char_count_out_of_range();
+    }
+    count.div_ceil(CHARS_PER_SCREEN_BLOCK)
+}
+
+#[inline(never)]
+#[track_caller]
+fn vram_full() -> ! {
+    crate::panic_handler::static_panic("Not enough background VRAM is free!")
+}
+
+#[inline(never)]
+#[track_caller]
+fn char_count_out_of_range() -> ! {
+    crate::panic_handler::static_panic("Character ranges must contain between 1 and 1024 chars!")
+}
+
+#[inline(never)]
+#[track_caller]
+fn char_base_out_of_range() -> ! {
+    crate::panic_handler::static_panic("Character bases must be between 0 and 3!")
+}
+
+#[inline(never)]
+#[track_caller]
+fn screen_block_count_out_of_range() -> ! {
+    crate::panic_handler::static_panic("Screen block counts must be between 1 and 32!")
+}
+
+#[inline(never)]
+#[track_caller]
+fn screen_block_out_of_range() -> ! {
+    crate::panic_handler::static_panic("Screen id is out of range for these screen blocks!")
+}
@@ -0,0 +1,430 @@
+//! A renderer for variable-width text.
+//!
+//! Unlike the terminal, which places each glyph in a fixed grid, this draws glyphs into a strip of
+//! 4bpp characters using their actual widths. The glyphs are taken from a [`TerminalFont`], with
+//! empty columns on either side of each glyph removed.
+//!
+//! [`TerminalFont`]: crate::display::TerminalFont
+
+use crate::display::{vram_alloc, vram_alloc::CharRange, MapAccess, TerminalFont, VramTile};
+use core::marker::PhantomData;
+
+/// The pixels and metrics of a single glyph.
+#[derive(Copy, Clone, Debug)]
+struct Glyph {
+    /// The rows of the glyph, with the leftmost pixel in the most significant bit.
+    rows: [u8; 8],
+    width: u8,
+}
+impl Glyph {
+    fn load<T: TerminalFont>(ch: char, space_width: u8) -> Glyph {
+        let (plane, tile, _) = T::get_font_glyph(ch);
+        let data = &T::get_font_data()[tile as usize * 8..tile as usize * 8 + 8];
+
+        // extract the plane used by this glyph
+        let shift = 3 - plane as u32;
+        let mut rows = [0u8; 8];
+        for (row, word) in rows.iter_mut().zip(data) {
+            for x in 0..8 {
+                if (word >> (x * 4 + shift)) & 1 != 0 {
+                    *row |= 0x80 >> x;
+                }
+            }
+        }
+
+        // remove the empty columns on either side of the glyph
+        let combined = rows.iter().fold(0, |acc, row| acc | row);
+        if combined == 0 {
+            return Glyph { rows, width: space_width };
+        }
+        let left = combined.leading_zeros();
+        for row in &mut rows {
+            *row <<= left;
+        }
+        Glyph { rows, width: (8 - left - combined.trailing_zeros()) as u8 }
+    }
+}
+
+/// Renders variable-width text into a block of allocated characters.
+///
+/// The text is drawn into `lines` rows of `W` characters each, which can be placed on a tile
+/// layer with [`VwfRenderer::map_tiles`]. Only the line currently being drawn is kept in memory,
+/// and it is uploaded to VRAM with [`CharAccess::write_char_4bpp`] whenever it is flushed.
+///
+/// Once every line is filled, further text is not drawn until the renderer is cleared, which
+/// allows longer text to be shown one page at a time.
+///
+/// [`CharAccess::write_char_4bpp`]: crate::display::CharAccess::write_char_4bpp
+pub struct VwfRenderer<T: TerminalFont, const W: usize> {
+    chars: CharRange,
+    lines: u8,
+    line: [[u32; 8]; W],
+    dirty_start: u8,
+    dirty_end: u8,
+
+    cursor_x: u16,
+    cursor_y: u8,
+    last_ch: Option<char>,
+    is_wrapped: bool,
+    is_full: bool,
+
+    foreground: u8,
+    background: u8,
+    letter_spacing: u8,
+    space_width: u8,
+    kerning: &'static [(char, char, i8)],
+    _phantom: PhantomData<T>,
+}
+impl<T: TerminalFont, const W: usize> VwfRenderer<T, W> {
+    /// Creates a new renderer with the given number of lines, allocating the characters it draws
+    /// into.
+    #[track_caller]
+    pub fn new(lines: usize) -> Self {
+        if W == 0 || W > 32 || lines == 0 || lines > 32 {
+            vwf_size_out_of_range();
+        }
+        let mut renderer = VwfRenderer {
+            chars: vram_alloc::alloc_chars(W * lines),
+            lines: lines as u8,
+            line: [[0; 8]; W],
+            dirty_start: 0,
+            dirty_end: 0,
+            cursor_x: 0,
+            cursor_y: 0,
+            last_ch: None,
+            is_wrapped: false,
+            is_full: false,
+            foreground: 1,
+            background: 0,
+            letter_spacing: 1,
+            space_width: 3,
+            kerning: &[],
+            _phantom: PhantomData,
+        };
+        renderer.clear();
+        renderer
+    }
+
+    /// Returns the characters this renderer draws into.
+    ///
+    /// Layers displaying the text must use the character base of this range.
+    pub fn chars(&self) -> &CharRange {
+        &self.chars
+    }
+
+    /// Returns the width of each line, in pixels.
+    pub fn line_width(&self) -> usize {
+        W * 8
+    }
+
+    /// Sets the palette indexes used for the text and the background behind it.
+    ///
+    /// Index 0 is transparent, and is the default background. This only affects text drawn after
+    /// the colors are changed, and the background of lines that are started afterwards.
+    #[track_caller]
+    pub fn set_colors(&mut self, foreground: u8, background: u8) -> &mut Self {
+        if foreground >= 16 || background >= 16 {
+            vwf_color_out_of_range();
+        }
+        self.foreground = foreground;
+        self.background = background;
+        self
+    }
+
+    /// Sets the number of pixels between each glyph. The default is 1.
+    pub fn set_letter_spacing(&mut self, spacing: u8) -> &mut Self {
+        self.letter_spacing = spacing;
+        self
+    }
+
+    /// Sets the width of spaces and other empty glyphs, in pixels. The default is 3.
+    pub fn set_space_width(&mut self, width: u8) -> &mut Self {
+        self.space_width = width;
+        self
+    }
+
+    /// Sets a table of pairs of characters whose spacing is adjusted by a number of pixels.
+    ///
+    /// For example, `('A', 'V', -1)` draws a `V` that follows an `A` one pixel further left.
+    pub fn set_kerning(&mut self, kerning: &'static [(char, char, i8)]) -> &mut Self {
+        self.kerning = kerning;
+        self
+    }
+
+    /// Writes the tile map entries that display the text into a tile map, with the top left
+    /// corner of the text at the given coordinates.
+    #[track_caller]
+    pub fn map_tiles(&self, map: MapAccess, x: usize, y: usize, palette: u8) {
+        let first_char = self.chars.first_char();
+        for line in 0..self.lines as usize {
+            for i in 0..W {
+                let tile = VramTile::default()
+                    .with_char((first_char + line * W + i) as u16)
+                    .with_palette(palette);
+                map.set_tile(x + i, y + line, tile);
+            }
+        }
+    }
+
+    fn background_word(&self) -> u32 {
+        self.background as u32 * 0x11111111
+    }
+    fn char_id(&self, line: usize, tile: usize) -> usize {
+        self.chars.first_char() + line * W + tile
+    }
+
+    /// Erases all text, and moves the cursor back to the start of the first line.
+    pub fn clear(&mut self) {
+        self.line = [[self.background_word(); 8]; W];
+        for line in 0..self.lines as usize {
+            let id = self.char_id(line, 0);
+            self.chars
+                .char_access()
+                .write_char_4bpp(id, self.line.as_flattened());
+        }
+        self.cursor_x = 0;
+        self.cursor_y = 0;
+        self.dirty_start = W as u8;
+        self.dirty_end = 0;
+        self.last_ch = None;
+        self.is_wrapped = false;
+        self.is_full = false;
+    }
+
+    /// Returns whether every line has been filled, so that no more text can be drawn until the
+    /// renderer is cleared.
+    pub fn is_full(&self) -> bool {
+        self.is_full
+    }
+
+    /// Returns the position of the cursor, as a pixel offset and a line.
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.cursor_x as usize, self.cursor_y as usize)
+    }
+
+    /// Uploads the parts of the current line that have changed to VRAM.
+    pub fn flush(&mut self) {
+        if self.dirty_start < self.dirty_end {
+            let (start, end) = (self.dirty_start as usize, self.dirty_end as usize);
+            let id = self.char_id(self.cursor_y as usize, start);
+            self.chars
+                .char_access()
+                .write_char_4bpp(id, self.line[start..end].as_flattened());
+        }
+        self.dirty_start = W as u8;
+        self.dirty_end = 0;
+    }
+
+    /// Moves the cursor to the start of the next line, or marks the renderer as full if it is on
+    /// the last line.
+    pub fn new_line(&mut self) {
+        if self.is_full {
+            return;
+        }
+        self.flush();
+        if self.cursor_y as usize + 1 == self.lines as usize {
+            self.is_full = true;
+        } else {
+            self.cursor_y += 1;
+            self.cursor_x = 0;
+            self.line = [[self.background_word(); 8]; W];
+        }
+        self.last_ch = None;
+        self.is_wrapped = false;
+    }
+
+    fn kerning_for(&self, ch: char) -> i32 {
+        let Some(last) = self.last_ch else { return 0 };
+        for &(left, right, amount) in self.kerning {
+            if left == last && right == ch {
+                return amount as i32;
+            }
+        }
+        0
+    }
+
+    /// Returns the width of a string of text in pixels, if it were drawn on a single line.
+    pub fn text_width(&self, text: &str) -> usize {
+        let mut width = 0i32;
+        let mut last = None;
+        for ch in text.chars() {
+            if let Some(last) = last {
+                width += self.letter_spacing as i32;
+                for &(left, right, amount) in self.kerning {
+                    if left == last && right == ch {
+                        width += amount as i32;
+                        break;
+                    }
+                }
+            }
+            width += Glyph::load::<T>(ch, self.space_width).width as i32;
+            last = Some(ch);
+        }
+        width.max(0) as usize
+    }
+
+    /// Draws a glyph at the cursor, wrapping to the next line if it does not fit.
+    ///
+    /// Returns `false` if the renderer became full and the glyph was not drawn.
+    fn draw_glyph(&mut self, ch: char) -> bool {
+        let glyph = Glyph::load::<T>(ch, self.space_width);
+        let mut x = (self.cursor_x as i32 + self.kerning_for(ch)).max(0) as usize;
+        if x + glyph.width as usize > W * 8 {
+            self.new_line();
+            self.is_wrapped = true;
+            if self.is_full {
+                return false;
+            }
+            x = 0;
+        }
+
+        // copy the glyph's pixels into the line buffer
+        let color = self.foreground as u32;
+        for (y, row) in glyph.rows.iter().enumerate() {
+            for column in 0..glyph.width as usize {
+                if row & (0x80 >> column) != 0 {
+                    let px = x + column;
+                    let shift = (px % 8) * 4;
+                    let word = &mut self.line[px / 8][y];
+                    *word = (*word & !(0xF << shift)) | (color << shift);
+                }
+            }
+        }
+
+        // mark the changed characters
+        let end = core::cmp::min(x + glyph.width as usize, W * 8);
+        self.dirty_start = core::cmp::min(self.dirty_start, (x / 8) as u8);
+        self.dirty_end = core::cmp::max(self.dirty_end, end.div_ceil(8) as u8);
+
+        self.cursor_x = (end + self.letter_spacing as usize) as u16;
+        self.last_ch = Some(ch);
+        true
+    }
+
+    /// Writes the first character of `text`, wrapping the line before it if it starts a word that
+    /// does not fit on the current line.
+    ///
+    /// Returns `false` if the renderer is full and the character was not written.
+    fn put_char(&mut self, text: &str) -> bool {
+        let Some(ch) = text.chars().next() else {
+            return true;
+        };
+        if self.is_full {
+            return false;
+        }
+
+        match ch {
+            '\n' => self.new_line(),
+            // spaces are dropped at the start of a line created by word wrapping.
+            ' ' if self.is_wrapped && self.cursor_x == 0 => {}
+            // a space that does not fit on the last line is dropped in the same way.
+            ' ' => {
+                self.draw_glyph(' ');
+            }
+            _ => {
+                let starts_word = matches!(self.last_ch, None | Some(' '));
+                if starts_word && self.cursor_x != 0 {
+                    let word_len = text.find([' ', '\n']).unwrap_or(text.len());
+                    let word_width = self.text_width(&text[..word_len]);
+                    let x = self.cursor_x as i32 + self.kerning_for(ch);
+                    if x as usize + word_width > W * 8 && word_width <= W * 8 {
+                        self.new_line();
+                        self.is_wrapped = true;
+                        if self.is_full {
+                            return false;
+                        }
+                    }
+                }
+                if !self.draw_glyph(ch) {
+                    return false;
+                }
+                self.is_wrapped = false;
+            }
+        }
+        true
+    }
+
+    /// Writes a string, wrapping lines between words.
+    ///
+    /// Returns the number of bytes that were written, which is less than the length of the string
+    /// if the renderer became full.
+    pub fn write_str(&mut self, text: &str) -> usize {
+        let mut pos = 0;
+        while pos < text.len() && self.put_char(&text[pos..]) {
+            pos += text[pos..].chars().next().unwrap().len_utf8();
+        }
+        self.flush();
+        pos
+    }
+}
+
+/// Reveals text in a [`VwfRenderer`] a few characters at a time.
+///
+/// If the renderer becomes full, the typewriter waits until it is cleared before continuing.
+#[derive(Copy, Clone, Debug)]
+pub struct Typewriter<'a> {
+    text: &'a str,
+    pos: usize,
+    frames_per_char: u8,
+    timer: u8,
+}
+impl<'a> Typewriter<'a> {
+    /// Creates a typewriter that reveals a character of the text every `frames_per_char` calls
+    /// to [`Typewriter::update`].
+    pub fn new(text: &'a str, frames_per_char: u8) -> Self {
+        Typewriter { text, pos: 0, frames_per_char: frames_per_char.max(1), timer: 0 }
+    }
+
+    /// Advances the typewriter by one frame, revealing a character if it is time to.
+    pub fn update<T: TerminalFont, const W: usize>(&mut self, renderer: &mut VwfRenderer<T, W>) {
+        self.timer += 1;
+        if self.timer >= self.frames_per_char {
+            self.timer = 0;
+            self.step(renderer, 1);
+        }
+    }
+
+    /// Immediately reveals up to `count` characters.
+    pub fn step<T: TerminalFont, const W: usize>(
+        &mut self,
+        renderer: &mut VwfRenderer<T, W>,
+        count: usize,
+    ) {
+        for _ in 0..count {
+            if self.is_done() || !renderer.put_char(&self.text[self.pos..]) {
+                break;
+            }
+            self.pos += self.text[self.pos..].chars().next().unwrap().len_utf8();
+        }
+        renderer.flush();
+    }
+
+    /// Reveals as much of the remaining text as fits in the renderer.
+    pub fn finish<T: TerminalFont, const W: usize>(&mut self, renderer: &mut VwfRenderer<T, W>) {
+        self.step(renderer, usize::MAX);
+    }
+
+    /// Returns whether all of the text has been revealed.
+    pub fn is_done(&self) -> bool {
+        self.pos == self.text.len()
+    }
+
+    /// Returns the text that has not been revealed yet.
+    pub fn remaining(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+}
+
+#[inline(never)]
+#[track_caller]
+fn vwf_size_out_of_range() -> ! {
+    crate::panic_handler::static_panic(
+        "Variable-width text must be between 1 and 32 characters wide and tall.",
+    )
+}
+
+#[inline(never)]
+#[track_caller]
+fn vwf_color_out_of_range() -> ! {
+    crate::panic_handler::static_panic("Palette indexes must be between 0 and 15 inclusive.")
+}
@@ -0,0 +1,361 @@
+//! Module for controlling the display windows.
+//!
+//! Windows restrict which layers, objects and color effects are displayed in a region of the
+//! screen. There are two rectangular windows, and a third window whose shape is given by objects
+//! drawn in [`ObjectMode::ObjWindow`] mode. Everything not covered by an enabled window uses the
+//! targets of [`WindowId::Outside`].
+//!
+//! When windows overlap, window 0 takes priority over window 1, which takes priority over the
+//! object window.
+//!
+//! [`ObjectMode::ObjWindow`]: crate::display::objects::ObjectMode::ObjWindow
+
+use crate::{
+    display::{edit_disp_cnt, shadow},
+    mmio::display::{DispCnt, WinBound, WinCnt},
+};
+use enumset::EnumSet;
+
+#[doc(inline)]
+pub use crate::mmio::display::WinTarget as WindowTarget;
+
+/// Identifies one of the display windows.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub enum WindowId {
+    /// The first rectangular window.
+    Win0,
+    /// The second rectangular window.
+    Win1,
+    /// The window formed by objects drawn in window mode.
+    ObjWindow,
+    /// The region of the screen not covered by any enabled window.
+    Outside,
+}
+
+/// The horizontal or vertical span of a rectangular window.
+///
+/// This is the format of the raw window bound registers, and is mainly useful as the value type
+/// of a [`ScanlineEffect`] that changes the shape of a window on every scanline.
+///
+/// [`ScanlineEffect`]: crate::display::scanline::ScanlineEffect
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+#[repr(transparent)]
+pub struct WindowSpan(WinBound);
+impl WindowSpan {
+    /// Creates a new span. The end of the span is exclusive.
+    pub fn new(start: u8, end: u8) -> Self {
+        WindowSpan(
+            WinBound::default()
+                .with_start(start as u32)
+                .with_end(end as u32),
+        )
+    }
+
+    /// Returns the start of this span.
+    pub fn start(&self) -> u8 {
+        self.0.start() as u8
+    }
+
+    /// Returns the end of this span.
+    pub fn end(&self) -> u8 {
+        self.0.end() as u8
+    }
+}
+
+/// The window configuration of a display mode that is not currently active.
+///
+/// By default, all windows are disabled and all targets are displayed in every window.
+#[derive(Debug)]
+pub struct Windows {
+    enabled: [bool; 3],
+    h_bounds: [WindowSpan; 2],
+    v_bounds: [WindowSpan; 2],
+    targets: [EnumSet<WindowTarget>; 4],
+}
+impl Windows {
+    pub(crate) fn new() -> Self {
+        Windows {
+            enabled: [false; 3],
+            h_bounds: [WindowSpan::default(); 2],
+            v_bounds: [WindowSpan::default(); 2],
+            targets: [EnumSet::all(); 4],
+        }
+    }
+
+    fn write_bounds(&self) {
+        for i in 0..2 {
+            shadow::write_win_bounds(i, self.h_bounds[i].0, self.v_bounds[i].0);
+        }
+    }
+    fn write_win_in(&self) {
+        let [win0, win1, _, _] = self.targets;
+        shadow::write_win_in(WinCnt::default().with_cnt_a(win0).with_cnt_b(win1));
+    }
+    fn write_win_out(&self) {
+        let [_, _, obj, outside] = self.targets;
+        shadow::write_win_out(WinCnt::default().with_cnt_a(outside).with_cnt_b(obj));
+    }
+    fn write_enabled(&self) {
+        edit_disp_cnt(|cnt| self.apply(cnt));
+    }
+
+    /// Returns the display control with the enabled windows set.
+    fn apply(&self, cnt: DispCnt) -> DispCnt {
+        cnt.with_use_window_0(self.enabled[0])
+            .with_use_window_1(self.enabled[1])
+            .with_use_obj_window(self.enabled[2])
+    }
+
+    pub(crate) fn activate(&mut self) -> ActiveWindows<'_> {
+        self.write_bounds();
+        self.write_win_in();
+        self.write_win_out();
+        ActiveWindows { windows: self }
+    }
+
+    /// Returns whether a given window is enabled.
+    ///
+    /// [`WindowId::Outside`] is always considered enabled.
+    pub fn enabled(&self, id: WindowId) -> bool {
+        match id {
+            WindowId::Outside => true,
+            _ => self.enabled[id as usize],
+        }
+    }
+
+    /// Sets whether a given window is enabled.
+    ///
+    /// This function panics if called with [`WindowId::Outside`].
+    #[track_caller]
+    pub fn set_enabled(&mut self, id: WindowId, enabled: bool) -> &mut Self {
+        if id == WindowId::Outside {
+            outside_window_not_configurable();
+        }
+        self.enabled[id as usize] = enabled;
+        self
+    }
+
+    /// Returns the bounds of a rectangular window as `(left, top, right, bottom)`.
+    ///
+    /// This function panics if called with anything but [`WindowId::Win0`] or
+    /// [`WindowId::Win1`].
+    #[track_caller]
+    pub fn bounds(&self, id: WindowId) -> (u8, u8, u8, u8) {
+        let id = rect_window_index(id);
+        let (h, v) = (self.h_bounds[id], self.v_bounds[id]);
+        (h.start(), v.start(), h.end(), v.end())
+    }
+
+    /// Sets the bounds of a rectangular window.
+    ///
+    /// The right and bottom edges are exclusive. If the left edge is greater than the right edge,
+    /// or the top edge greater than the bottom edge, the window wraps around the screen.
+    ///
+    /// This function panics if called with anything but [`WindowId::Win0`] or
+    /// [`WindowId::Win1`].
+    #[track_caller]
+    pub fn set_bounds(
+        &mut self,
+        id: WindowId,
+        left: u8,
+        top: u8,
+        right: u8,
+        bottom: u8,
+    ) -> &mut Self {
+        let id = rect_window_index(id);
+        self.h_bounds[id] = WindowSpan::new(left, right);
+        self.v_bounds[id] = WindowSpan::new(top, bottom);
+        self
+    }
+
+    /// Returns what is displayed inside of a given window.
+    pub fn targets(&self, id: WindowId) -> EnumSet<WindowTarget> {
+        self.targets[id as usize]
+    }
+
+    /// Sets what is displayed inside of a given window.
+    pub fn set_targets(
+        &mut self,
+        id: WindowId,
+        targets: impl Into<EnumSet<WindowTarget>>,
+    ) -> &mut Self {
+        self.targets[id as usize] = targets.into();
+        self
+    }
+}
+
+/// The window configuration of an active display mode.
+#[derive(Debug)]
+pub struct ActiveWindows<'a> {
+    windows: &'a mut Windows,
+}
+impl<'a> ActiveWindows<'a> {
+    pub(crate) fn apply(&self, cnt: DispCnt) -> DispCnt {
+        self.windows.apply(cnt)
+    }
+
+    /// Returns whether a given window is enabled.
+    ///
+    /// [`WindowId::Outside`] is always considered enabled.
+    pub fn enabled(&self, id: WindowId) -> bool {
+        self.windows.enabled(id)
+    }
+
+    /// Sets whether a given window is enabled.
+    ///
+    /// This function panics if called with [`WindowId::Outside`].
+    #[track_caller]
+    pub fn set_enabled<'b>(
+        &'b mut self,
+        id: WindowId,
+        enabled: bool,
+    ) -> ActiveWindowsEditGuard<'b, 'a> {
+        self.windows.set_enabled(id, enabled);
+        ActiveWindowsEditGuard::new(self).mark_enabled_dirty()
+    }
+
+    /// Returns the bounds of a rectangular window as `(left, top, right, bottom)`.
+    ///
+    /// This function panics if called with anything but [`WindowId::Win0`] or
+    /// [`WindowId::Win1`].
+    #[track_caller]
+    pub fn bounds(&self, id: WindowId) -> (u8, u8, u8, u8) {
+        self.windows.bounds(id)
+    }
+
+    /// Sets the bounds of a rectangular window.
+    ///
+    /// The right and bottom edges are exclusive. If the left edge is greater than the right edge,
+    /// or the top edge greater than the bottom edge, the window wraps around the screen.
+    ///
+    /// This function panics if called with anything but [`WindowId::Win0`] or
+    /// [`WindowId::Win1`].
+    #[track_caller]
+    pub fn set_bounds<'b>(
+        &'b mut self,
+        id: WindowId,
+        left: u8,
+        top: u8,
+        right: u8,
+        bottom: u8,
+    ) -> ActiveWindowsEditGuard<'b, 'a> {
+        self.windows.set_bounds(id, left, top, right, bottom);
+        ActiveWindowsEditGuard::new(self).mark_bounds_dirty()
+    }
+
+    /// Returns what is displayed inside of a given window.
+    pub fn targets(&self, id: WindowId) -> EnumSet<WindowTarget> {
+        self.windows.targets(id)
+    }
+
+    /// Sets what is displayed inside of a given window.
+    pub fn set_targets<'b>(
+        &'b mut self,
+        id: WindowId,
+        targets: impl Into<EnumSet<WindowTarget>>,
+    ) -> ActiveWindowsEditGuard<'b, 'a> {
+        self.windows.set_targets(id, targets);
+        ActiveWindowsEditGuard::new(self).mark_targets_dirty(id)
+    }
+}
+
+/// A temporary guard created to allow chaining operations on [`ActiveWindows`], and only writing
+/// once to the memory mapped IO.
+#[derive(Debug)]
+pub struct ActiveWindowsEditGuard<'a, 'b: 'a> {
+    windows: &'a mut ActiveWindows<'b>,
+    is_bounds_dirty: bool,
+    is_win_in_dirty: bool,
+    is_win_out_dirty: bool,
+    is_enabled_dirty: bool,
+}
+impl<'a, 'b: 'a> ActiveWindowsEditGuard<'a, 'b> {
+    fn new(windows: &'a mut ActiveWindows<'b>) -> Self {
+        ActiveWindowsEditGuard {
+            windows,
+            is_bounds_dirty: false,
+            is_win_in_dirty: false,
+            is_win_out_dirty: false,
+            is_enabled_dirty: false,
+        }
+    }
+
+    fn mark_bounds_dirty(mut self) -> Self {
+        self.is_bounds_dirty = true;
+        self
+    }
+    fn mark_targets_dirty(mut self, id: WindowId) -> Self {
+        match id {
+            WindowId::Win0 | WindowId::Win1 => self.is_win_in_dirty = true,
+            WindowId::ObjWindow | WindowId::Outside => self.is_win_out_dirty = true,
+        }
+        self
+    }
+    fn mark_enabled_dirty(mut self) -> Self {
+        self.is_enabled_dirty = true;
+        self
+    }
+
+    /// Sets whether a given window is enabled.
+    ///
+    /// This function panics if called with [`WindowId::Outside`].
+    #[track_caller]
+    pub fn set_enabled(self, id: WindowId, enabled: bool) -> Self {
+        self.windows.windows.set_enabled(id, enabled);
+        self.mark_enabled_dirty()
+    }
+
+    /// Sets the bounds of a rectangular window.
+    ///
+    /// This function panics if called with anything but [`WindowId::Win0`] or
+    /// [`WindowId::Win1`].
+    #[track_caller]
+    pub fn set_bounds(self, id: WindowId, left: u8, top: u8, right: u8, bottom: u8) -> Self {
+        self.windows
+            .windows
+            .set_bounds(id, left, top, right, bottom);
+        self.mark_bounds_dirty()
+    }
+
+    /// Sets what is displayed inside of a given window.
+    pub fn set_targets(self, id: WindowId, targets: impl Into<EnumSet<WindowTarget>>) -> Self {
+        self.windows.windows.set_targets(id, targets);
+        self.mark_targets_dirty(id)
+    }
+}
+impl<'a, 'b: 'a> Drop for ActiveWindowsEditGuard<'a, 'b> {
+    fn drop(&mut self) {
+        if self.is_bounds_dirty {
+            self.windows.windows.write_bounds();
+        }
+        if self.is_win_in_dirty {
+            self.windows.windows.write_win_in();
+        }
+        if self.is_win_out_dirty {
+            self.windows.windows.write_win_out();
+        }
+        if self.is_enabled_dirty {
+            self.windows.windows.write_enabled();
+        }
+    }
+}
+
+#[track_caller]
+pub(crate) fn rect_window_index(id: WindowId) -> usize {
+    match id {
+        WindowId::Win0 | WindowId::Win1 => id as usize,
+        _ => not_rect_window(),
+    }
+}
+
+#[inline(never)]
+#[track_caller]
+fn outside_window_not_configurable() -> ! {
+    crate::panic_handler::static_panic("The outside window cannot be enabled or disabled!")
+}
+
+#[inline(never)]
+#[track_caller]
+fn not_rect_window() -> ! {
+    crate::panic_handler::static_panic("Only window 0 and window 1 have bounds!")
+}
@@ -35,14 +35,17 @@ impl DmaChannelId {
     /// Creates a new DMA channel for this ID.
     #[track_caller]
     pub fn create(self) -> DmaChannel {
-        DmaChannel {
+        self.try_create().unwrap_or_else(|| dma_channel_in_use())
+    }
+
+    /// Creates a new DMA channel for this ID, or returns `None` if it is already in use.
+    pub fn try_create(self) -> Option<DmaChannel> {
+        Some(DmaChannel {
             channel: self,
             irq_notify: false,
             force_u16: false,
-            _lock: DMA_LOCK[self as usize]
-                .try_lock()
-                .unwrap_or_else(|| dma_channel_in_use()),
-        }
+            _lock: DMA_LOCK[self as usize].try_lock()?,
+        })
     }
 }
 
@@ -109,6 +112,11 @@ pub struct DmaChannel {
     _lock: RawMutexGuard<'static>,
 }
 impl DmaChannel {
+    /// Returns the ID of this DMA channel.
+    pub fn id(&self) -> DmaChannelId {
+        self.channel
+    }
+
     /// Triggers an IRQ whenever this DMA transfer completes successfully.
     pub fn with_irq_notify(mut self) -> Self {
         self.irq_notify = true;
@@ -0,0 +1,289 @@
+//! Module for tracking the state of the keypad between frames.
+//!
+//! An [`InputState`] reads the keypad once per frame, and reports which buttons were pressed or
+//! released since the last frame, how long each button has been held, and which buttons should
+//! auto-repeat, such as a direction held down to scroll through a menu.
+//!
+//! The state can either be updated manually with [`InputState::update`], usually just after
+//! [`wait_for_vblank`], or by the VBlank interrupt after calling [`enable_vblank_update`]. In the
+//! second case, [`take_vblank_state`] returns every button pressed or released since it was last
+//! called, so presses are not lost when a frame takes longer than a VBlank to process.
+//!
+//! # Recording and replay
+//!
+//! The keypad can be recorded once per VBlank with [`start_recording`], and the resulting
+//! [`InputRecording`] can be replayed later with [`start_replay`]. While a recording or replay is
+//! running, [`pressed_keys`] returns the buttons sampled at the last VBlank, so every part of the
+//! game sees the same input in both cases. Together with the seed stored in the recording, this
+//! can be used to reproduce bug reports, or to show attract mode demos.
+//!
+//! # Example
+//!
+//! ```rust
+//! use lgba::{input::InputState, sys::Button};
+//!
+//! let mut input = InputState::new();
+//! let mut cursor = 0;
+//! loop {
+//!     lgba::sys::wait_for_vblank();
+//!     input.update();
+//!     if input.is_repeated(Button::Down) {
+//!         cursor += 1;
+//!     }
+//!     if input.is_just_pressed(Button::A) {
+//!         break;
+//!     }
+//! }
+//! ```
+//!
+//! [`pressed_keys`]: crate::sys::pressed_keys
+//! [`wait_for_vblank`]: crate::sys::wait_for_vblank
+
+use crate::{irq, mmio::reg::KEYINPUT, sync::Mutex, sys::Button};
+use enumset::EnumSet;
+
+mod recording;
+
+pub(crate) use recording::tape_keys;
+pub use recording::{
+    is_recording, is_recording_full, is_replaying, start_recording, start_replay, stop_recording,
+    stop_replay, InputRecording,
+};
+
+const BUTTON_COUNT: usize = 10;
+
+/// The state of the keypad, updated once per frame.
+#[derive(Copy, Clone, Debug)]
+pub struct InputState {
+    keys: EnumSet<Button>,
+    pressed: EnumSet<Button>,
+    released: EnumSet<Button>,
+    repeated: EnumSet<Button>,
+    held_frames: [u32; BUTTON_COUNT],
+    repeat_delay: u16,
+    repeat_interval: u16,
+}
+impl Default for InputState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl InputState {
+    /// Creates a new input state from the buttons currently held.
+    ///
+    /// Buttons that are already held are not reported as just pressed, and are counted as if they
+    /// were pressed on the frame the state was created.
+    pub fn new() -> Self {
+        Self::with_keys(crate::sys::pressed_keys())
+    }
+
+    /// Creates a new input state with a given set of buttons held.
+    pub fn with_keys(keys: EnumSet<Button>) -> Self {
+        let mut held_frames = [0; BUTTON_COUNT];
+        for button in keys {
+            held_frames[button as usize] = 1;
+        }
+        InputState {
+            keys,
+            pressed: EnumSet::empty(),
+            released: EnumSet::empty(),
+            repeated: EnumSet::empty(),
+            held_frames,
+            repeat_delay: 20,
+            repeat_interval: 4,
+        }
+    }
+
+    /// Reads the keypad, and advances the state by one frame.
+    ///
+    /// This should be called exactly once per frame.
+    pub fn update(&mut self) {
+        self.update_with(crate::sys::pressed_keys());
+    }
+
+    /// Advances the state by one frame, using a given set of held buttons instead of the keypad.
+    ///
+    /// This is useful for input that does not come from the keypad, such as a recording.
+    pub fn update_with(&mut self, keys: EnumSet<Button>) {
+        self.pressed = keys - self.keys;
+        self.released = self.keys - keys;
+        self.keys = keys;
+        self.repeated = EnumSet::empty();
+
+        let delay = self.repeat_delay as u32;
+        let interval = self.repeat_interval as u32;
+        for button in EnumSet::<Button>::all() {
+            let held = &mut self.held_frames[button as usize];
+            if keys.contains(button) {
+                *held = held.saturating_add(1);
+
+                // the frame a button is pressed on counts as the first repeat.
+                let since_press = *held - 1;
+                if since_press == 0
+                    || (since_press >= delay && (since_press - delay).is_multiple_of(interval))
+                {
+                    self.repeated |= button;
+                }
+            } else {
+                *held = 0;
+            }
+        }
+    }
+
+    /// Returns the buttons currently held.
+    pub fn keys(&self) -> EnumSet<Button> {
+        self.keys
+    }
+
+    /// Returns the buttons pressed since the last frame.
+    pub fn just_pressed(&self) -> EnumSet<Button> {
+        self.pressed
+    }
+
+    /// Returns the buttons released since the last frame.
+    pub fn just_released(&self) -> EnumSet<Button> {
+        self.released
+    }
+
+    /// Returns the buttons that were just pressed, or have been held long enough to repeat.
+    pub fn repeated(&self) -> EnumSet<Button> {
+        self.repeated
+    }
+
+    /// Returns whether a button is currently held.
+    pub fn is_held(&self, button: Button) -> bool {
+        self.keys.contains(button)
+    }
+
+    /// Returns whether a button was pressed since the last frame.
+    pub fn is_just_pressed(&self, button: Button) -> bool {
+        self.pressed.contains(button)
+    }
+
+    /// Returns whether a button was released since the last frame.
+    pub fn is_just_released(&self, button: Button) -> bool {
+        self.released.contains(button)
+    }
+
+    /// Returns whether a button was just pressed, or has been held long enough to repeat.
+    pub fn is_repeated(&self, button: Button) -> bool {
+        self.repeated.contains(button)
+    }
+
+    /// Returns the number of frames a button has been held for, or 0 if it is not held.
+    ///
+    /// This is 1 on the frame the button is pressed.
+    pub fn held_frames(&self, button: Button) -> u32 {
+        self.held_frames[button as usize]
+    }
+
+    /// Returns the number of frames a button must be held before it starts repeating.
+    pub fn repeat_delay(&self) -> u32 {
+        self.repeat_delay as u32
+    }
+
+    /// Returns the number of frames between repeats of a held button.
+    pub fn repeat_interval(&self) -> u32 {
+        self.repeat_interval as u32
+    }
+
+    /// Sets how long a button must be held before it repeats, and how often it repeats after.
+    ///
+    /// The default is a delay of 20 frames, and an interval of 4 frames. This function panics if
+    /// either value is larger than 65535, or if the interval is 0.
+    #[track_caller]
+    pub fn set_repeat(&mut self, delay: u32, interval: u32) -> &mut Self {
+        if interval == 0 || delay > u16::MAX as u32 || interval > u16::MAX as u32 {
+            input_repeat_out_of_range();
+        }
+        self.repeat_delay = delay as u16;
+        self.repeat_interval = interval as u16;
+        self
+    }
+}
+
+struct VBlankInput {
+    state: InputState,
+    pressed: EnumSet<Button>,
+    released: EnumSet<Button>,
+    repeated: EnumSet<Button>,
+}
+
+static VBLANK_INPUT: Mutex<Option<VBlankInput>> = Mutex::new(None);
+
+/// Reads the keypad directly, ignoring any recording that is being replayed.
+pub fn keypad_keys() -> EnumSet<Button> {
+    !KEYINPUT.read()
+}
+
+/// Samples the keypad for recordings, and updates the input state used by [`take_vblank_state`].
+pub(crate) fn on_vblank() {
+    recording::on_vblank();
+
+    // the lock is only held by the main thread while interrupts are disabled.
+    if let Some(mut input) = VBLANK_INPUT.try_lock() {
+        if let Some(input) = &mut *input {
+            input.state.update();
+            input.pressed |= input.state.pressed;
+            input.released |= input.state.released;
+            input.repeated |= input.state.repeated;
+        }
+    }
+}
+
+/// Starts updating an input state during each VBlank.
+///
+/// The state is usually created with [`InputState::new`], after configuring it as needed. The
+/// VBlank interrupt must be enabled for the state to be updated.
+pub fn enable_vblank_update(state: InputState) {
+    let input = VBlankInput {
+        state,
+        pressed: EnumSet::empty(),
+        released: EnumSet::empty(),
+        repeated: EnumSet::empty(),
+    };
+    irq::suppress(|| *VBLANK_INPUT.lock() = Some(input));
+}
+
+/// Stops updating the input state during each VBlank.
+pub fn disable_vblank_update() {
+    irq::suppress(|| *VBLANK_INPUT.lock() = None);
+}
+
+/// Returns whether an input state is being updated during each VBlank.
+pub fn is_vblank_update_enabled() -> bool {
+    irq::suppress(|| VBLANK_INPUT.lock().is_some())
+}
+
+/// Returns the input state updated during VBlank.
+///
+/// The buttons reported as just pressed, just released, or repeated include every VBlank since
+/// the last call to this function, rather than only the last one. This function panics if
+/// [`enable_vblank_update`] has not been called.
+#[track_caller]
+pub fn take_vblank_state() -> InputState {
+    let state = irq::suppress(|| {
+        let mut input = VBLANK_INPUT.lock();
+        let input = input.as_mut()?;
+        let mut state = input.state;
+        state.pressed = core::mem::take(&mut input.pressed);
+        state.released = core::mem::take(&mut input.released);
+        state.repeated = core::mem::take(&mut input.repeated);
+        Some(state)
+    });
+    state.unwrap_or_else(|| vblank_update_not_enabled())
+}
+
+#[inline(never)]
+#[track_caller]
+fn input_repeat_out_of_range() -> ! {
+    crate::panic_handler::static_panic(
+        "Repeat delays must be at most 65535, and intervals between 1 and 65535!",
+    )
+}
+
+#[inline(never)]
+#[track_caller]
+fn vblank_update_not_enabled() -> ! {
+    crate::panic_handler::static_panic("VBlank input updates are not enabled!")
+}
@@ -0,0 +1,323 @@
+//! Recording and replaying keypad input.
+
+use crate::{
+    input::keypad_keys,
+    irq,
+    save::{Error, SaveAccess},
+    sync::{Mutex, Static},
+    sys::Button,
+};
+use enumset::EnumSet;
+use lgba_common::input::{
+    decode_entry, encode_entry, validate_recording, RecordingHeader, ENTRY_LEN, HEADER_LEN,
+};
+
+/// A recording of the keypad, one sample per VBlank.
+///
+/// The format is documented in `lgba_common::input`, and recordings can be examined on the
+/// host with `lgba_romtool dump-input`.
+#[derive(Copy, Clone, Debug)]
+pub struct InputRecording {
+    data: &'static [u8],
+    header: RecordingHeader,
+}
+impl InputRecording {
+    /// Loads a recording, such as one stored in game data or in EWRAM.
+    ///
+    /// This function panics if the data is not a complete recording.
+    #[track_caller]
+    pub fn from_bytes(data: &'static [u8]) -> Self {
+        Self::try_from_bytes(data).unwrap_or_else(|| input_recording_invalid())
+    }
+
+    /// Loads a recording, or returns `None` if the data is not a complete recording.
+    pub fn try_from_bytes(data: &'static [u8]) -> Option<Self> {
+        let header = validate_recording(data)?;
+        Some(InputRecording { data: &data[..header.data_len()], header })
+    }
+
+    /// Reads a recording written by [`write_to_save`] into a buffer.
+    ///
+    /// Returns `None` if there is no complete recording at the offset, or if it does not fit in
+    /// the buffer.
+    ///
+    /// [`write_to_save`]: InputRecording::write_to_save
+    pub fn read_from_save(
+        save: &mut SaveAccess,
+        offset: usize,
+        buffer: &'static mut [u8],
+    ) -> Result<Option<Self>, Error> {
+        if buffer.len() < HEADER_LEN {
+            return Ok(None);
+        }
+        save.read(offset, &mut buffer[..HEADER_LEN])?;
+        let Some(header) = RecordingHeader::decode(buffer) else {
+            return Ok(None);
+        };
+        let len = header.data_len();
+        if len > buffer.len() {
+            return Ok(None);
+        }
+        save.read(offset + HEADER_LEN, &mut buffer[HEADER_LEN..len])?;
+        let buffer: &'static [u8] = buffer;
+        Ok(Self::try_from_bytes(&buffer[..len]))
+    }
+
+    /// Writes this recording into save media, erasing any sectors it overlaps.
+    pub fn write_to_save(&self, save: &mut SaveAccess, offset: usize) -> Result<(), Error> {
+        save.prepare_write(offset..offset + self.data.len())?
+            .write_and_verify(offset, self.data)
+    }
+
+    /// Returns the seed stored when the recording was started.
+    pub fn seed(&self) -> u32 {
+        self.header.seed
+    }
+
+    /// Returns the number of VBlanks in this recording.
+    pub fn frame_count(&self) -> u32 {
+        self.header.frame_count
+    }
+
+    /// Returns the raw data of this recording.
+    pub fn as_bytes(&self) -> &'static [u8] {
+        self.data
+    }
+}
+
+struct Recorder {
+    buffer: &'static mut [u8],
+    seed: u32,
+    frame: u32,
+    entries: u32,
+    last_frame: u32,
+    last_keys: EnumSet<Button>,
+    is_full: bool,
+}
+impl Recorder {
+    fn sample(&mut self) -> EnumSet<Button> {
+        let keys = keypad_keys();
+        if self.is_full {
+            return keys;
+        }
+
+        // an entry is added without a change if the delay would not fit in 16 bits.
+        let delay = self.frame - self.last_frame;
+        if self.entries == 0 || keys != self.last_keys || delay == u16::MAX as u32 {
+            let offset = HEADER_LEN + self.entries as usize * ENTRY_LEN;
+            if offset + ENTRY_LEN > self.buffer.len() {
+                self.is_full = true;
+                return keys;
+            }
+            self.buffer[offset..offset + ENTRY_LEN]
+                .copy_from_slice(&encode_entry(delay as u16, keys.as_repr()));
+            self.entries += 1;
+            self.last_frame = self.frame;
+            self.last_keys = keys;
+        }
+        self.frame += 1;
+        keys
+    }
+
+    fn finish(self) -> InputRecording {
+        let header = RecordingHeader {
+            seed: self.seed,
+            frame_count: self.frame,
+            entry_count: self.entries,
+        };
+        self.buffer[..HEADER_LEN].copy_from_slice(&header.encode());
+        let buffer: &'static [u8] = self.buffer;
+        InputRecording { data: &buffer[..header.data_len()], header }
+    }
+}
+
+struct Replayer {
+    recording: InputRecording,
+    frame: u32,
+    next_entry: u32,
+    entry_frame: u32,
+    keys: EnumSet<Button>,
+}
+impl Replayer {
+    fn sample(&mut self) -> Option<EnumSet<Button>> {
+        let header = &self.recording.header;
+        if self.frame >= header.frame_count {
+            return None;
+        }
+        while self.next_entry < header.entry_count {
+            let offset = HEADER_LEN + self.next_entry as usize * ENTRY_LEN;
+            let (delay, keys) = decode_entry(&self.recording.data[offset..]);
+            if self.entry_frame + delay as u32 > self.frame {
+                break;
+            }
+            self.entry_frame += delay as u32;
+            self.keys = EnumSet::from_repr_truncated(keys);
+            self.next_entry += 1;
+        }
+        self.frame += 1;
+        Some(self.keys)
+    }
+}
+
+enum Tape {
+    Recording(Recorder),
+    Replay(Replayer),
+}
+impl Tape {
+    fn sample(&mut self) -> Option<EnumSet<Button>> {
+        match self {
+            Tape::Recording(recorder) => Some(recorder.sample()),
+            Tape::Replay(replayer) => replayer.sample(),
+        }
+    }
+}
+
+static TAPE: Mutex<Option<Tape>> = Mutex::new(None);
+static TAPE_KEYS: Static<Option<EnumSet<Button>>> = Static::new(None);
+
+/// Samples the keypad for a recording, or advances a replay.
+pub(crate) fn on_vblank() {
+    // the lock is only held by the main thread while interrupts are disabled.
+    if let Some(mut tape) = TAPE.try_lock() {
+        if let Some(current) = &mut *tape {
+            let keys = current.sample();
+            if keys.is_none() {
+                *tape = None;
+            }
+            TAPE_KEYS.write(keys);
+        }
+    }
+}
+
+/// Returns the keys sampled by the current recording or replay, if there is one.
+pub(crate) fn tape_keys() -> Option<EnumSet<Button>> {
+    TAPE_KEYS.read()
+}
+
+#[track_caller]
+fn start_tape(mut tape: Tape) {
+    let started = irq::suppress(|| {
+        let mut current = TAPE.lock();
+        if current.is_some() {
+            return false;
+        }
+        TAPE_KEYS.write(tape.sample());
+        *current = Some(tape);
+        true
+    });
+    if !started {
+        input_tape_in_use();
+    }
+}
+
+/// Starts recording the keypad into a buffer, usually in EWRAM.
+///
+/// The keypad is sampled immediately, and then once per VBlank until the recording is stopped or
+/// the buffer is full. The seed is stored in the recording, and is usually used to seed the
+/// game's random number generator, so the recording can be replayed exactly.
+///
+/// While recording, [`pressed_keys`] returns the last sample rather than reading the keypad, so
+/// the game sees the same input as a replay of the recording. This function panics if the buffer
+/// is too small to contain a recording, or if a recording or replay is already running.
+///
+/// [`pressed_keys`]: crate::sys::pressed_keys
+#[track_caller]
+pub fn start_recording(buffer: &'static mut [u8], seed: u32) {
+    if buffer.len() < HEADER_LEN + ENTRY_LEN {
+        input_recording_buffer_too_small();
+    }
+    start_tape(Tape::Recording(Recorder {
+        buffer,
+        seed,
+        frame: 0,
+        entries: 0,
+        last_frame: 0,
+        last_keys: EnumSet::empty(),
+        is_full: false,
+    }));
+}
+
+/// Stops recording the keypad, and returns the recording.
+///
+/// Returns `None` if the keypad is not being recorded.
+pub fn stop_recording() -> Option<InputRecording> {
+    let recorder = irq::suppress(|| {
+        let mut tape = TAPE.lock();
+        match tape.take() {
+            Some(Tape::Recording(recorder)) => {
+                TAPE_KEYS.write(None);
+                Some(recorder)
+            }
+            other => {
+                *tape = other;
+                None
+            }
+        }
+    });
+    recorder.map(Recorder::finish)
+}
+
+/// Returns whether the keypad is being recorded.
+pub fn is_recording() -> bool {
+    irq::suppress(|| matches!(*TAPE.lock(), Some(Tape::Recording(_))))
+}
+
+/// Returns whether a recording is being recorded and its buffer is full.
+pub fn is_recording_full() -> bool {
+    irq::suppress(|| matches!(&*TAPE.lock(), Some(Tape::Recording(r)) if r.is_full))
+}
+
+/// Starts replaying a recording in place of the keypad.
+///
+/// The first frame of the recording is used immediately, and the next one during each VBlank.
+/// Until the replay ends or is stopped, [`pressed_keys`] returns the buttons from the recording,
+/// and [`keypad_keys`] can be used to read the actual keypad. This function panics if a
+/// recording or replay is already running.
+///
+/// [`pressed_keys`]: crate::sys::pressed_keys
+#[track_caller]
+pub fn start_replay(recording: InputRecording) {
+    start_tape(Tape::Replay(Replayer {
+        recording,
+        frame: 0,
+        next_entry: 0,
+        entry_frame: 0,
+        keys: EnumSet::empty(),
+    }));
+}
+
+/// Stops replaying a recording, and returns to reading the keypad.
+pub fn stop_replay() {
+    irq::suppress(|| {
+        let mut tape = TAPE.lock();
+        if matches!(*tape, Some(Tape::Replay(_))) {
+            *tape = None;
+            TAPE_KEYS.write(None);
+        }
+    });
+}
+
+/// Returns whether a recording is being replayed.
+///
+/// This returns `false` once the end of the recording is reached.
+pub fn is_replaying() -> bool {
+    irq::suppress(|| matches!(*TAPE.lock(), Some(Tape::Replay(_))))
+}
+
+#[inline(never)]
+#[track_caller]
+fn input_recording_invalid() -> ! {
+    crate::panic_handler::static_panic("Input recording data is malformed.")
+}
+
+#[inline(never)]
+#[track_caller]
+fn input_recording_buffer_too_small() -> ! {
+    crate::panic_handler::static_panic("Input recording buffer is too small!")
+}
+
+#[inline(never)]
+#[track_caller]
+fn input_tape_in_use() -> ! {
+    crate::panic_handler::static_panic("An input recording or replay is already running!")
+}
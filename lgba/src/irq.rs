@@ -98,14 +98,14 @@ impl<T: FnMut() + Send + Sync> InterruptHandler<T> {
         })
     }
 
-    /// Unregistered the interrupt handler for execution.
+    /// Unregisters the interrupt handler for execution.
     #[track_caller]
     pub fn deregister(self: Pin<&mut Self>) {
         suppress(|| unsafe {
             if is_in_interrupt() {
                 interrupt_change_in_interrupt();
             }
-            if self.node.is_registered {
+            if !self.node.is_registered {
                 interrupt_not_registered();
             }
 
@@ -119,13 +119,23 @@ impl<T: FnMut() + Send + Sync> InterruptHandler<T> {
             } else {
                 INTERRUPT_TABLE[handler.node.interrupt as usize].write(handler.node.next);
             }
+            handler.node.next = core::ptr::null_mut();
+            handler.node.prev = core::ptr::null_mut();
+            handler.node.is_registered = false;
         })
     }
+
+    /// Returns whether the interrupt handler is currently registered.
+    pub fn is_registered(&self) -> bool {
+        self.node.is_registered
+    }
 }
 impl<T: FnMut() + Send + Sync> Drop for InterruptHandler<T> {
     fn drop(&mut self) {
-        let pin = unsafe { Pin::new_unchecked(self) };
-        pin.deregister();
+        if self.node.is_registered {
+            let pin = unsafe { Pin::new_unchecked(self) };
+            pin.deregister();
+        }
     }
 }
 
@@ -266,6 +276,7 @@ const fn interrupt_change_in_interrupt() {
 /// Executes a closure with interrupts disabled in its body.
 pub fn suppress<R>(mut func: impl FnOnce() -> R) -> R {
     let prev_ime = IME.read();
+    IME.write(false);
 
     memory_write_hint(&mut func);
     let mut result = func();
@@ -315,6 +326,11 @@ unsafe fn trigger_interrupts_0(interrupts: EnumSet<Interrupt>) {
             }
         };
     }
+    if interrupts.contains(Interrupt::VBlank) {
+        // lgba's own vblank tasks run before any user handlers.
+        crate::display::on_vblank();
+        crate::input::on_vblank();
+    }
     check_interrupt!(Interrupt::VBlank);
     check_interrupt!(Interrupt::HBlank);
     check_interrupt!(Interrupt::VCounter);
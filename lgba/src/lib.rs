@@ -55,6 +55,7 @@ mod panic_handler;
 
 pub mod display;
 pub mod dma;
+pub mod input;
 pub mod irq;
 pub mod save;
 pub mod sync;
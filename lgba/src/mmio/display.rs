@@ -37,7 +37,7 @@ packed_struct_fields!(
     (mode, with_mode, DispMode, 0..=2),
     (active_frame, with_active_frame, usize, 4..=4),
     (hblank_oam_access, with_hblank_oam_access, bool, 5),
-    (use_2d_obj_vram, with_use_2d_obj_vram, bool, 6),
+    (obj_char_1d, with_obj_char_1d, bool, 6),
     (forced_blank, with_forced_blank, bool, 7),
     (display_bg0, with_display_bg0, bool, 8),
     (display_bg1, with_display_bg1, bool, 9),
@@ -88,34 +88,105 @@ packed_struct_fields!(
     (enable_mosaic, with_mosaic, bool, 6),
     (enable_256_color, with_enable_256_color, bool, 7),
     (tile_map_base, with_tile_map_base, usize, 8..=12),
+    (affine_wrap, with_affine_wrap, bool, 13),
     (screen_size, with_screen_size, u32, 14..=15),
 );
 
+macro_rules! gba_frac {
+    ($name:ident, $inner:ty, $int:ty) => {
+        impl $name {
+            /// The value `0.0`.
+            pub const ZERO: $name = $name(0);
+            /// The value `1.0`.
+            pub const ONE: $name = $name(1 << 8);
+
+            /// Creates a new value from its raw fixed-point representation.
+            pub const fn from_raw(raw: $inner) -> Self {
+                $name(raw)
+            }
+
+            /// Returns the raw fixed-point representation of this value.
+            pub const fn to_raw(self) -> $inner {
+                self.0
+            }
+
+            /// Creates a new value from an integer.
+            pub const fn from_int(value: $int) -> Self {
+                $name((value as $inner) << 8)
+            }
+
+            /// Creates a new value from the ratio `num / den`, rounding towards zero.
+            #[track_caller]
+            pub const fn from_ratio(num: i32, den: i32) -> Self {
+                $name((((num as i64) << 8) / den as i64) as $inner)
+            }
+
+            /// Returns the integer part of this value, rounding towards negative infinity.
+            pub const fn floor(self) -> $inner {
+                self.0 >> 8
+            }
+        }
+        impl core::ops::Add for $name {
+            type Output = $name;
+            fn add(self, rhs: Self) -> Self::Output {
+                $name(self.0.wrapping_add(rhs.0))
+            }
+        }
+        impl core::ops::Sub for $name {
+            type Output = $name;
+            fn sub(self, rhs: Self) -> Self::Output {
+                $name(self.0.wrapping_sub(rhs.0))
+            }
+        }
+        impl core::ops::Mul for $name {
+            type Output = $name;
+            fn mul(self, rhs: Self) -> Self::Output {
+                $name(((self.0 as i64 * rhs.0 as i64) >> 8) as $inner)
+            }
+        }
+        impl core::ops::Neg for $name {
+            type Output = $name;
+            fn neg(self) -> Self::Output {
+                $name(self.0.wrapping_neg())
+            }
+        }
+        impl core::fmt::Display for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                let abs = (self.0 as i64).unsigned_abs();
+                let sign = if self.0 < 0 { "-" } else { "" };
+                write!(f, "{sign}{}.{:03}", abs >> 8, ((abs & 0xFF) * 1000) >> 8)
+            }
+        }
+    };
+}
+
+/// A signed fixed-point number with 20 integer bits and 8 fractional bits.
+///
+/// This is used for the reference point of affine background layers.
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
 #[repr(transparent)]
-pub struct GbaFrac32(u32);
-#[rustfmt::skip]
-packed_struct_fields!(
-    GbaFrac32, u32,
-
-    // Not directly documented here, as this API will only be used internally.
-    (frac, with_frac, u32, 0..=7),
-    (int, with_int, u32, 8..=26),
-    (sign, with_sign, bool, 27),
-);
+pub struct GbaFrac32(i32);
+gba_frac!(GbaFrac32, i32, i32);
+impl From<GbaFrac16> for GbaFrac32 {
+    fn from(value: GbaFrac16) -> Self {
+        GbaFrac32(value.0 as i32)
+    }
+}
 
+/// A signed fixed-point number with 8 integer bits and 8 fractional bits.
+///
+/// This is used for the parameters of affine transformation matrices.
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
 #[repr(transparent)]
-pub struct GbaFrac16(u16);
-#[rustfmt::skip]
-packed_struct_fields!(
-    GbaFrac16, u16,
+pub struct GbaFrac16(i16);
+gba_frac!(GbaFrac16, i16, i8);
 
-    // Not directly documented here, as this API will only be used internally.
-    (frac, with_frac, u32, 0..=7),
-    (int, with_int, u32, 8..=14),
-    (sign, with_sign, bool, 15),
-);
+/// A 15-bit color, as stored in palette RAM and 16bpp bitmaps.
+///
+/// Each of the red, green and blue components is 5 bits, with red in the lowest bits.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+#[repr(transparent)]
+pub struct Color(pub(crate) u16);
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
 #[repr(transparent)]
@@ -125,17 +196,24 @@ packed_struct_fields!(
     WinBound, u16,
 
     // Not directly documented here, as this API will only be used internally.
-    (max, with_max, u32, 0..=7),
-    (min, with_min, u32, 8..=15),
+    (end, with_end, u32, 0..=7),
+    (start, with_start, u32, 8..=15),
 );
 
+/// Represents something that may be displayed inside of a window.
 #[derive(EnumSetType, Debug)]
 pub enum WinTarget {
+    /// Background layer 0.
     Bg0 = 0,
+    /// Background layer 1.
     Bg1 = 1,
+    /// Background layer 2.
     Bg2 = 2,
+    /// Background layer 3.
     Bg3 = 3,
+    /// Objects.
     Obj = 4,
+    /// The color special effects configured by blending.
     ColorEffect = 5,
 }
 
@@ -171,11 +249,17 @@ packed_struct_fields!(
 /// Represents a layer that may be blended.
 #[derive(EnumSetType, Debug)]
 pub enum BlendTarget {
+    /// Background layer 0.
     Bg0 = 0,
+    /// Background layer 1.
     Bg1 = 1,
+    /// Background layer 2.
     Bg2 = 2,
+    /// Background layer 3.
     Bg3 = 3,
+    /// Objects.
     Obj = 4,
+    /// The backdrop color shown where nothing else is drawn.
     Backdrop = 5,
 }
 
@@ -188,9 +272,9 @@ pub enum BlendingMode {
     None,
     /// The two targets are mixed.
     Alpha,
-    /// The first target is lightened by the second.
+    /// The first target is brightened toward white.
     Lighten,
-    /// The first target is darkened by the second.
+    /// The first target is darkened toward black.
     Darken,
 }
 
@@ -242,6 +326,7 @@ pub enum ObjMode {
     ObjWindow,
 }
 
+/// The shape of an object.
 #[derive(IntoPrimitive, TryFromPrimitive)]
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
 #[repr(u16)]
@@ -258,14 +343,16 @@ pub struct ObjAttr0(u16);
 packed_struct_fields!(
     ObjAttr0, u16,
 
+    // Not directly documented here, as this API will only be used internally.
     (y_coordinate, with_y_coordinate, u32, 0..=7),
     (rotation_enabled, with_rotation_enabled, bool, 8),
+    // `double_size` and `disabled` share a bit, depending on whether rotation is enabled.
     (double_size, with_double_size, bool, 9),
     (disabled, with_disabled, bool, 9),
     (obj_mode, with_obj_mode, ObjMode, 10..=11),
-    (mosiac_enabled, with_mosiac_enabled, bool, 12),
+    (mosaic_enabled, with_mosaic_enabled, bool, 12),
     (use_256_color, with_use_256_color, bool, 13),
-
+    (obj_shape, with_obj_shape, ObjShape, 14..=15),
 );
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
@@ -275,11 +362,12 @@ pub struct ObjAttr1(u16);
 packed_struct_fields!(
     ObjAttr1, u16,
 
-    (x_coordinate, with_x_coordinate, u32, 0..=7),
+    // Not directly documented here, as this API will only be used internally.
+    (x_coordinate, with_x_coordinate, u32, 0..=8),
     (rotation_id, with_rotation_id, usize, 9..=13),
     (h_flip, with_h_flip, bool, 12),
     (v_flip, with_v_flip, bool, 13),
-    (obj_shape, with_obj_shape, u8, 14..=15),
+    (obj_size, with_obj_size, u32, 14..=15),
 );
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
@@ -289,9 +377,8 @@ pub struct ObjAttr2(u16);
 packed_struct_fields!(
     ObjAttr2, u16,
 
-    (x_coordinate, with_x_coordinate, u32, 0..=7),
-    (rotation_id, with_rotation_id, usize, 9..=13),
-    (h_flip, with_h_flip, bool, 12),
-    (v_flip, with_v_flip, bool, 13),
-    (obj_shape, with_obj_shape, u8, 14..=15),
+    // Not directly documented here, as this API will only be used internally.
+    (char, with_char, u16, 0..=9),
+    (priority, with_priority, u32, 10..=11),
+    (palette, with_palette, u8, 12..=15),
 );
@@ -109,8 +109,8 @@ pub const BG_PA: RegSpanned<GbaFrac16, 2, 8> = unsafe { RegSpanned::new(0x400002
 pub const BG_PB: RegSpanned<GbaFrac16, 2, 8> = unsafe { RegSpanned::new(0x4000022) };
 pub const BG_PC: RegSpanned<GbaFrac16, 2, 8> = unsafe { RegSpanned::new(0x4000024) };
 pub const BG_PD: RegSpanned<GbaFrac16, 2, 8> = unsafe { RegSpanned::new(0x4000026) };
-pub const WIN_H: RegSpanned<WinBound, 2, 2> = unsafe { RegSpanned::new(0x4000040) };
-pub const WIN_V: RegSpanned<WinBound, 2, 2> = unsafe { RegSpanned::new(0x4000042) };
+pub const WIN_H: RegSpanned<WinBound, 2, 1> = unsafe { RegSpanned::new(0x4000040) };
+pub const WIN_V: RegSpanned<WinBound, 2, 1> = unsafe { RegSpanned::new(0x4000044) };
 pub const WININ: Register<WinCnt> = unsafe { Register::new(0x4000048) };
 pub const WINOUT: Register<WinCnt> = unsafe { Register::new(0x400004A) };
 pub const MOSAIC: Register<Mosaic> = unsafe { Register::new(0x400004C) };
@@ -121,12 +121,19 @@ pub const BLDY: Register<u16> = unsafe { Register::new(0x4000054) };
 //
 // VRAM Offsets
 //
-pub const BG_PALETTE_RAM: RegArray<u16, 256> = unsafe { RegArray::new(0x5000000) };
-pub const OBJ_PALETTE_RAM: RegArray<u16, 256> = unsafe { RegArray::new(0x5000200) };
+pub const BG_PALETTE_RAM: RegArray<Color, 256> = unsafe { RegArray::new(0x5000000) };
+pub const OBJ_PALETTE_RAM: RegArray<Color, 256> = unsafe { RegArray::new(0x5000200) };
 pub const VRAM_BASE: usize = 0x6000000;
 pub const VRAM_END: usize = 0x6010000;
 pub const VRAM_OBJ_BASE: usize = 0x6010000;
 pub const VRAM_OBJ_END: usize = 0x6018000;
+pub const OAM_BASE: usize = 0x7000000;
+pub const OAM_END: usize = 0x7000400;
+
+//
+// Sound Registers
+//
+pub const SOUNDCNT_L: Register<u16> = unsafe { Register::new(0x4000080) };
 
 //
 // DMA Transfer Registers
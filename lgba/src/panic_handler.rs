@@ -1,8 +1,12 @@
 use crate::{
     asm::{EXH_LGBA_VERSION, EXH_ROM_CNAME, EXH_ROM_CVER, EXH_ROM_REPO},
-    display::{ActiveTerminalAccess, Terminal, TerminalFontAscii},
+    display::{ActiveTerminalAccess, Color, Terminal, TerminalFontAscii},
     dma::DmaChannelId,
     eprintln,
+    mmio::{
+        reg::{DMA_CNT_H, SOUNDCNT_L},
+        sys::DmaCnt,
+    },
     sync::Static,
 };
 use core::{
@@ -10,8 +14,6 @@ use core::{
     panic::{Location, PanicInfo},
 };
 
-// TODO: Prevent long messages from scrolling off the screen.
-
 #[inline(never)]
 fn panic_start() {
     static PANICKING: Static<bool> = Static::new(false);
@@ -66,17 +68,46 @@ fn write_location(
     }
 }
 
+/// Stops all DMA transfers and sound, as [`abort`](crate::sys::abort) does.
+fn stop_dma_and_sound() {
+    for i in 0..4 {
+        unsafe { DMA_CNT_H.index(i).write(DmaCnt::default()) }
+    }
+    SOUNDCNT_L.write(0);
+}
+
 fn panic_with_term(func: impl FnOnce(&mut ActiveTerminalAccess<TerminalFontAscii>)) -> ! {
     // set up the graphical terminal with a basic font
     let mut terminal = Terminal::new();
-    terminal.set_color(0, crate::display::rgb_24bpp(200, 0, 0), !0);
+    terminal.set_color(0, Color::from_rgb24(200, 0, 0), Color::WHITE);
     terminal.use_dma_channel(DmaChannelId::Dma3);
+
+    // keep long messages in dedicated storage, as the heap may still contain the panic message
+    #[cfg(feature = "panic_scrollback")]
+    {
+        #[crate::ewram]
+        static PANIC_SCROLLBACK: crate::display::TerminalScrollback<64> =
+            crate::display::TerminalScrollback::new();
+        terminal.set_scrollback(&PANIC_SCROLLBACK);
+    }
+
     let terminal = terminal.activate_no_lock::<TerminalFontAscii>();
     let mut terminal = terminal.lock();
 
     // run the actual function
     func(&mut terminal);
 
+    // allow the parts of the message that scrolled off the screen to be read
+    if terminal.scrollback_lines() > 0 {
+        // this never reaches `abort`, so stop DMA and sound the same way it would.
+        stop_dma_and_sound();
+        terminal.set_half_width(true);
+        terminal.write_str("\n(Use Up/Down and L/R to scroll, and B to return.)");
+        loop {
+            terminal.view_scrollback();
+        }
+    }
+
     // abort cleanly
     crate::sys::abort()
 }
@@ -32,3 +32,32 @@ pub fn wait_for_vblank() {
 const fn wait_for_vblank_in_interrupt() {
     panic!("wait_for_vblank cannot be called in an interrupt.");
 }
+
+/// Computes the parameters of a rotation and scaling matrix using the BIOS `ObjAffineSet`
+/// function.
+///
+/// The scale factors are in 8.8 fixed point, and the angle is in units of 1/65536th of a full
+/// rotation, of which only the upper 8 bits are used.
+pub(crate) fn obj_affine_set(scale_x: i16, scale_y: i16, angle: u16) -> [i16; 4] {
+    #[repr(C, align(4))]
+    struct ObjAffineSource {
+        scale_x: i16,
+        scale_y: i16,
+        angle: u16,
+        _pad: u16,
+    }
+
+    let source = ObjAffineSource { scale_x, scale_y, angle, _pad: 0 };
+    let mut dest = [0i16; 4];
+    unsafe {
+        asm!(
+            "swi #0x0F",
+            inout("r0") &source as *const _ => _,
+            inout("r1") dest.as_mut_ptr() => _,
+            inout("r2") 1 => _,
+            inout("r3") 2 => _,
+            out("r12") _,
+        );
+    }
+    dest
+}
@@ -1,7 +1,7 @@
 //! Various functions and helper types for basic GBA system functions.
 
 use crate::mmio::{
-    reg::{KEYCNT, KEYINPUT},
+    reg::KEYCNT,
     sys::{ButtonCondition, KeyCnt},
 };
 use core::ops::Range;
@@ -24,9 +24,10 @@ pub use crate::mmio::sys::Button;
 
 /// Returns the currently pressed keys.
 ///
-/// This should be called once a frame, instead of every time button state is checked.
+/// This should be called once a frame, instead of every time button state is checked. While the
+/// keypad is being recorded or replayed, this returns the keys sampled at the last VBlank.
 pub fn pressed_keys() -> EnumSet<Button> {
-    !KEYINPUT.read()
+    crate::input::tape_keys().unwrap_or_else(crate::input::keypad_keys)
 }
 
 /// Sets the keys that trigger a [`Keypad`] interrupt.
@@ -38,6 +38,8 @@ impl FilesystemEncoder {
     fn write_serial_bytes(&mut self, data: &[u8]) -> Result<SerialSlice<u8>> {
         let hash = hashed(data, 0);
         if !self.encoder.cached_objects.contains_key(&hash) {
+            // files are aligned so they can be copied to VRAM or read as words directly
+            self.encoder.align::<u32>();
             let ptr = self.encoder.encode_bytes(data)?;
             self.encoder.cached_objects.insert(hash, ptr);
         }
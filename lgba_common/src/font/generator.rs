@@ -1,25 +1,31 @@
-use crate::build_fonts::{
-    font_data::{data_is_half_width, load_fonts, CharacterInfo, CharacterSets},
+use crate::font::{
+    sources::{data_is_half_width, CharacterInfo, FontSource},
     FontConfig,
 };
+use anyhow::{bail, Result};
 use kanji::Level;
 use lgba_phf::generator::SpecialTy;
 use proc_macro2::{Literal, TokenStream};
 use quote::quote;
-use std::collections::{BTreeSet, HashMap, HashSet};
-use syn::Result;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    format,
+    string::String,
+    vec,
+    vec::Vec,
+};
 use unic_ucd_bidi::BidiClass;
 use unic_ucd_block::Block;
 use unic_ucd_common::is_control;
 use unic_ucd_normal::is_combining_mark;
 
 #[derive(Clone, Debug)]
-enum DecodedMap {
-    Contents(BTreeSet<&'static str>),
+enum DecodedMap<'a> {
+    Contents(BTreeSet<&'a str>),
     Wildcard,
 }
-impl DecodedMap {
-    fn from_list(list: Vec<&'static str>) -> Self {
+impl<'a> DecodedMap<'a> {
+    fn from_list(list: Vec<&'a str>) -> Self {
         let mut set = BTreeSet::new();
         for i in list {
             if i == "*" {
@@ -38,19 +44,18 @@ impl DecodedMap {
 }
 
 #[derive(Clone, Debug)]
-struct DecodedFontConfig {
+struct DecodedFontConfig<'a> {
     low_plane_limit: usize,
-    disable_unscii: DecodedMap,
-    disable_misaki: DecodedMap,
+    sources: Vec<FontSource>,
     chars: HashSet<char>,
-    block: DecodedMap,
-    allow_halfwidth_blocks: DecodedMap,
+    block: DecodedMap<'a>,
+    allow_halfwidth_blocks: DecodedMap<'a>,
     fallback_char: char,
     kanji_max_level: Level,
     delta: f32,
 }
-impl DecodedFontConfig {
-    fn from_config(config: FontConfig) -> Result<DecodedFontConfig> {
+impl<'a> DecodedFontConfig<'a> {
+    fn from_config(config: FontConfig<'a>) -> Result<DecodedFontConfig<'a>> {
         let mut whitelisted_chars = HashSet::new();
         for string in config.chars {
             for char in string.chars() {
@@ -60,8 +65,7 @@ impl DecodedFontConfig {
 
         Ok(DecodedFontConfig {
             low_plane_limit: config.low_plane_limit.unwrap_or(0x100),
-            disable_unscii: DecodedMap::from_list(config.disable_unscii),
-            disable_misaki: DecodedMap::from_list(config.disable_misaki),
+            sources: config.sources,
             chars: whitelisted_chars,
             block: DecodedMap::from_list(config.block),
             allow_halfwidth_blocks: DecodedMap::from_list(config.allow_halfwidth_blocks),
@@ -79,7 +83,7 @@ impl DecodedFontConfig {
                 "2" | "Two" | "two" => Level::Two,
                 "PreOne" | "preone" => Level::PreOne,
                 "1" | "One" | "one" => Level::One,
-                x => panic!("'{}' is not a valid kanji max level.", x),
+                x => bail!("'{}' is not a valid kanji max level.", x),
             },
             delta: config.delta.unwrap_or(1.0),
         })
@@ -111,29 +115,28 @@ fn is_lgba_private_use(ch: char) -> bool {
     (ch >= 0xF400 && ch < 0xF480) || (ch >= 0xF500 && ch < 0xF510)
 }
 
-fn build_from_fonts(config: &DecodedFontConfig, characters: &CharacterSets) -> Vec<CharacterInfo> {
+fn build_from_fonts(config: &DecodedFontConfig) -> Vec<CharacterInfo> {
     let mut char_map = HashMap::new();
-
-    // add characters from unscii
-    for char in &characters.unscii {
-        let is_disabled = !config.disable_unscii.contains(block_name(char.ch));
-        if char.ch != '\0' && is_disabled {
-            char_map.insert(char.ch, process_char(config, *char));
-        }
-    }
-
-    // add characters from misaki
-    for char in &characters.misaki {
-        let is_disabled = !config.disable_misaki.contains(block_name(char.ch));
-        if char.ch != '\0' && is_disabled && !char_map.contains_key(&char.ch) {
-            char_map.insert(char.ch, process_char(config, *char));
-        }
-        if (char.ch as u32) < 0x80 {
-            let new_ch = char::from_u32(0xF400 + char.ch as u32).unwrap();
-            let mut new_char = *char;
-            new_char.ch = new_ch;
-            char_map.insert(new_ch, new_char);
+    let mut has_half_width_ascii = false;
+
+    // add characters from each source, with earlier sources taking priority
+    for source in &config.sources {
+        let disabled =
+            DecodedMap::from_list(source.disabled_blocks.iter().map(|x| x.as_str()).collect());
+        let provides_half_width_ascii = source.half_width_ascii && !has_half_width_ascii;
+        for char in &source.glyphs {
+            let is_enabled = !disabled.contains(block_name(char.ch));
+            if is_enabled && !char_map.contains_key(&char.ch) {
+                char_map.insert(char.ch, process_char(config, *char));
+            }
+            if provides_half_width_ascii && (char.ch as u32) < 0x80 {
+                let new_ch = char::from_u32(0xF400 + char.ch as u32).unwrap();
+                let mut new_char = *char;
+                new_char.ch = new_ch;
+                char_map.insert(new_ch, new_char);
+            }
         }
+        has_half_width_ascii |= source.half_width_ascii;
     }
 
     // add space characters
@@ -384,7 +387,7 @@ impl GlyphPlaneBuilder {
     }
 }
 
-fn build_planes(config: &mut DecodedFontConfig, ch_data: CharacterData) -> GlyphData {
+fn build_planes(config: &mut DecodedFontConfig, ch_data: CharacterData) -> Result<GlyphData> {
     let tile_count = ((ch_data.glyph_count + 4 + 15) / 16) * 16;
     if config.low_plane_limit > tile_count {
         config.low_plane_limit = tile_count;
@@ -403,7 +406,9 @@ fn build_planes(config: &mut DecodedFontConfig, ch_data: CharacterData) -> Glyph
         ch_count - low_plane_dupe_check.len()
     };
     let tile_count = ((ch_data.glyph_count + dupe_count + 4 + 15) / 16) * 16 + 16;
-    assert!(tile_count <= 4096);
+    if tile_count > 4096 {
+        bail!("Too many distinct glyphs for a terminal font: {} glyphs", ch_data.glyph_count);
+    }
 
     // create a new glyph builder
     let mut builder = GlyphPlaneBuilder {
@@ -494,14 +499,14 @@ fn build_planes(config: &mut DecodedFontConfig, ch_data: CharacterData) -> Glyph
     }
 
     // Returns the glyph data
-    GlyphData {
+    Ok(GlyphData {
         tile_count,
         data,
         low_plane: builder.low_plane_table,
         low_plane_half_width: builder.low_plane_half_width,
         glyph_map: builder.glyph_map,
         glyph_lookup: builder.glyph_lookup,
-    }
+    })
 }
 
 fn make_u8_literal(data: &[u8]) -> TokenStream {
@@ -511,18 +516,19 @@ fn make_u8_literal(data: &[u8]) -> TokenStream {
 fn make_u16_literal(data: &[u16]) -> TokenStream {
     quote! { [#(#data,)*] }
 }
-fn make_u32_data_literal(data: &[u8]) -> TokenStream {
+fn make_u32_data_literal(data: &[u8], lgba: &TokenStream) -> TokenStream {
     assert_eq!(data.len() % 4, 0);
     let literal_data = Literal::byte_string(data);
     let data_len = data.len() / 4;
-    quote! { crate::__macro_export::xfer_u8_u32::<#data_len>(#literal_data) }
+    quote! { #lgba::__macro_export::xfer_u8_u32::<#data_len>(#literal_data) }
 }
 
 fn make_glyphs_file(
     config: &DecodedFontConfig,
     glyphs: GlyphData,
     target_ty: TokenStream,
-) -> TokenStream {
+    lgba: TokenStream,
+) -> Result<TokenStream> {
     // Creates the low plane table bitset
     let mut low_plane = vec![0u16; config.low_plane_limit / 16];
     for (i, is_low_glyph) in glyphs.low_plane.iter().enumerate() {
@@ -543,12 +549,13 @@ fn make_glyphs_file(
     }
 
     // Compute the raw PHF for the high planes
-    let entries: Vec<_> = glyphs.glyph_map.keys().cloned().collect();
+    let mut entries: Vec<_> = glyphs.glyph_map.keys().cloned().collect();
+    entries.sort();
     let phf = lgba_phf::generator::generate_hash(config.delta, &entries);
     let phf_func = phf.generate_syn_code(
         quote! { lookup_glyph },
         quote! { &u16 },
-        quote! { lgba_phf },
+        quote! { #lgba::__macro_export::lgba_phf },
         Some(SpecialTy::U16),
     );
 
@@ -590,7 +597,13 @@ fn make_glyphs_file(
     }
 
     // Find the replacement glyph
-    let (fb_hi, fb_lo, fb_half) = glyphs.glyph_lookup[&(config.fallback_char as u16)];
+    let Some(&(fb_hi, fb_lo, fb_half)) = glyphs.glyph_lookup.get(&(config.fallback_char as u16))
+    else {
+        bail!(
+            "The fallback character '{}' is not available in this font.",
+            config.fallback_char
+        );
+    };
 
     // Calculate statistics
     let bytes = glyphs.data.len()
@@ -641,7 +654,7 @@ fn make_glyphs_file(
     let glyph_check_data = make_u16_literal(&glyph_check);
     let glyph_id_lo_data = make_u8_literal(&glyph_id_lo);
 
-    let font_data = make_u32_data_literal(&glyphs.data);
+    let font_data = make_u32_data_literal(&glyphs.data, &lgba);
     let font_data_size = glyphs.tile_count * 2;
 
     let (load_hi_defines, load_hi) = if hi_bits != 0 {
@@ -720,7 +733,7 @@ fn make_glyphs_file(
         }
 
         #[doc = #documentation]
-        impl crate::display::TerminalFont for #target_ty {
+        impl #lgba::display::TerminalFont for #target_ty {
             fn get_font_glyph(id: char) -> (u8, u16, bool) {
                 get_font_glyph(id)
             }
@@ -734,22 +747,21 @@ fn make_glyphs_file(
         std::fs::write(format!("font_data_{target_ty}.bin"), &glyphs.data).unwrap();
     }
 
-    quote! {
+    Ok(quote! {
         const _: () = {
             #impl_content
-            ()
         };
-    }
+    })
 }
 
-pub fn generate_fonts(config: FontConfig, target_ty: TokenStream) -> Result<TokenStream> {
-    let characters = load_fonts();
-
+pub fn generate_fonts(
+    config: FontConfig,
+    target_ty: TokenStream,
+    lgba: TokenStream,
+) -> Result<TokenStream> {
     let mut config = DecodedFontConfig::from_config(config)?;
-    let characters = build_from_fonts(&config, &characters);
+    let characters = build_from_fonts(&config);
     let character_list = filter_characters(&config, characters);
-    let glyphs = build_planes(&mut config, character_list);
-    let tokens = make_glyphs_file(&config, glyphs, target_ty);
-
-    Ok(tokens)
+    let glyphs = build_planes(&mut config, character_list)?;
+    make_glyphs_file(&config, glyphs, target_ty, lgba)
 }
@@ -0,0 +1,42 @@
+//! The generator for terminal fonts.
+
+mod generator;
+mod sources;
+
+pub use sources::FontSource;
+
+use anyhow::Result;
+use proc_macro2::TokenStream;
+use std::vec::Vec;
+
+/// The configuration for a generated terminal font.
+#[derive(Clone, Debug, Default)]
+pub struct FontConfig<'a> {
+    /// The fonts that glyphs are taken from, in order of priority.
+    pub sources: Vec<FontSource>,
+    /// The number of characters stored in the low plane, which can be looked up more quickly.
+    pub low_plane_limit: Option<usize>,
+    /// Strings containing characters that should be included in the font.
+    pub chars: Vec<&'a str>,
+    /// Unicode blocks that should be included in the font, or `*` for all blocks.
+    pub block: Vec<&'a str>,
+    /// Unicode blocks where characters may be rendered as half-width characters.
+    pub allow_halfwidth_blocks: Vec<&'a str>,
+    /// The character used for characters missing from the font.
+    pub fallback_char: Option<char>,
+    /// The highest kanji level included in the font.
+    pub kanji_max_level: Option<&'a str>,
+    /// The delta used for generating the perfect hash function.
+    pub delta: Option<f32>,
+}
+
+/// Generates an implementation of `TerminalFont` for a type.
+///
+/// `lgba` is the path of the `lgba` crate in the generated code.
+pub fn generate_font(
+    config: FontConfig,
+    target_ty: TokenStream,
+    lgba: TokenStream,
+) -> Result<TokenStream> {
+    generator::generate_fonts(config, target_ty, lgba)
+}
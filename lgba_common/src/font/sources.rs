@@ -0,0 +1,142 @@
+use anyhow::{bail, Context, Result};
+use std::{cmp, format, io::Cursor, string::String, vec::Vec};
+
+const UNSCII_DATA: &str = include_str!("data/unscii-8.hex");
+const MISAKI_DATA: &[u8] = include_bytes!("data/misaki_gothic_2nd.bdf");
+
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub struct CharacterInfo {
+    pub ch: char,
+    pub data: u64,
+    pub is_half_width: bool,
+}
+impl CharacterInfo {
+    fn new(ch: char, data: u64) -> Self {
+        let has_left = (data & 0xF0F0F0F0F0F0F0F0) != 0;
+        let has_right = (data & 0x0F0F0F0F0F0F0F0F) != 0;
+        let is_half_width = has_left && !has_right;
+        CharacterInfo { ch, data, is_half_width }
+    }
+}
+
+pub fn data_is_half_width(data: u64) -> bool {
+    (data & 0x0F0F0F0F0F0F0F0F) == 0
+}
+
+/// A set of 8x8 glyphs that a terminal font can be built from.
+///
+/// When a font is built from multiple sources, characters are taken from the first source that
+/// contains them.
+#[derive(Clone, Debug)]
+pub struct FontSource {
+    pub(crate) glyphs: Vec<CharacterInfo>,
+    pub(crate) disabled_blocks: Vec<String>,
+    pub(crate) half_width_ascii: bool,
+}
+impl FontSource {
+    fn from_glyphs(mut glyphs: Vec<CharacterInfo>) -> Self {
+        glyphs.retain(|x| x.ch != '\0');
+        glyphs.sort_by_key(|x| x.ch as u32);
+        FontSource { glyphs, disabled_blocks: Vec::new(), half_width_ascii: false }
+    }
+
+    /// Parses a font in the `.hex` format, with one `codepoint:glyph` line per character.
+    ///
+    /// Only 8x8 glyphs (with 16 hex digits) are supported.
+    pub fn from_hex(data: &str) -> Result<Self> {
+        let mut glyphs = Vec::new();
+        for (i, line) in data.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let line_no = i + 1;
+
+            let Some((hex_str, hex_bmp)) = line.split_once(':') else {
+                bail!("line {line_no}: expected `codepoint:glyph`");
+            };
+            let ch = u32::from_str_radix(hex_str, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .with_context(|| format!("line {line_no}: invalid codepoint '{hex_str}'"))?;
+            if ch == '\0' {
+                continue;
+            }
+            if hex_bmp.len() != 16 {
+                bail!("line {line_no}: only 8x8 glyphs are supported");
+            }
+            let data = u64::from_str_radix(hex_bmp, 16)
+                .with_context(|| format!("line {line_no}: invalid glyph data"))?;
+            glyphs.push(CharacterInfo::new(ch, data));
+        }
+        Ok(FontSource::from_glyphs(glyphs))
+    }
+
+    /// Parses a font in the BDF format.
+    ///
+    /// Every glyph must fit into an 8x8 cell.
+    pub fn from_bdf(data: &[u8]) -> Result<Self> {
+        let font = match bdf::read(Cursor::new(data)) {
+            Ok(font) => font,
+            Err(e) => bail!("could not parse BDF font: {e}"),
+        };
+
+        let mut glyphs = Vec::new();
+        for (ch, glyph) in font.glyphs() {
+            // compute the bounds of the glyph
+            let (width, height) = (glyph.width() as i32, glyph.height() as i32);
+            let x_off = cmp::max(0, glyph.bounds().x);
+            let y_off = if height < 8 {
+                (8 - height) - 1 - cmp::max(0, glyph.bounds().y)
+            } else {
+                0
+            };
+            if y_off < 0 || x_off + width > 8 || y_off + height > 8 {
+                bail!("glyph U+{:04X} does not fit into an 8x8 cell", *ch as u32);
+            }
+            let (x_off, y_off) = (x_off as u32, y_off as u32);
+
+            // copy the glyph to a `u64` format
+            let mut data = 0u64;
+            for x in 0..glyph.width() {
+                for y in 0..glyph.height() {
+                    let tx = x + x_off;
+                    let ty = y + y_off;
+                    data |= (glyph.get(x, y) as u64) << (63 - (tx + ty * 8));
+                }
+            }
+            glyphs.push(CharacterInfo::new(*ch, data));
+        }
+        Ok(FontSource::from_glyphs(glyphs))
+    }
+
+    /// Returns the bundled unscii-8 font.
+    pub fn unscii() -> Self {
+        FontSource::from_hex(UNSCII_DATA).unwrap()
+    }
+
+    /// Returns the bundled Misaki Gothic font, which also provides the half-width ASCII glyphs.
+    pub fn misaki() -> Self {
+        FontSource::from_bdf(MISAKI_DATA)
+            .unwrap()
+            .with_half_width_ascii(true)
+    }
+
+    /// Excludes Unicode blocks from this source, by name.
+    ///
+    /// Characters in these blocks may still be taken from later sources.
+    pub fn with_disabled_blocks(mut self, blocks: &[&str]) -> Self {
+        self.disabled_blocks
+            .extend(blocks.iter().map(|x| String::from(*x)));
+        self
+    }
+
+    /// Sets whether this source provides the glyphs used for ASCII characters in the half-width
+    /// mode of the terminal.
+    ///
+    /// These glyphs must only use the left 4 columns of the character.
+    pub fn with_half_width_ascii(mut self, half_width_ascii: bool) -> Self {
+        self.half_width_ascii = half_width_ascii;
+        self
+    }
+}
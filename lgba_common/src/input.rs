@@ -0,0 +1,148 @@
+//! The format used for keypad recordings.
+//!
+//! A recording begins with a 20 byte header: the magic number `lGir`, the format version as a
+//! `u16`, a reserved `u16`, a seed chosen by the game as a `u32`, the number of frames recorded
+//! as a `u32`, and the number of entries as a `u32`. It is followed by 4 byte entries, each
+//! containing the number of frames since the previous entry as a `u16`, and the buttons held
+//! from that frame onwards as a `u16` in the format of `KEYINPUT` with the bits inverted. The
+//! first entry is always on frame 0.
+//!
+//! All numbers are little-endian.
+
+pub const RECORDING_MAGIC: [u8; 4] = *b"lGir";
+pub const RECORDING_VERSION: u16 = 1;
+pub const HEADER_LEN: usize = 20;
+pub const ENTRY_LEN: usize = 4;
+
+/// The names of each button, in the order of their bits.
+pub const BUTTON_NAMES: [&str; 10] =
+    ["A", "B", "Select", "Start", "Right", "Left", "Up", "Down", "R", "L"];
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct RecordingHeader {
+    pub seed: u32,
+    pub frame_count: u32,
+    pub entry_count: u32,
+}
+impl RecordingHeader {
+    /// Parses a header, returning `None` if it is not a recording of a supported version.
+    pub fn decode(data: &[u8]) -> Option<RecordingHeader> {
+        if data.len() < HEADER_LEN
+            || data[0..4] != RECORDING_MAGIC
+            || read_u16(data, 4) != RECORDING_VERSION
+        {
+            return None;
+        }
+        Some(RecordingHeader {
+            seed: read_u32(data, 8),
+            frame_count: read_u32(data, 12),
+            entry_count: read_u32(data, 16),
+        })
+    }
+
+    pub fn encode(&self) -> [u8; HEADER_LEN] {
+        let mut data = [0; HEADER_LEN];
+        data[0..4].copy_from_slice(&RECORDING_MAGIC);
+        data[4..6].copy_from_slice(&RECORDING_VERSION.to_le_bytes());
+        data[8..12].copy_from_slice(&self.seed.to_le_bytes());
+        data[12..16].copy_from_slice(&self.frame_count.to_le_bytes());
+        data[16..20].copy_from_slice(&self.entry_count.to_le_bytes());
+        data
+    }
+
+    /// Returns the length of the recording in bytes, including the header.
+    pub fn data_len(&self) -> usize {
+        (self.entry_count as usize)
+            .saturating_mul(ENTRY_LEN)
+            .saturating_add(HEADER_LEN)
+    }
+}
+
+pub fn encode_entry(delay: u16, keys: u16) -> [u8; ENTRY_LEN] {
+    let mut data = [0; ENTRY_LEN];
+    data[0..2].copy_from_slice(&delay.to_le_bytes());
+    data[2..4].copy_from_slice(&keys.to_le_bytes());
+    data
+}
+
+/// Returns the number of frames since the previous entry, and the buttons held.
+pub fn decode_entry(data: &[u8]) -> (u16, u16) {
+    (read_u16(data, 0), read_u16(data, 2))
+}
+
+/// Checks that a recording is complete and well-formed, and returns its header.
+pub fn validate_recording(data: &[u8]) -> Option<RecordingHeader> {
+    let header = RecordingHeader::decode(data)?;
+    if data.len() < header.data_len() || (header.entry_count == 0) != (header.frame_count == 0) {
+        return None;
+    }
+    let mut last = None;
+    for (frame, keys) in RecordingEntries::new(data, &header) {
+        let in_order = match last {
+            None => frame == 0,
+            Some(last) => frame > last,
+        };
+        if !in_order || frame >= header.frame_count || keys >> BUTTON_NAMES.len() != 0 {
+            return None;
+        }
+        last = Some(frame);
+    }
+    Some(header)
+}
+
+/// An iterator over the entries of a recording, returning the frame of each entry and the
+/// buttons held starting from it.
+pub struct RecordingEntries<'a> {
+    entries: &'a [u8],
+    frame: u32,
+}
+impl<'a> RecordingEntries<'a> {
+    pub fn new(data: &'a [u8], header: &RecordingHeader) -> Self {
+        RecordingEntries { entries: &data[HEADER_LEN..header.data_len()], frame: 0 }
+    }
+}
+impl<'a> Iterator for RecordingEntries<'a> {
+    type Item = (u32, u16);
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let (delay, keys) = decode_entry(self.entries);
+        self.entries = &self.entries[ENTRY_LEN..];
+        self.frame = self.frame.saturating_add(delay as u32);
+        Some((self.frame, keys))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::input::*;
+
+    #[test]
+    fn test_recording_round_trip() {
+        let header = RecordingHeader { seed: 1234, frame_count: 70000, entry_count: 3 };
+        let mut data = [0; HEADER_LEN + 3 * ENTRY_LEN];
+        data[..HEADER_LEN].copy_from_slice(&header.encode());
+        data[HEADER_LEN..HEADER_LEN + 4].copy_from_slice(&encode_entry(0, 0));
+        data[HEADER_LEN + 4..HEADER_LEN + 8].copy_from_slice(&encode_entry(60, 0b1001));
+        data[HEADER_LEN + 8..].copy_from_slice(&encode_entry(u16::MAX, 0b1001));
+
+        assert_eq!(validate_recording(&data), Some(header));
+        let mut entries = RecordingEntries::new(&data, &header);
+        assert_eq!(entries.next(), Some((0, 0)));
+        assert_eq!(entries.next(), Some((60, 0b1001)));
+        assert_eq!(entries.next(), Some((65595, 0b1001)));
+        assert_eq!(entries.next(), None);
+
+        assert_eq!(validate_recording(&data[..data.len() - 1]), None);
+        data[HEADER_LEN + 4..HEADER_LEN + 6].copy_from_slice(&0u16.to_le_bytes());
+        assert_eq!(validate_recording(&data), None);
+    }
+}
@@ -9,6 +9,7 @@
 extern crate std;
 
 pub mod common;
+pub mod input;
 
 #[cfg(feature = "data")]
 pub mod data;
@@ -19,5 +20,8 @@ pub mod phf;
 #[cfg(feature = "generator_build")]
 mod encoder;
 
+#[cfg(feature = "generator_font")]
+pub mod font;
+
 #[cfg(feature = "hashes")]
 pub mod hashes;
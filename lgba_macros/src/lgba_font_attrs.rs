@@ -0,0 +1,171 @@
+use crate::error;
+use lgba_common::font::{generate_font, FontConfig, FontSource};
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as SynTokenStream};
+use quote::quote;
+use std::{fs, path::PathBuf};
+use syn::{
+    braced, bracketed,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Attribute, Ident, LitBool, LitChar, LitInt, LitStr, Result, Token, Visibility,
+};
+
+fn parse_str_list(input: ParseStream) -> Result<Vec<LitStr>> {
+    let content;
+    bracketed!(content in input);
+    let list = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+    Ok(list.into_iter().collect())
+}
+
+struct FontInvocation {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    sources: Vec<LitStr>,
+    builtin: bool,
+    chars: Vec<LitStr>,
+    blocks: Vec<LitStr>,
+    halfwidth_blocks: Vec<LitStr>,
+    fallback: Option<char>,
+    kanji_max_level: Option<LitStr>,
+    low_plane_limit: Option<usize>,
+}
+impl Parse for FontInvocation {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis: Visibility = input.parse()?;
+        let name: Ident = input.parse()?;
+
+        let mut invocation = FontInvocation {
+            attrs,
+            vis,
+            name,
+            sources: Vec::new(),
+            builtin: true,
+            chars: Vec::new(),
+            blocks: Vec::new(),
+            halfwidth_blocks: Vec::new(),
+            fallback: None,
+            kanji_max_level: None,
+            low_plane_limit: None,
+        };
+
+        let content;
+        braced!(content in input);
+        while !content.is_empty() {
+            let key: Ident = content.parse()?;
+            content.parse::<Token![:]>()?;
+            match key.to_string().as_str() {
+                "sources" => invocation.sources = parse_str_list(&content)?,
+                "builtin" => invocation.builtin = content.parse::<LitBool>()?.value,
+                "chars" => invocation.chars.push(content.parse()?),
+                "blocks" => invocation.blocks = parse_str_list(&content)?,
+                "halfwidth_blocks" => invocation.halfwidth_blocks = parse_str_list(&content)?,
+                "fallback" => invocation.fallback = Some(content.parse::<LitChar>()?.value()),
+                "kanji_max_level" => invocation.kanji_max_level = Some(content.parse()?),
+                "low_plane_limit" => {
+                    invocation.low_plane_limit = Some(content.parse::<LitInt>()?.base10_parse()?)
+                }
+                _ => error(key.span(), format_args!("Unknown font option `{key}`."))?,
+            }
+            if !content.is_empty() {
+                content.parse::<Token![,]>()?;
+            }
+        }
+        Ok(invocation)
+    }
+}
+
+fn load_source(lit: &LitStr) -> Result<(PathBuf, FontSource)> {
+    let path = std::env::var("CARGO_MANIFEST_DIR").expect("Could not find CARGO_MANIFEST_DIR?");
+    let mut path = PathBuf::from(path);
+    path.push(lit.value());
+
+    let data = match fs::read(&path) {
+        Ok(v) => v,
+        Err(e) => {
+            error(lit.span(), format_args!("Could not read font at '{}': {e}", path.display()))?
+        }
+    };
+    let source = match path.extension().and_then(|x| x.to_str()) {
+        Some("bdf") => FontSource::from_bdf(&data),
+        Some("hex") => match std::str::from_utf8(&data) {
+            Ok(data) => FontSource::from_hex(data),
+            Err(_) => error(lit.span(), "Font files in the `.hex` format must be UTF-8.")?,
+        },
+        _ => error(lit.span(), "Fonts must be in the `.bdf` or `.hex` format.")?,
+    };
+    match source {
+        Ok(source) => Ok((path, source)),
+        Err(e) => {
+            error(lit.span(), format_args!("Could not parse font at '{}': {e}", path.display()))?
+        }
+    }
+}
+
+fn terminal_font_impl_0(input: SynTokenStream) -> Result<SynTokenStream> {
+    let invocation: FontInvocation = syn::parse2(input)?;
+
+    // load the font sources
+    let mut paths = Vec::new();
+    let mut sources = Vec::new();
+    for lit in &invocation.sources {
+        let (path, source) = load_source(lit)?;
+        paths.push(path.to_string_lossy().to_string());
+        sources.push(source);
+    }
+    if invocation.builtin {
+        sources.push(FontSource::unscii());
+        sources.push(FontSource::misaki());
+    }
+    if sources.is_empty() {
+        error(invocation.name.span(), "A terminal font requires at least one source.")?;
+    }
+
+    // generate the font
+    let chars: Vec<_> = invocation.chars.iter().map(|x| x.value()).collect();
+    let blocks: Vec<_> = invocation.blocks.iter().map(|x| x.value()).collect();
+    let halfwidth_blocks: Vec<_> = invocation
+        .halfwidth_blocks
+        .iter()
+        .map(|x| x.value())
+        .collect();
+    let kanji_max_level = invocation.kanji_max_level.as_ref().map(|x| x.value());
+    let config = FontConfig {
+        sources,
+        low_plane_limit: invocation.low_plane_limit,
+        chars: chars.iter().map(|x| x.as_str()).collect(),
+        block: blocks.iter().map(|x| x.as_str()).collect(),
+        allow_halfwidth_blocks: halfwidth_blocks.iter().map(|x| x.as_str()).collect(),
+        fallback_char: invocation.fallback,
+        kanji_max_level: kanji_max_level.as_deref(),
+        delta: None,
+    };
+
+    let name = &invocation.name;
+    let tokens = match generate_font(config, quote! { #name }, quote! { lgba }) {
+        Ok(v) => v,
+        Err(e) => error(Span::call_site(), format_args!("Could not generate font: {e}"))?,
+    };
+
+    // generate the final type
+    let attrs = &invocation.attrs;
+    let vis = &invocation.vis;
+    Ok(quote! {
+        #(#attrs)*
+        #vis enum #name {}
+        #tokens
+
+        // ensures the font is rebuilt when its sources change.
+        const _: () = {
+            #(const _: &[u8] = include_bytes!(#paths);)*
+        };
+    })
+}
+pub fn terminal_font_impl(input: TokenStream) -> TokenStream {
+    match terminal_font_impl_0(input.into()) {
+        Ok(x) => x.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
@@ -6,6 +6,9 @@ mod lgba_attrs;
 #[cfg(feature = "data")]
 mod lgba_data_attrs;
 
+#[cfg(feature = "terminal_font")]
+mod lgba_font_attrs;
+
 extern crate proc_macro;
 
 use proc_macro::TokenStream;
@@ -82,3 +85,40 @@ pub fn hash_impl(item: TokenStream) -> TokenStream {
 pub fn load_data_impl(item: TokenStream) -> TokenStream {
     lgba_data_attrs::load_data_impl(item)
 }
+
+/// Generates a [`TerminalFont`] from font files in the BDF or `.hex` formats.
+///
+/// Only glyphs that fit into an 8x8 cell are supported. Paths are relative to the directory
+/// containing the crate's `Cargo.toml`, and sources listed earlier take priority when more than
+/// one contains a character. Unless `builtin` is set to `false`, the fonts used by the built-in
+/// terminal fonts are added as the last sources.
+///
+/// ```ignore
+/// lgba::display::terminal_font! {
+///     /// The font used for the game's menus.
+///     pub MenuFont {
+///         sources: ["fonts/menu.bdf", "fonts/symbols.hex"],
+///         blocks: ["Basic Latin", "Hiragana", "Katakana"],
+///         chars: "★♪←↑→↓",
+///         fallback: '?',
+///     }
+/// }
+/// ```
+///
+/// The following options are supported:
+///
+/// * `sources`: The font files to take glyphs from.
+/// * `builtin`: Whether to use the built-in fonts as fallbacks. Defaults to `true`.
+/// * `blocks`: The names of the Unicode blocks to include, or `"*"` for every block.
+/// * `chars`: A string of additional characters to include.
+/// * `halfwidth_blocks`: The Unicode blocks where narrow glyphs are rendered as half-width.
+/// * `fallback`: The character rendered for characters missing from the font.
+/// * `kanji_max_level`: The most advanced Kanji Kentei level to include, from `"10"` to `"1"`.
+/// * `low_plane_limit`: The number of codepoints that use the faster lookup table.
+///
+/// [`TerminalFont`]: https://docs.rs/lgba/latest/lgba/display/trait.TerminalFont.html
+#[cfg(feature = "terminal_font")]
+#[proc_macro]
+pub fn terminal_font(item: TokenStream) -> TokenStream {
+    lgba_font_attrs::terminal_font_impl(item)
+}
@@ -0,0 +1,44 @@
+use anyhow::{bail, Result};
+use lgba_common::input::{validate_recording, RecordingEntries, BUTTON_NAMES};
+use std::fmt::Write;
+
+/// Decodes a keypad recording into a listing of the frames where the held buttons change.
+///
+/// The recording starts at the given offset into the data, such as when it is stored in a save
+/// file alongside other data.
+pub fn dump_input_recording(data: &[u8], offset: usize) -> Result<String> {
+    let Some(data) = data.get(offset..) else {
+        bail!("Offset {offset:#x} is past the end of the file.");
+    };
+    let Some(header) = validate_recording(data) else {
+        bail!("No valid input recording found at offset {offset:#x}.");
+    };
+
+    let mut out = String::new();
+    writeln!(out, "Seed    : {:#010x}", header.seed)?;
+    writeln!(out, "Frames  : {}", header.frame_count)?;
+    writeln!(out, "Entries : {}", header.entry_count)?;
+    writeln!(out)?;
+
+    // entries that only exist because of long delays do not change the buttons held.
+    let mut last_keys = None;
+    for (frame, keys) in RecordingEntries::new(data, &header) {
+        if last_keys == Some(keys) {
+            continue;
+        }
+        last_keys = Some(keys);
+
+        write!(out, "{frame:>8} :")?;
+        if keys == 0 {
+            write!(out, " (none)")?;
+        }
+        for (i, name) in BUTTON_NAMES.iter().enumerate() {
+            if keys & (1 << i) != 0 {
+                write!(out, " {name}")?;
+            }
+        }
+        writeln!(out)?;
+    }
+    writeln!(out, "{:>8} : (end)", header.frame_count)?;
+    Ok(out)
+}
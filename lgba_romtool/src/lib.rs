@@ -2,6 +2,8 @@
 
 mod build_rom;
 mod compile;
+mod input_recording;
 
 pub use build_rom::*;
 pub use compile::*;
+pub use input_recording::*;
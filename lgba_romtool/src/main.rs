@@ -36,12 +36,24 @@ struct BuildRom {
     data_file: Vec<PathBuf>,
 }
 
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+#[command(propagate_version = true)]
+struct DumpInput {
+    #[arg(short = 'i', long)]
+    input: PathBuf,
+    #[arg(long, default_value_t = 0)]
+    offset: usize,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Compiles a GBA binary from a cargo package
     Compile(Compile),
     /// Converts a GBA binary to a proper GBA ROM
     BuildRom(BuildRom),
+    /// Prints the contents of a keypad recording, such as one stored in a save file
+    DumpInput(DumpInput),
 }
 
 fn execute(cli: Cli) -> Result<()> {
@@ -58,6 +70,10 @@ fn execute(cli: Cli) -> Result<()> {
             rom.print_statistics()?;
             fs::write(v.output, rom.produce_rom()?)?;
         }
+        Commands::DumpInput(v) => {
+            let data = fs::read(v.input)?;
+            print!("{}", lgba_romtool::dump_input_recording(&data, v.offset)?);
+        }
     }
     Ok(())
 }
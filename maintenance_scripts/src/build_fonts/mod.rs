@@ -1,6 +1,4 @@
-mod font_data;
-mod gen_fonts;
-
+use lgba_common::font::{generate_font, FontConfig};
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::quote;
 use std::{
@@ -8,21 +6,6 @@ use std::{
     process::{Command, Stdio},
 };
 
-#[derive(Clone, Debug)]
-pub struct FontConfig {
-    pub name: &'static str,
-    pub description: &'static str,
-    pub low_plane_limit: Option<usize>,
-    pub disable_unscii: Vec<&'static str>,
-    pub disable_misaki: Vec<&'static str>,
-    pub chars: Vec<&'static str>,
-    pub block: Vec<&'static str>,
-    pub allow_halfwidth_blocks: Vec<&'static str>,
-    pub fallback_char: Option<char>,
-    pub kanji_max_level: Option<&'static str>,
-    pub delta: Option<f32>,
-}
-
 fn rustfmt(tokens: TokenStream) -> String {
     let mut command = Command::new("rustfmt")
         .stdin(Stdio::piped())
@@ -34,7 +17,7 @@ fn rustfmt(tokens: TokenStream) -> String {
         .stdin
         .as_mut()
         .unwrap()
-        .write(tokens.to_string().as_bytes())
+        .write_all(tokens.to_string().as_bytes())
         .unwrap();
     command.stdin.take();
 
@@ -43,10 +26,9 @@ fn rustfmt(tokens: TokenStream) -> String {
     String::from_utf8_lossy(&output.stdout).to_string()
 }
 
-pub fn make_terminal_font(target: &str, config: FontConfig) {
-    let ident = Ident::new(config.name, Span::call_site());
-    let doc = config.description;
-    let tokens = gen_fonts::generate_fonts(config, quote! { #ident }).unwrap();
+pub fn make_terminal_font(target: &str, name: &str, doc: &str, config: FontConfig) {
+    let ident = Ident::new(name, Span::call_site());
+    let tokens = generate_font(config, quote! { #ident }, quote! { crate }).unwrap();
     let source = rustfmt(quote! {
         #[doc = #doc]
         pub enum #ident {}
@@ -2,21 +2,22 @@
 
 mod build_fonts;
 
+use lgba_common::font::{FontConfig, FontSource};
+
 fn main() {
     build_fonts::make_terminal_font(
         "lgba/src/display/terminal/gen_font_ascii.rs",
-        build_fonts::FontConfig {
-            name: "TerminalFontAscii",
-            description: "\
-                A terminal font supporting only 7-bit ASCII characters.
+        "TerminalFontAscii",
+        "\
+            A terminal font supporting only 7-bit ASCII characters.
 
-                This font does not require additional storage space in the ROM, as it is used by
-                the panic handler.
-            "
-            .trim(),
+            This font does not require additional storage space in the ROM, as it is used by
+            the panic handler.
+        "
+        .trim(),
+        FontConfig {
+            sources: vec![FontSource::unscii(), FontSource::misaki()],
             low_plane_limit: None,
-            disable_unscii: vec![],
-            disable_misaki: vec![],
             chars: vec![],
             block: vec!["Basic Latin"],
             allow_halfwidth_blocks: vec![],
@@ -27,16 +28,15 @@ fn main() {
     );
     build_fonts::make_terminal_font(
         "lgba/src/display/terminal/gen_font_basic.rs",
-        build_fonts::FontConfig {
-            name: "TerminalFontBasic",
-            description: "\
-                A terminal font supporting many scripts and a reasonable selection of graphics
-                characters for rendering menus.
-            "
-            .trim(),
+        "TerminalFontBasic",
+        "\
+            A terminal font supporting many scripts and a reasonable selection of graphics
+            characters for rendering menus.
+        "
+        .trim(),
+        FontConfig {
+            sources: vec![FontSource::unscii(), FontSource::misaki()],
             low_plane_limit: Some(0x400),
-            disable_unscii: vec![],
-            disable_misaki: vec![],
             chars: vec!["①②③④⑤⑥⑦⑧⑨■□●○★♪⌛⏩⏪←↑→↓↔↕‐‑‒–—―†‡•․…⁇▲▶▼◀▩⌘♀♂─│┌┐└┘├┤┬┴┼╭╮╯╰"],
             block: vec![
                 "Basic Latin",
@@ -62,18 +62,20 @@ fn main() {
     );
     build_fonts::make_terminal_font(
         "lgba/src/display/terminal/gen_font_full.rs",
-        build_fonts::FontConfig {
-            name: "TerminalFontFull",
-            description: "\
-                A terminal font supporting most characters from the source fonts.
+        "TerminalFontFull",
+        "\
+            A terminal font supporting most characters from the source fonts.
 
-                Only kanji on the jouyou list are included. This font is not suited for rendering
-                Chinese or Korean text.
-            "
-            .trim(),
+            Only kanji on the jouyou list are included. This font is not suited for rendering
+            Chinese or Korean text.
+        "
+        .trim(),
+        FontConfig {
+            sources: vec![
+                FontSource::unscii().with_disabled_blocks(&["Halfwidth and Fullwidth Forms"]),
+                FontSource::misaki(),
+            ],
             low_plane_limit: Some(0x400),
-            disable_unscii: vec!["Halfwidth and Fullwidth Forms"],
-            disable_misaki: vec![],
             chars: vec![],
             block: vec![
                 "Arrows",